@@ -0,0 +1,90 @@
+//! PoW Bypass Token
+//!
+//! Issues a signed bypass token for a trusted client (health checker,
+//! internal service, partner). The client sends it in `X-PoW-Bypass` to
+//! skip the PoW session requirement; bypassed requests are logged with the
+//! credential id printed here.
+//!
+//! Signs with `POW_BYPASS_SECRET` / `POW_BYPASS_KEY_ID`, the same keys the
+//! server verifies with. Rotating the secret revokes every issued token.
+//!
+//! ## Usage
+//! ```text
+//! cargo run --bin pow_bypass_token -- [--days 30] [--id <uuid>]
+//! ```
+
+#[path = "../secrets.rs"]
+mod secrets;
+
+use pow::application::bypass::BypassPolicy;
+use std::env;
+use std::time::Duration;
+use uuid::Uuid;
+
+const USAGE: &str = "usage: pow_bypass_token [--days N] [--id UUID]";
+
+/// Token lifetime when `--days` is not given
+const DEFAULT_DAYS: u64 = 30;
+
+#[derive(Debug)]
+struct Args {
+    days: u64,
+    credential_id: Uuid,
+}
+
+fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+
+    let args = parse_args(env::args().skip(1))?;
+
+    let policy = BypassPolicy {
+        token_keys: Some(secrets::load_session_keys("POW_BYPASS")?),
+        ..BypassPolicy::default()
+    };
+    let token = policy
+        .issue_token(
+            args.credential_id,
+            Duration::from_secs(args.days * 24 * 3600),
+        )
+        .ok_or_else(|| anyhow::anyhow!("bypass tokens are disabled"))?;
+
+    eprintln!(
+        "credential {} (valid for {} days)",
+        args.credential_id, args.days
+    );
+    println!("{token}");
+
+    Ok(())
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> anyhow::Result<Args> {
+    let mut parsed = Args {
+        days: DEFAULT_DAYS,
+        credential_id: Uuid::new_v4(),
+    };
+
+    while let Some(flag) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow::anyhow!("{flag} needs a value\n{USAGE}"))
+        };
+        match flag.as_str() {
+            "--days" => match value()?.parse::<u64>() {
+                Ok(days) if (1..=3650).contains(&days) => parsed.days = days,
+                _ => anyhow::bail!("--days must be between 1 and 3650\n{USAGE}"),
+            },
+            "--id" => {
+                parsed.credential_id = value()?
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("--id must be a UUID\n{USAGE}"))?;
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            _ => anyhow::bail!("unknown argument: {flag}\n{USAGE}"),
+        }
+    }
+
+    Ok(parsed)
+}
@@ -0,0 +1,109 @@
+//! PoW Difficulty Report
+//!
+//! Prints solve-time percentiles per work and device class from the
+//! telemetry rollup, and the baseline difficulty that would hit a target
+//! median solve time.
+//!
+//! ## Usage
+//! ```text
+//! cargo run --bin pow_report -- [--hours 24] [--target-ms 2000] [--min-samples 20]
+//! ```
+
+use pow::application::telemetry_report::{TelemetryReportInput, TelemetryReportUseCase};
+use pow::store::PowStore;
+use sqlx::postgres::PgPoolOptions;
+use std::env;
+use std::sync::Arc;
+
+const USAGE: &str = "usage: pow_report [--hours N] [--target-ms N] [--min-samples N]";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+
+    let input = parse_args(env::args().skip(1))?;
+
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set in environment");
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&database_url)
+        .await?;
+
+    let use_case = TelemetryReportUseCase::new(Arc::new(PowStore::new(pool)));
+    let report = use_case.execute(input.clone()).await?;
+
+    println!(
+        "PoW solve times over the last {}h (issue -> submit, server-measured)",
+        input.window_ms / 3_600_000
+    );
+    println!();
+    println!(
+        "{:<10} {:>5} {:<8} {:>8} {:>9} {:>9} {:>9} {:>12}",
+        "algorithm", "bits", "device", "samples", "p50 ms", "p90 ms", "p99 ms", "client H/s"
+    );
+    for s in &report.stats {
+        println!(
+            "{:<10} {:>5} {:<8} {:>8} {:>9} {:>9} {:>9} {:>12}",
+            s.algorithm,
+            s.work_bits,
+            s.device_class.as_str(),
+            s.samples,
+            s.p50_ms,
+            s.p90_ms,
+            s.p99_ms,
+            s.client_hash_rate
+                .map_or_else(|| "-".to_string(), |rate| format!("{rate:.0}")),
+        );
+    }
+    if report.stats.is_empty() {
+        println!("(no solves recorded)");
+    }
+
+    println!();
+    println!(
+        "Suggested baseline difficulty for a {} ms median (groups with >= {} solves):",
+        report.target_median_ms, input.min_samples
+    );
+    for s in &report.suggestions {
+        println!(
+            "  {:<10} {:<8} {:>2} bits  ({} solves)",
+            s.algorithm,
+            s.device_class.map_or("all", |c| c.as_str()),
+            s.difficulty_bits,
+            s.samples
+        );
+    }
+    if report.suggestions.is_empty() {
+        println!("  (not enough data)");
+    }
+
+    Ok(())
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> anyhow::Result<TelemetryReportInput> {
+    let mut input = TelemetryReportInput::default();
+
+    while let Some(flag) = args.next() {
+        let mut value = || -> anyhow::Result<i64> {
+            let value = args
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("{flag} needs a value\n{USAGE}"))?;
+            match value.parse::<i64>() {
+                Ok(n) if n > 0 => Ok(n),
+                _ => anyhow::bail!("{flag} must be a positive integer\n{USAGE}"),
+            }
+        };
+        match flag.as_str() {
+            "--hours" => input.window_ms = value()? * 3_600_000,
+            "--target-ms" => input.target_median_ms = value()?,
+            "--min-samples" => input.min_samples = value()? as u64,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            _ => anyhow::bail!("unknown argument: {flag}\n{USAGE}"),
+        }
+    }
+
+    Ok(input)
+}
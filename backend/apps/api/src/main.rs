@@ -4,17 +4,40 @@
 //! Uses `anyhow` for startup errors, but application-level
 //! errors should use `kernel::error::AppError`.
 
-use auth::{AuthConfig, PgAuthRepository, auth_router};
+mod maintenance;
+mod secrets;
+
+use anyhow::Context;
+use auth::config::AuthRateLimits;
+use auth::middleware::{AuthMiddlewareState, require_admin_session};
+use auth::router::{GuardedRoute, auth_router_guarded};
+use auth::{AuthConfig, PgAuthRepository, auth_admin_router, auth_router};
 use axum::{
     Router, http,
-    http::{Method, header},
+    http::{HeaderName, Method, header},
+    middleware::{from_fn, from_fn_with_state},
 };
 use base64::Engine;
 use base64::engine::general_purpose;
-use pow::{PowConfig, pow_router, store::PowStore};
+use platform::client::{ClientNetworkKey, ForwardingHeader, TrustedProxies, resolve_client_ip};
+use platform::csrf::{CsrfConfig, csrf_protect};
+use platform::net::parse_cidr_list;
+use platform::rate_limit::{PgRateLimitStore, RateLimitBackend};
+use platform::rate_limit_layer::{
+    RATE_LIMIT_LIMIT_HEADER, RATE_LIMIT_REMAINING_HEADER, RATE_LIMIT_RESET_HEADER,
+};
+use pow::config::{ActionPolicy, ChallengeMode};
+use pow::domain::algorithm::{ARGON2ID_DEFAULT_DIFFICULTY_BITS, Argon2Params, PowAlgorithm};
+use pow::domain::difficulty::AdaptiveDifficulty;
+use pow::domain::value_objects::{Difficulty, PowAction};
+use pow::middleware::{PowActionGuardState, require_pow_action};
+use pow::{PowConfig, pow_admin_router, pow_router, store::PowStore};
+use secrets::{decode_secret, load_session_keys};
 use sqlx::postgres::PgPoolOptions;
 use std::env;
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
 use tower_http::cors::{AllowHeaders, AllowMethods, CorsLayer};
 use tower_http::trace::TraceLayer;
@@ -58,57 +81,20 @@ async fn main() -> anyhow::Result<()> {
 
     tracing::info!("Migrations completed");
 
-    // Startup cleanup: remove expired PoW data
-    // Errors here should not prevent server startup
-    let pow_store_for_cleanup = PowStore::new(pool.clone());
-    match pow_store_for_cleanup.cleanup_expired().await {
-        Ok((challenges, sessions, rate_limits)) => {
-            tracing::info!(
-                challenges_deleted = challenges,
-                sessions_deleted = sessions,
-                rate_limits_deleted = rate_limits,
-                "POW session cleanup completed"
-            );
-        }
-        Err(e) => {
-            tracing::warn!(
-                error = %e,
-                "POW session cleanup failed, continuing anyway"
-            );
-        }
-    }
-
-    let auth_store_for_cleanup = PgAuthRepository::new(pool.clone());
-    match auth_store_for_cleanup.cleanup_expired().await {
-        Ok(sessions) => {
-            tracing::info!(
-                sessions_deleted = sessions,
-                "Auth session cleanup completed"
-            );
-        }
-        Err(e) => {
-            tracing::warn!(
-                error = %e,
-                "Auth session cleanup failed, continuing anyway"
-            );
-        }
-    }
+    // Periodic cleanup of expired PoW and auth data
+    let maintenance_config = maintenance::MaintenanceConfig::from_env()?;
+    maintenance::spawn(
+        pool.clone(),
+        PowStore::new(pool.clone()),
+        PgAuthRepository::new(pool.clone()),
+        maintenance_config,
+    );
 
     // Auth configuration
-    let auth_config = if cfg!(debug_assertions) {
+    let mut auth_config = if cfg!(debug_assertions) {
         AuthConfig::development()
     } else {
-        let secret_b64 =
-            env::var("AUTH_SESSION_SECRET").expect("AUTH_SESSION_SECRET must be set in production");
-        let secret_bytes = Engine::decode(&general_purpose::STANDARD, &secret_b64)?;
-        if secret_bytes.len() != 32 {
-            anyhow::bail!(
-                "AUTH_SESSION_SECRET must decode to exactly 32 bytes (got {} bytes)",
-                secret_bytes.len()
-            );
-        }
-        let mut secret = [0u8; 32];
-        secret.copy_from_slice(&secret_bytes);
+        let session_keys = load_session_keys("AUTH_SESSION")?;
 
         // Optional password pepper (base64). If set, it must decode to non-empty bytes.
         let pepper = match env::var("AUTH_PASSWORD_PEPPER_B64") {
@@ -123,37 +109,153 @@ async fn main() -> anyhow::Result<()> {
         };
 
         AuthConfig {
-            session_secret: secret,
+            session_keys,
             password_pepper: pepper,
             ..AuthConfig::default()
         }
     };
 
     // PoW configuration
-    let pow_config = if cfg!(debug_assertions) {
+    let mut pow_config = if cfg!(debug_assertions) {
         PowConfig::development()
     } else {
-        // In production, load secret from environment
-        let secret_b64 =
-            env::var("POW_SESSION_SECRET").expect("POW_SESSION_SECRET must be set in production");
-        let secret_bytes = Engine::decode(&general_purpose::STANDARD, &secret_b64)?;
-        if secret_bytes.len() != 32 {
-            anyhow::bail!(
-                "POW_SESSION_SECRET must decode to exactly 32 bytes (got {} bytes)",
-                secret_bytes.len()
-            );
-        }
-        let mut secret = [0u8; 32];
-        secret.copy_from_slice(&secret_bytes);
+        // In production, load secrets from environment
         PowConfig {
-            session_secret: secret,
+            session_keys: load_session_keys("POW_SESSION")?,
             ..PowConfig::default()
         }
     };
 
-    let pow_store = PowStore::new(pool.clone());
+    // PoW algorithm (sha256 by default)
+    if let Ok(algorithm) = env::var("POW_ALGORITHM") {
+        match algorithm.trim() {
+            "" | "sha256" => {}
+            "argon2id" => {
+                pow_config.algorithm = PowAlgorithm::Argon2id(Argon2Params::default());
+                pow_config.difficulty_bits = ARGON2ID_DEFAULT_DIFFICULTY_BITS;
+            }
+            other => anyhow::bail!("Unsupported POW_ALGORITHM: {other}"),
+        }
+    }
+
+    // PoW challenge storage (stored by default)
+    if let Ok(mode) = env::var("POW_CHALLENGE_MODE") {
+        pow_config.challenge_mode = match mode.trim() {
+            "" | "stored" => ChallengeMode::Stored,
+            "stateless" => ChallengeMode::Stateless,
+            other => anyhow::bail!("Unsupported POW_CHALLENGE_MODE: {other}"),
+        };
+    }
+
+    // Requests a PoW session may authorize (unlimited by default)
+    if let Ok(budget) = env::var("POW_SESSION_REQUEST_BUDGET")
+        && !budget.trim().is_empty()
+    {
+        pow_config.session_request_budget = Some(budget.trim().parse()?);
+    }
+
+    // Difficulty policy: "fixed" (default) or "adaptive"
+    match env::var("POW_DIFFICULTY_POLICY").as_deref().map(str::trim) {
+        Err(_) | Ok("" | "fixed") => {}
+        Ok("adaptive") => pow_config.difficulty_policy = Arc::new(load_adaptive_difficulty()?),
+        Ok(other) => anyhow::bail!("Unsupported POW_DIFFICULTY_POLICY: {other}"),
+    }
+    if let Ok(window) = env::var("POW_DIFFICULTY_WINDOW_SECS")
+        && !window.trim().is_empty()
+    {
+        pow_config.difficulty_window = Duration::from_secs(window.trim().parse()?);
+    }
+
+    // Networks clients are grouped into for limits and bans
+    pow_config.client_network = load_client_network_key()?;
+
+    // Trusted clients that skip the PoW session requirement
+    if let Ok(networks) = env::var("POW_BYPASS_CIDRS") {
+        pow_config.bypass.networks = parse_cidr_list(&networks)?;
+    }
+    if let Ok(roles) = env::var("POW_BYPASS_ROLES") {
+        pow_config.bypass.roles = roles
+            .split(',')
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+    if env::var("POW_BYPASS_SECRET").is_ok_and(|v| !v.trim().is_empty()) {
+        let token_keys = load_session_keys("POW_BYPASS")?;
+        // A shared secret would let every PoW session token double as a bypass token
+        if token_keys.shares_secret_with(&pow_config.session_keys) {
+            anyhow::bail!("POW_BYPASS_SECRET must differ from the PoW session secrets");
+        }
+        pow_config.bypass.token_keys = Some(token_keys);
+    }
+
+    // Action-scoped PoW proofs (difficulty relative to the baseline)
+    for (action, policy) in load_action_policies(pow_config.difficulty_bits)? {
+        pow_config = pow_config.with_action(action, policy);
+    }
+
+    // Rate limit state: "postgres" (shared by all instances) or "memory" (per instance)
+    let rate_limits = match env::var("RATE_LIMIT_STORE").as_deref().map(str::trim) {
+        Err(_) | Ok("" | "postgres") => {
+            RateLimitBackend::Postgres(PgRateLimitStore::new(pool.clone()))
+        }
+        Ok("memory") => RateLimitBackend::memory(),
+        Ok(other) => anyhow::bail!("Unsupported RATE_LIMIT_STORE: {other}"),
+    };
+
+    auth_config.rate_limits = Some(AuthRateLimits::new(rate_limits.clone()));
+
+    let pow_store = PowStore::new(pool.clone())
+        .with_rate_limits(rate_limits)
+        .with_client_network(pow_config.client_network);
     let auth_store = PgAuthRepository::new(pool.clone());
 
+    // Admin routes require an admin session with a recent re-authentication
+    let admin_auth = AuthMiddlewareState {
+        repo: Arc::new(auth_store.clone()),
+        config: Arc::new(auth_config.clone()),
+    };
+    let pow_admin = {
+        let admin_auth = admin_auth.clone();
+        pow_admin_router(pow_store.clone(), pow_config.clone()).route_layer(from_fn(
+            move |req, next| require_admin_session(admin_auth.clone(), req, next),
+        ))
+    };
+    let auth_admin = auth_admin_router(auth_store.clone(), auth_config.clone()).route_layer(
+        from_fn(move |req, next| require_admin_session(admin_auth.clone(), req, next)),
+    );
+
+    // Require a PoW session or an action-bound proof for sign-up / sign-in
+    // (AUTH_REQUIRE_POW=false to disable)
+    let auth_require_pow = env::var("AUTH_REQUIRE_POW")
+        .map(|v| v.trim() != "false")
+        .unwrap_or(true);
+    let auth = if auth_require_pow {
+        for route in [GuardedRoute::SignUp, GuardedRoute::SignIn] {
+            let configured = PowAction::new(route.as_str())
+                .is_some_and(|action| pow_config.action_policy(&action).is_some());
+            if !configured {
+                anyhow::bail!(
+                    "POW_ACTIONS must configure \"{}\" while AUTH_REQUIRE_POW is enabled",
+                    route.as_str()
+                );
+            }
+        }
+        let guard_repo = Arc::new(pow_store.clone());
+        let guard_config = Arc::new(pow_config.clone());
+        auth_router_guarded(auth_store, auth_config, |route| {
+            let action = PowAction::new(route.as_str()).expect("valid action");
+            from_fn_with_state(
+                PowActionGuardState::new(guard_repo.clone(), guard_config.clone(), action)
+                    .or_session(),
+                require_pow_action::<PowStore>,
+            )
+        })
+    } else {
+        auth_router(auth_store, auth_config)
+    };
+
     // CORS configuration
     let frontend_origins = env::var("FRONTEND_ORIGINS")
         .unwrap_or_else(|_| "http://localhost:40922,http://127.0.0.1:40922".to_string());
@@ -163,6 +265,31 @@ async fn main() -> anyhow::Result<()> {
         .filter_map(|origin| origin.trim().parse().ok())
         .collect();
 
+    // CSRF protection for cookie-authenticated routers
+    // Tokens are signed, so every instance must share the secret across restarts.
+    let csrf_config = match env::var("CSRF_SECRET") {
+        Ok(secret) if !secret.trim().is_empty() => {
+            CsrfConfig::new(decode_secret("CSRF_SECRET", secret.trim())?)
+        }
+        _ if cfg!(debug_assertions) => CsrfConfig::with_random_secret(),
+        _ => anyhow::bail!("CSRF_SECRET must be set in production"),
+    };
+    let trusted_origins: Vec<&str> = frontend_origins
+        .split(',')
+        .map(str::trim)
+        .filter(|o| !o.is_empty())
+        .collect();
+    if trusted_origins.is_empty() {
+        tracing::warn!("FRONTEND_ORIGINS is empty; only same-origin requests pass CSRF checks");
+    }
+    let csrf_config = Arc::new(
+        csrf_config
+            .with_trusted_origins(trusted_origins)
+            .with_cookie_secure(!cfg!(debug_assertions)),
+    );
+    let csrf_header = HeaderName::from_static("x-csrf-token");
+    let pow_proof_header = HeaderName::from_static("x-pow-proof");
+
     let cors = CorsLayer::new()
         .allow_origin(allowed_origins)
         .allow_methods(AllowMethods::list([
@@ -174,13 +301,62 @@ async fn main() -> anyhow::Result<()> {
             header::CONTENT_TYPE,
             header::AUTHORIZATION,
             header::ACCEPT,
+            csrf_header.clone(),
+            pow_proof_header,
         ]))
+        .expose_headers([
+            csrf_header,
+            HeaderName::from_static("x-pow-required"),
+            HeaderName::from_static("x-pow-action"),
+            HeaderName::from_static("x-pow-challenge"),
+            HeaderName::from_static("x-pow-remaining"),
+            HeaderName::from_static(RATE_LIMIT_LIMIT_HEADER),
+            HeaderName::from_static(RATE_LIMIT_REMAINING_HEADER),
+            HeaderName::from_static(RATE_LIMIT_RESET_HEADER),
+            header::RETRY_AFTER,
+        ])
         .allow_credentials(true);
 
+    // Reverse proxies whose forwarding headers are believed (none by default)
+    let mut trusted_proxies = TrustedProxies::default();
+    if let Ok(networks) = env::var("TRUSTED_PROXY_CIDRS") {
+        trusted_proxies.networks = parse_cidr_list(&networks)?;
+    }
+    if let Ok(hops) = env::var("TRUSTED_PROXY_HOPS")
+        && !hops.trim().is_empty()
+    {
+        trusted_proxies.hops = hops.trim().parse()?;
+    }
+    if let Ok(name) = env::var("TRUSTED_PROXY_HEADER")
+        && !name.trim().is_empty()
+    {
+        trusted_proxies.header = ForwardingHeader::from_name(&name)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported TRUSTED_PROXY_HEADER: {name}"))?;
+    }
+
     // Build router
     let app = Router::new()
-        .nest("/api/pow", pow_router(pow_store, pow_config))
-        .nest("/api/auth", auth_router(auth_store, auth_config))
+        .nest(
+            "/api/pow",
+            pow_router(pow_store, pow_config)
+                .layer(from_fn_with_state(csrf_config.clone(), csrf_protect)),
+        )
+        .nest(
+            "/api/admin/pow",
+            pow_admin.layer(from_fn_with_state(csrf_config.clone(), csrf_protect)),
+        )
+        .nest(
+            "/api/admin/auth",
+            auth_admin.layer(from_fn_with_state(csrf_config.clone(), csrf_protect)),
+        )
+        .nest(
+            "/api/auth",
+            auth.layer(from_fn_with_state(csrf_config, csrf_protect)),
+        )
+        .layer(from_fn_with_state(
+            Arc::new(trusted_proxies),
+            resolve_client_ip,
+        ))
         .layer(TraceLayer::new_for_http())
         .layer(cors);
 
@@ -197,3 +373,106 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Load the client network prefix lengths from environment
+///
+/// - `CLIENT_NETWORK_V4_PREFIX`: IPv4 prefix length (default: 24)
+/// - `CLIENT_NETWORK_V6_PREFIX`: IPv6 prefix length (default: 48)
+fn load_client_network_key() -> anyhow::Result<ClientNetworkKey> {
+    let default = ClientNetworkKey::default();
+    let prefix_len = |name: &str, default: u8| -> anyhow::Result<u8> {
+        match env::var(name) {
+            Ok(v) if !v.trim().is_empty() => Ok(v.trim().parse()?),
+            _ => Ok(default),
+        }
+    };
+    let v4 = prefix_len("CLIENT_NETWORK_V4_PREFIX", default.v4_prefix_len())?;
+    let v6 = prefix_len("CLIENT_NETWORK_V6_PREFIX", default.v6_prefix_len())?;
+    ClientNetworkKey::new(v4, v6)
+        .ok_or_else(|| anyhow::anyhow!("Invalid client network prefix lengths: /{v4}, /{v6}"))
+}
+
+/// Action policies used when `POW_ACTIONS` is not set
+const DEFAULT_POW_ACTIONS: &str = "signup=+2,signin=0,comment:create=-2:payload";
+
+/// Load the action-scoped PoW policies from environment
+///
+/// `POW_ACTIONS`: comma-separated `action=offset[:payload]` entries, where
+/// `offset` is the difficulty relative to the baseline in bits and
+/// `:payload` requires the proof to be bound to the request body
+/// (default: `signup=+2,signin=0,comment:create=-2:payload`).
+fn load_action_policies(baseline: u8) -> anyhow::Result<Vec<(PowAction, ActionPolicy)>> {
+    let actions = env::var("POW_ACTIONS")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_POW_ACTIONS.to_string());
+
+    actions
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let Some((name, policy)) = entry.split_once('=') else {
+                anyhow::bail!("POW_ACTIONS entries must be formatted as action=offset[:payload]");
+            };
+            let action = PowAction::new(name.trim())
+                .ok_or_else(|| anyhow::anyhow!("Invalid PoW action in POW_ACTIONS: {name}"))?;
+            let (offset, require_payload_hash) = match policy.trim().split_once(':') {
+                Some((offset, "payload")) => (offset, true),
+                Some((_, flag)) => anyhow::bail!("Unsupported POW_ACTIONS flag: {flag}"),
+                None => (policy.trim(), false),
+            };
+            let offset: i16 = offset
+                .parse()
+                .with_context(|| format!("Invalid difficulty offset for PoW action {action}"))?;
+            let bits = (i16::from(baseline) + offset).clamp(1, i16::from(Difficulty::MAX)) as u8;
+            Ok((
+                action,
+                ActionPolicy {
+                    require_payload_hash,
+                    ..ActionPolicy::new(bits)
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Load the adaptive difficulty thresholds from environment
+///
+/// Unset variables keep the defaults; a threshold of 0 disables its signal.
+///
+/// - `POW_ADAPTIVE_GLOBAL_THRESHOLD`: challenges issued by this instance
+/// - `POW_ADAPTIVE_CLIENT_THRESHOLD`: challenges issued to one fingerprint
+/// - `POW_ADAPTIVE_NETWORK_THRESHOLD`: challenges issued to one client network
+/// - `POW_ADAPTIVE_INVALID_NONCE_THRESHOLD`: invalid nonces from one fingerprint
+/// - `POW_ADAPTIVE_SESSION_THRESHOLD`: sessions created by one fingerprint
+/// - `POW_ADAPTIVE_MAX_EXTRA_BITS`: maximum bits added to the baseline
+fn load_adaptive_difficulty() -> anyhow::Result<AdaptiveDifficulty> {
+    fn var<T: std::str::FromStr>(name: &str, default: T) -> anyhow::Result<T>
+    where
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        match env::var(name) {
+            Ok(v) if !v.trim().is_empty() => {
+                v.trim().parse().with_context(|| format!("Invalid {name}"))
+            }
+            _ => Ok(default),
+        }
+    }
+
+    let default = AdaptiveDifficulty::default();
+    Ok(AdaptiveDifficulty {
+        global_threshold: var("POW_ADAPTIVE_GLOBAL_THRESHOLD", default.global_threshold)?,
+        client_threshold: var("POW_ADAPTIVE_CLIENT_THRESHOLD", default.client_threshold)?,
+        network_threshold: var("POW_ADAPTIVE_NETWORK_THRESHOLD", default.network_threshold)?,
+        invalid_nonce_threshold: var(
+            "POW_ADAPTIVE_INVALID_NONCE_THRESHOLD",
+            default.invalid_nonce_threshold,
+        )?,
+        session_churn_threshold: var(
+            "POW_ADAPTIVE_SESSION_THRESHOLD",
+            default.session_churn_threshold,
+        )?,
+        max_extra_bits: var("POW_ADAPTIVE_MAX_EXTRA_BITS", default.max_extra_bits)?,
+    })
+}
@@ -0,0 +1,247 @@
+//! Background Maintenance
+//!
+//! Periodically removes expired PoW and auth data.
+//!
+//! Each job holds a Postgres advisory lock (keyed by its name) while it
+//! runs, so with several API instances only one of them runs a job at a
+//! time; the others skip that round. Runs are spread with random jitter so
+//! instances started together do not contend on every tick.
+
+use auth::PgAuthRepository;
+use pow::store::PowStore;
+use rand::Rng;
+use sqlx::PgPool;
+use std::env;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// Maintenance configuration
+#[derive(Debug, Clone)]
+pub struct MaintenanceConfig {
+    /// Run maintenance jobs at all
+    pub enabled: bool,
+    /// Time between PoW cleanups
+    pub pow_cleanup_interval: Duration,
+    /// Time between auth cleanups (expired sessions and lockouts)
+    pub auth_cleanup_interval: Duration,
+    /// Upper bound of the random delay added to every interval
+    pub jitter: Duration,
+    /// Rows deleted per statement
+    pub batch_size: i64,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            pow_cleanup_interval: Duration::from_secs(300),
+            auth_cleanup_interval: Duration::from_secs(300),
+            jitter: Duration::from_secs(30),
+            batch_size: 1_000,
+        }
+    }
+}
+
+impl MaintenanceConfig {
+    /// Load from environment, falling back to the defaults
+    ///
+    /// - `MAINTENANCE_ENABLED`: `false` disables all jobs
+    /// - `MAINTENANCE_POW_INTERVAL_SECS`, `MAINTENANCE_AUTH_INTERVAL_SECS`
+    /// - `MAINTENANCE_JITTER_SECS`
+    /// - `MAINTENANCE_BATCH_SIZE`
+    pub fn from_env() -> anyhow::Result<Self> {
+        let mut config = Self::default();
+
+        if let Ok(enabled) = env::var("MAINTENANCE_ENABLED") {
+            config.enabled = enabled.trim() != "false";
+        }
+        if let Some(secs) = env_number("MAINTENANCE_POW_INTERVAL_SECS")? {
+            config.pow_cleanup_interval = Duration::from_secs(secs.max(1));
+        }
+        if let Some(secs) = env_number("MAINTENANCE_AUTH_INTERVAL_SECS")? {
+            config.auth_cleanup_interval = Duration::from_secs(secs.max(1));
+        }
+        if let Some(secs) = env_number("MAINTENANCE_JITTER_SECS")? {
+            config.jitter = Duration::from_secs(secs);
+        }
+        if let Some(rows) = env_number("MAINTENANCE_BATCH_SIZE")? {
+            config.batch_size = rows.clamp(1, i64::MAX as u64) as i64;
+        }
+
+        Ok(config)
+    }
+}
+
+fn env_number(name: &str) -> anyhow::Result<Option<u64>> {
+    match env::var(name) {
+        Ok(v) if !v.trim().is_empty() => Ok(Some(v.trim().parse()?)),
+        _ => Ok(None),
+    }
+}
+
+type JobFuture = Pin<Box<dyn Future<Output = anyhow::Result<u64>> + Send>>;
+
+/// A periodic job; `run` returns the number of rows it touched
+struct Job {
+    name: &'static str,
+    interval: Duration,
+    run: Box<dyn Fn() -> JobFuture + Send + Sync>,
+}
+
+/// Outcome of one scheduled run
+enum RunOutcome {
+    Completed(u64),
+    /// Another instance holds the job's lock
+    Skipped,
+    Failed(anyhow::Error),
+}
+
+/// Start the maintenance jobs on the runtime
+///
+/// Returns no handles when maintenance is disabled.
+pub fn spawn(
+    pool: PgPool,
+    pow_store: PowStore,
+    auth_store: PgAuthRepository,
+    config: MaintenanceConfig,
+) -> Vec<JoinHandle<()>> {
+    if !config.enabled {
+        tracing::info!("Background maintenance disabled");
+        return Vec::new();
+    }
+
+    let batch_size = config.batch_size;
+    let jobs = [
+        Job {
+            name: "pow_cleanup",
+            interval: config.pow_cleanup_interval,
+            run: Box::new(move || {
+                let store = pow_store.clone();
+                Box::pin(async move {
+                    let (challenges, sessions, rate_limits) =
+                        store.cleanup_expired_batched(batch_size).await?;
+                    Ok(challenges + sessions + rate_limits)
+                })
+            }),
+        },
+        Job {
+            name: "auth_cleanup",
+            interval: config.auth_cleanup_interval,
+            run: Box::new(move || {
+                let store = auth_store.clone();
+                Box::pin(async move {
+                    let sessions = store.cleanup_expired_batched(batch_size).await?;
+                    let lockouts = store.reset_expired_lockouts().await?;
+                    Ok(sessions + lockouts)
+                })
+            }),
+        },
+    ];
+
+    jobs.into_iter()
+        .map(|job| {
+            tracing::info!(
+                job = job.name,
+                interval_secs = job.interval.as_secs(),
+                jitter_secs = config.jitter.as_secs(),
+                batch_size,
+                "Scheduled maintenance job"
+            );
+            tokio::spawn(run_periodically(pool.clone(), job, config.jitter))
+        })
+        .collect()
+}
+
+async fn run_periodically(pool: PgPool, job: Job, jitter: Duration) {
+    let mut runs: u64 = 0;
+    let mut failures: u64 = 0;
+    let mut skipped: u64 = 0;
+
+    // The first run replaces the old cleanup at startup
+    tokio::time::sleep(random_jitter(jitter)).await;
+
+    loop {
+        let started = Instant::now();
+        let outcome = run_locked(&pool, &job).await;
+        let duration_ms = started.elapsed().as_millis() as u64;
+
+        match outcome {
+            RunOutcome::Completed(rows) => {
+                runs += 1;
+                tracing::info!(
+                    job = job.name,
+                    rows,
+                    duration_ms,
+                    runs,
+                    failures,
+                    skipped,
+                    "Maintenance job completed"
+                );
+            }
+            RunOutcome::Skipped => {
+                skipped += 1;
+                tracing::debug!(
+                    job = job.name,
+                    skipped,
+                    "Maintenance job running on another instance, skipped"
+                );
+            }
+            RunOutcome::Failed(e) => {
+                runs += 1;
+                failures += 1;
+                tracing::warn!(
+                    job = job.name,
+                    error = %e,
+                    duration_ms,
+                    runs,
+                    failures,
+                    skipped,
+                    "Maintenance job failed"
+                );
+            }
+        }
+
+        tokio::time::sleep(job.interval + random_jitter(jitter)).await;
+    }
+}
+
+/// Run `job` while holding its advisory lock
+///
+/// The lock is transaction-scoped, so it is released when the transaction
+/// ends even if the connection is dropped mid-run.
+async fn run_locked(pool: &PgPool, job: &Job) -> RunOutcome {
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return RunOutcome::Failed(e.into()),
+    };
+
+    let locked = sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_xact_lock(hashtext($1))")
+        .bind(format!("maintenance:{}", job.name))
+        .fetch_one(&mut *tx)
+        .await;
+    match locked {
+        Ok(true) => {}
+        Ok(false) => return RunOutcome::Skipped,
+        Err(e) => return RunOutcome::Failed(e.into()),
+    }
+
+    let outcome = match (job.run)().await {
+        Ok(rows) => RunOutcome::Completed(rows),
+        Err(e) => RunOutcome::Failed(e),
+    };
+
+    if let Err(e) = tx.commit().await {
+        tracing::warn!(job = job.name, error = %e, "Failed to release maintenance lock");
+    }
+
+    outcome
+}
+
+fn random_jitter(max: Duration) -> Duration {
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+    Duration::from_millis(rand::thread_rng().gen_range(0..=max.as_millis() as u64))
+}
@@ -0,0 +1,61 @@
+//! Secrets
+//!
+//! Loads signing secrets from environment (shared by the server and the
+//! admin binaries).
+
+use anyhow::Context;
+use base64::Engine;
+use base64::engine::general_purpose;
+use platform::session_token::{SessionKey, SessionKeyRing};
+use std::env;
+
+/// Load session token keys from environment
+///
+/// - `{prefix}_SECRET`: current signing secret (base64, 32 bytes)
+/// - `{prefix}_KEY_ID`: id of the current secret (default: 1)
+/// - `{prefix}_PREVIOUS_KEYS`: retired secrets still accepted during
+///   rotation, as comma-separated `id:base64` pairs
+pub fn load_session_keys(prefix: &str) -> anyhow::Result<SessionKeyRing> {
+    let secret_var = format!("{prefix}_SECRET");
+    let secret_b64 =
+        env::var(&secret_var).with_context(|| format!("{secret_var} must be set in production"))?;
+
+    let key_id = match env::var(format!("{prefix}_KEY_ID")) {
+        Ok(v) if !v.trim().is_empty() => v.trim().parse()?,
+        _ => 1,
+    };
+
+    let mut keys = SessionKeyRing::new(SessionKey::new(
+        key_id,
+        decode_secret(&secret_var, secret_b64.trim())?,
+    ));
+
+    let previous_var = format!("{prefix}_PREVIOUS_KEYS");
+    if let Ok(previous) = env::var(&previous_var) {
+        for entry in previous.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((id, secret_b64)) = entry.split_once(':') else {
+                anyhow::bail!("{previous_var} entries must be formatted as id:base64");
+            };
+            keys = keys.with_previous(SessionKey::new(
+                id.trim().parse()?,
+                decode_secret(&previous_var, secret_b64.trim())?,
+            ));
+        }
+    }
+
+    Ok(keys)
+}
+
+/// Decode a base64 secret that must be exactly 32 bytes
+pub fn decode_secret(name: &str, secret_b64: &str) -> anyhow::Result<[u8; 32]> {
+    let secret_bytes = Engine::decode(&general_purpose::STANDARD, secret_b64)?;
+    if secret_bytes.len() != 32 {
+        anyhow::bail!(
+            "{name} must decode to exactly 32 bytes (got {} bytes)",
+            secret_bytes.len()
+        );
+    }
+    let mut secret = [0u8; 32];
+    secret.copy_from_slice(&secret_bytes);
+    Ok(secret)
+}
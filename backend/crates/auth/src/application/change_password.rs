@@ -0,0 +1,79 @@
+//! Change Password Use Case
+//!
+//! Replaces the password of a signed-in user.
+//! Callers must enforce a recent re-authentication (step-up) beforehand.
+
+use std::sync::Arc;
+
+use crate::application::config::AuthConfig;
+use crate::domain::entity::auth_session::AuthSession;
+use crate::domain::repository::{AuthRepository, AuthSessionRepository};
+use crate::domain::value_object::user_password::{RawPassword, UserPassword};
+use crate::error::{AuthError, AuthResult};
+
+/// Change password input
+pub struct ChangePasswordInput {
+    pub new_password: String,
+}
+
+/// Change password use case
+pub struct ChangePasswordUseCase<A, S>
+where
+    A: AuthRepository,
+    S: AuthSessionRepository,
+{
+    auth_repo: Arc<A>,
+    session_repo: Arc<S>,
+    config: Arc<AuthConfig>,
+}
+
+impl<A, S> ChangePasswordUseCase<A, S>
+where
+    A: AuthRepository,
+    S: AuthSessionRepository,
+{
+    pub fn new(auth_repo: Arc<A>, session_repo: Arc<S>, config: Arc<AuthConfig>) -> Self {
+        Self {
+            auth_repo,
+            session_repo,
+            config,
+        }
+    }
+
+    /// Change password and revoke all other sessions of the user
+    ///
+    /// Returns the number of revoked sessions. The current session is kept;
+    /// the caller is expected to rotate its ID.
+    pub async fn execute(
+        &self,
+        session: &AuthSession,
+        input: ChangePasswordInput,
+    ) -> AuthResult<u64> {
+        let raw_password = RawPassword::new(input.new_password)
+            .map_err(|e| AuthError::PasswordValidation(e.to_string()))?;
+        let password_hash = UserPassword::from_raw(&raw_password, self.config.pepper())
+            .map_err(|e| AuthError::Internal(e.to_string()))?;
+
+        let mut auth = self
+            .auth_repo
+            .find_by_user_id(&session.user_id)
+            .await?
+            .ok_or(AuthError::Internal("Auth not found".to_string()))?;
+
+        auth.update_password(password_hash);
+        self.auth_repo.update(&auth).await?;
+
+        let revoked = self
+            .session_repo
+            .delete_all_for_user(&session.user_id, Some(session.session_id))
+            .await?;
+
+        tracing::info!(
+            user_id = %session.user_id,
+            sessions_revoked = revoked,
+            "Password changed"
+        );
+
+        Ok(revoked)
+    }
+}
@@ -0,0 +1,86 @@
+//! Change Role Use Case
+//!
+//! Changes a user's role and keeps existing sessions consistent with it.
+
+use std::sync::Arc;
+
+use crate::domain::repository::{AuthSessionRepository, UserRepository};
+use crate::domain::value_object::{public_id::PublicId, user_role::UserRole};
+use crate::error::{AuthError, AuthResult};
+
+/// Change role use case
+pub struct ChangeRoleUseCase<U, S>
+where
+    U: UserRepository,
+    S: AuthSessionRepository,
+{
+    user_repo: Arc<U>,
+    session_repo: Arc<S>,
+}
+
+impl<U, S> ChangeRoleUseCase<U, S>
+where
+    U: UserRepository,
+    S: AuthSessionRepository,
+{
+    pub fn new(user_repo: Arc<U>, session_repo: Arc<S>) -> Self {
+        Self {
+            user_repo,
+            session_repo,
+        }
+    }
+
+    /// Change the role of a user
+    ///
+    /// - Super admins are managed out of band: this path can neither
+    ///   grant nor revoke the role.
+    /// - Elevation revokes every session: the user must sign in again,
+    ///   which enforces 2FA for moderator+ and yields a fresh session ID.
+    /// - Demotion replaces the role snapshot in all sessions immediately,
+    ///   so stale elevated sessions cannot linger.
+    pub async fn execute(&self, public_id: &PublicId, new_role: UserRole) -> AuthResult<()> {
+        let mut user = self
+            .user_repo
+            .find_by_public_id(public_id)
+            .await?
+            .ok_or(AuthError::UserNotFound)?;
+
+        let old_role = user.user_role;
+        if old_role == new_role {
+            return Ok(());
+        }
+
+        if old_role.is_super_admin() || new_role.is_super_admin() {
+            return Err(AuthError::RoleChangeForbidden);
+        }
+
+        user.set_role(new_role);
+        self.user_repo.update(&user).await?;
+        let user_id = &user.user_id;
+
+        if new_role.id() > old_role.id() {
+            let revoked = self.session_repo.delete_all_for_user(user_id, None).await?;
+            tracing::info!(
+                user_id = %user_id,
+                old_role = %old_role,
+                new_role = %new_role,
+                sessions_revoked = revoked,
+                "User role elevated"
+            );
+        } else {
+            let updated = self
+                .session_repo
+                .update_role_for_user(user_id, new_role)
+                .await?;
+            tracing::info!(
+                user_id = %user_id,
+                old_role = %old_role,
+                new_role = %new_role,
+                sessions_updated = updated,
+                "User role lowered"
+            );
+        }
+
+        Ok(())
+    }
+}
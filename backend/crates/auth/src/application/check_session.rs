@@ -4,6 +4,8 @@
 
 use std::sync::Arc;
 
+use chrono::Utc;
+
 use crate::application::config::AuthConfig;
 use crate::domain::entity::auth_session::AuthSession;
 use crate::domain::repository::AuthSessionRepository;
@@ -15,6 +17,10 @@ pub struct SessionInfoOutput {
     pub public_id: String,
     pub user_role: String,
     pub expires_at_ms: i64,
+    /// Step-up is satisfied until this time (None if never re-authenticated)
+    pub reauth_expires_at_ms: Option<i64>,
+    /// Whether the session expiration was extended (cookie must be re-issued)
+    pub renewed: bool,
 }
 
 /// Check session use case
@@ -43,12 +49,17 @@ where
         session_token: &str,
         fingerprint_hash: &[u8],
     ) -> AuthResult<SessionInfoOutput> {
-        let session = self.get_session(session_token, fingerprint_hash).await?;
+        let (session, renewed) = self
+            .get_session_with_renewal(session_token, fingerprint_hash)
+            .await?;
+        let reauth_window = self.reauth_window()?;
 
         Ok(SessionInfoOutput {
             public_id: session.public_id.to_string(),
             user_role: session.user_role.code().to_string(),
             expires_at_ms: session.expires_at_ms,
+            reauth_expires_at_ms: session.reauth_expires_at_ms(reauth_window),
+            renewed,
         })
     }
 
@@ -65,7 +76,22 @@ where
         session_token: &str,
         fingerprint_hash: &[u8],
     ) -> AuthResult<AuthSession> {
-        let session_id = self.parse_session_token(session_token)?;
+        self.get_session_with_renewal(session_token, fingerprint_hash)
+            .await
+            .map(|(session, _)| session)
+    }
+
+    /// Get session, update last activity and report whether it was extended
+    ///
+    /// Enforces the idle timeout and the absolute maximum lifetime.
+    /// When the second value is true, the caller should re-issue the
+    /// session cookie with an updated Max-Age.
+    pub async fn get_session_with_renewal(
+        &self,
+        session_token: &str,
+        fingerprint_hash: &[u8],
+    ) -> AuthResult<(AuthSession, bool)> {
+        let session_id = parse_session_token(&self.config, session_token)?;
 
         let session = self
             .session_repo
@@ -78,6 +104,17 @@ where
             return Err(AuthError::SessionInvalid);
         }
 
+        let idle_timeout = chrono::Duration::from_std(self.config.session_idle_timeout)
+            .map_err(|e| AuthError::Internal(format!("Invalid idle timeout: {e}")))?;
+        let max_lifetime = chrono::Duration::from_std(self.config.session_max_lifetime)
+            .map_err(|e| AuthError::Internal(format!("Invalid max lifetime: {e}")))?;
+
+        if session.is_idle(idle_timeout) || session.exceeds_max_lifetime(max_lifetime) {
+            tracing::info!(session_id = %session_id, "Auth session timed out");
+            self.session_repo.delete(session_id).await?;
+            return Err(AuthError::SessionInvalid);
+        }
+
         // Update last activity (fire and forget)
         let mut session = session;
         session.touch();
@@ -85,7 +122,7 @@ where
         // Extend remember-me sessions based on config
         let ttl_long = chrono::Duration::from_std(self.config.session_ttl_long)
             .map_err(|e| AuthError::Internal(format!("Invalid session TTL: {e}")))?;
-        session.extend_if_needed(ttl_long);
+        let renewed = session.extend_if_needed(ttl_long, max_lifetime);
 
         // Update in background
         let session_clone = session.clone();
@@ -96,38 +133,45 @@ where
             }
         });
 
-        Ok(session)
+        Ok((session, renewed))
     }
 
-    /// Parse and verify session token
-    fn parse_session_token(&self, token: &str) -> AuthResult<Uuid> {
-        use base64::Engine;
-        use hmac::{Hmac, Mac};
-        use sha2::Sha256;
+    /// Get session and require a recent re-authentication (step-up)
+    ///
+    /// Use this for sensitive operations such as changing credentials,
+    /// disabling 2FA or admin actions.
+    pub async fn get_session_with_recent_reauth(
+        &self,
+        session_token: &str,
+        fingerprint_hash: &[u8],
+    ) -> AuthResult<AuthSession> {
+        let session = self.get_session(session_token, fingerprint_hash).await?;
+        self.require_recent_reauth(session)
+    }
 
-        let parts: Vec<&str> = token.split('.').collect();
-        if parts.len() != 2 {
-            return Err(AuthError::SessionInvalid);
+    /// Require a recent re-authentication of an already resolved session
+    pub fn require_recent_reauth(&self, session: AuthSession) -> AuthResult<AuthSession> {
+        if !session.is_recently_reauthenticated(self.reauth_window()?) {
+            return Err(AuthError::ReauthenticationRequired);
         }
 
-        let session_id_str = parts[0];
-        let signature_b64 = parts[1];
-
-        // Verify signature
-        let mut mac = Hmac::<Sha256>::new_from_slice(&self.config.session_secret)
-            .expect("HMAC can take key of any size");
-        mac.update(session_id_str.as_bytes());
-
-        let signature = base64::engine::general_purpose::URL_SAFE_NO_PAD
-            .decode(signature_b64)
-            .map_err(|_| AuthError::SessionInvalid)?;
-
-        mac.verify_slice(&signature)
-            .map_err(|_| AuthError::SessionInvalid)?;
+        Ok(session)
+    }
 
-        // Parse UUID
-        session_id_str
-            .parse()
-            .map_err(|_| AuthError::SessionInvalid)
+    fn reauth_window(&self) -> AuthResult<chrono::Duration> {
+        chrono::Duration::from_std(self.config.reauth_window)
+            .map_err(|e| AuthError::Internal(format!("Invalid reauth window: {e}")))
     }
 }
+
+/// Parse and verify a session token, returning the session ID
+pub(crate) fn parse_session_token(config: &AuthConfig, token: &str) -> AuthResult<Uuid> {
+    config
+        .session_keys
+        .verify(token, Utc::now().timestamp_millis())
+        .map(|claims| claims.session_id)
+        .map_err(|e| {
+            tracing::debug!(error = %e, "Session token rejected");
+            AuthError::SessionInvalid
+        })
+}
@@ -2,40 +2,97 @@
 //!
 //! Configuration for the Auth application layer.
 
+use std::sync::Arc;
 use std::time::Duration;
 
+use platform::rate_limit::{RateLimitBackend, RateLimitConfig};
+use platform::rate_limit_layer::{RateLimitKey, RateLimitLayer};
+
 /// Re-export SameSite from platform
 pub use platform::cookie::SameSite;
+/// Re-export session token keys from platform
+pub use platform::session_token::{SessionKey, SessionKeyRing};
 
 /// Auth application configuration
 #[derive(Debug, Clone)]
 pub struct AuthConfig {
     /// Session cookie name
     pub session_cookie_name: String,
-    /// Session secret key for HMAC signing (32 bytes)
-    pub session_secret: [u8; 32],
+    /// Keys for signing and verifying session tokens
+    pub session_keys: SessionKeyRing,
     /// Session TTL without "Remember Me" (12 hours)
     pub session_ttl_short: Duration,
     /// Session TTL with "Remember Me" (1 week)
     pub session_ttl_long: Duration,
+    /// Reject sessions whose last activity is older than this
+    pub session_idle_timeout: Duration,
+    /// Absolute maximum session lifetime (extension never exceeds this)
+    pub session_max_lifetime: Duration,
+    /// How long a re-authentication satisfies step-up checks ("sudo mode")
+    pub reauth_window: Duration,
     /// Whether to require Secure cookie
     pub cookie_secure: bool,
     /// SameSite policy
     pub cookie_same_site: SameSite,
     /// Password pepper (optional, application-wide secret)
     pub password_pepper: Option<Vec<u8>>,
+    /// Per-client limits on the credential endpoints (None = disabled)
+    pub rate_limits: Option<AuthRateLimits>,
 }
 
 impl Default for AuthConfig {
     fn default() -> Self {
         Self {
             session_cookie_name: "auth_session".to_string(),
-            session_secret: [0u8; 32],
+            session_keys: SessionKeyRing::new(SessionKey::new(1, [0u8; 32])),
             session_ttl_short: Duration::from_secs(12 * 3600), // 12 hours
             session_ttl_long: Duration::from_secs(7 * 24 * 3600), // 1 week
+            session_idle_timeout: Duration::from_secs(3 * 24 * 3600), // 3 days
+            session_max_lifetime: Duration::from_secs(30 * 24 * 3600), // 30 days
+            reauth_window: Duration::from_secs(10 * 60),       // 10 minutes
             cookie_secure: true,
             cookie_same_site: SameSite::Lax,
             password_pepper: None,
+            rate_limits: Some(AuthRateLimits::new(RateLimitBackend::memory())),
+        }
+    }
+}
+
+/// Per-client rate limits on the credential endpoints
+///
+/// The per-account lockout alone does not slow down guessing across
+/// accounts, and anyone can use it to lock a victim out. These limits count
+/// requests per client instead.
+#[derive(Debug, Clone)]
+pub struct AuthRateLimits {
+    /// POST /signin, per client IP
+    pub sign_in: RateLimitLayer<RateLimitBackend>,
+    /// POST /signup, per client network
+    pub sign_up: RateLimitLayer<RateLimitBackend>,
+    /// POST /totp/*, per user (or client IP) and route
+    pub totp: RateLimitLayer<RateLimitBackend>,
+}
+
+impl AuthRateLimits {
+    /// Default limits kept in `store`
+    pub fn new(store: RateLimitBackend) -> Self {
+        let store = Arc::new(store);
+        Self {
+            sign_in: RateLimitLayer::new(
+                "auth:signin",
+                store.clone(),
+                RateLimitConfig::new(10, 60),
+            )
+            .key(RateLimitKey::Ip),
+            sign_up: RateLimitLayer::new(
+                "auth:signup",
+                store.clone(),
+                RateLimitConfig::new(5, 3600),
+            )
+            .key(RateLimitKey::network()),
+            totp: RateLimitLayer::new("auth:totp", store, RateLimitConfig::new(10, 300))
+                .key(RateLimitKey::User)
+                .key(RateLimitKey::Route),
         }
     }
 }
@@ -43,11 +100,8 @@ impl Default for AuthConfig {
 impl AuthConfig {
     /// Create config with a random session secret (for development)
     pub fn with_random_secret() -> Self {
-        use rand::RngCore;
-        let mut secret = [0u8; 32];
-        rand::rng().fill_bytes(&mut secret);
         Self {
-            session_secret: secret,
+            session_keys: SessionKeyRing::new(SessionKey::random(1)),
             ..Default::default()
         }
     }
@@ -60,6 +114,16 @@ impl AuthConfig {
         }
     }
 
+    /// Get the initial session TTL, capped by the absolute maximum lifetime
+    pub fn session_ttl(&self, remember_me: bool) -> Duration {
+        let ttl = if remember_me {
+            self.session_ttl_long
+        } else {
+            self.session_ttl_short
+        };
+        ttl.min(self.session_max_lifetime)
+    }
+
     /// Get session TTL in milliseconds
     pub fn session_ttl_short_ms(&self) -> i64 {
         self.session_ttl_short.as_millis() as i64
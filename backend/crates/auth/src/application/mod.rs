@@ -2,16 +2,24 @@
 //!
 //! Use cases and application services.
 
+pub mod change_password;
+pub mod change_role;
 pub mod check_session;
 pub mod config;
+pub mod reauthenticate;
+pub mod rotate_session;
 pub mod sign_in;
 pub mod sign_out;
 pub mod sign_up;
 pub mod totp_setup;
 
 // Re-exports
+pub use change_password::{ChangePasswordInput, ChangePasswordUseCase};
+pub use change_role::ChangeRoleUseCase;
 pub use check_session::CheckSessionUseCase;
 pub use config::AuthConfig;
+pub use reauthenticate::{ReauthenticateInput, ReauthenticateOutput, ReauthenticateUseCase};
+pub use rotate_session::{RotateSessionOutput, RotateSessionUseCase};
 pub use sign_in::{ClientFingerprint, SignInInput, SignInOutput, SignInUseCase};
 pub use sign_out::SignOutUseCase;
 pub use sign_up::{SignUpInput, SignUpOutput, SignUpUseCase};
@@ -0,0 +1,157 @@
+//! Re-authenticate Use Case
+//!
+//! Step-up authentication ("sudo mode") for sensitive operations.
+//! A signed-in user proves possession of a credential again (password or TOTP)
+//! and the session is marked as recently re-authenticated.
+
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::application::config::AuthConfig;
+use crate::domain::entity::auth_session::AuthSession;
+use crate::domain::repository::{AuthRepository, AuthSessionRepository, UserRepository};
+use crate::domain::value_object::user_password::RawPassword;
+use crate::error::{AuthError, AuthResult};
+
+/// Re-authenticate input (exactly one credential is expected)
+pub struct ReauthenticateInput {
+    /// Current password
+    pub password: Option<String>,
+    /// Current TOTP code (only if 2FA is enabled)
+    pub totp_code: Option<String>,
+}
+
+/// Re-authenticate output
+pub struct ReauthenticateOutput {
+    /// Step-up is satisfied until this time (Unix timestamp ms)
+    pub reauth_expires_at_ms: i64,
+}
+
+/// Re-authenticate use case
+pub struct ReauthenticateUseCase<U, A, S>
+where
+    U: UserRepository,
+    A: AuthRepository,
+    S: AuthSessionRepository,
+{
+    user_repo: Arc<U>,
+    auth_repo: Arc<A>,
+    session_repo: Arc<S>,
+    config: Arc<AuthConfig>,
+}
+
+impl<U, A, S> ReauthenticateUseCase<U, A, S>
+where
+    U: UserRepository,
+    A: AuthRepository,
+    S: AuthSessionRepository,
+{
+    pub fn new(
+        user_repo: Arc<U>,
+        auth_repo: Arc<A>,
+        session_repo: Arc<S>,
+        config: Arc<AuthConfig>,
+    ) -> Self {
+        Self {
+            user_repo,
+            auth_repo,
+            session_repo,
+            config,
+        }
+    }
+
+    /// Verify a credential for the session owner and mark the session
+    pub async fn execute(
+        &self,
+        session: &AuthSession,
+        input: ReauthenticateInput,
+    ) -> AuthResult<ReauthenticateOutput> {
+        let user = self
+            .user_repo
+            .find_by_id(&session.user_id)
+            .await?
+            .ok_or(AuthError::SessionInvalid)?;
+
+        if !user.can_login() {
+            return Err(AuthError::AccountDisabled);
+        }
+
+        let mut auth = self
+            .auth_repo
+            .find_by_user_id(&session.user_id)
+            .await?
+            .ok_or(AuthError::Internal("Auth not found".to_string()))?;
+
+        // Failed step-up attempts count towards the same lockout as sign-in,
+        // so a stolen cookie cannot be used to brute-force the password.
+        if auth.is_locked() {
+            return Err(AuthError::AccountLocked);
+        }
+
+        let result = match (&input.totp_code, input.password) {
+            (Some(code), _) => {
+                let secret = auth
+                    .totp_secret
+                    .as_ref()
+                    .filter(|_| auth.requires_2fa())
+                    .ok_or(AuthError::TwoFactorNotSetup)?;
+
+                let valid = secret
+                    .verify(code, user.user_name.as_str())
+                    .map_err(|e| AuthError::Internal(e.to_string()))?;
+
+                if valid {
+                    Ok(())
+                } else {
+                    Err(AuthError::InvalidTwoFactorCode)
+                }
+            }
+            (None, Some(password)) => {
+                let raw_password =
+                    RawPassword::new(password).map_err(|_| AuthError::InvalidCredentials)?;
+
+                let valid = auth
+                    .password_hash
+                    .verify(&raw_password, self.config.pepper())
+                    .map_err(|e| AuthError::Internal(e.to_string()))?;
+
+                if valid {
+                    Ok(())
+                } else {
+                    Err(AuthError::InvalidCredentials)
+                }
+            }
+            (None, None) => return Err(AuthError::InvalidCredentials),
+        };
+
+        if let Err(e) = result {
+            auth.record_failure();
+            self.auth_repo.update(&auth).await?;
+            return Err(e);
+        }
+
+        if auth.login_failed_count > 0 {
+            auth.reset_failures();
+            self.auth_repo.update(&auth).await?;
+        }
+
+        let now = Utc::now();
+        self.session_repo
+            .update_reauthenticated_at(session.session_id, now)
+            .await?;
+
+        let window = chrono::Duration::from_std(self.config.reauth_window)
+            .map_err(|e| AuthError::Internal(format!("Invalid reauth window: {e}")))?;
+
+        tracing::info!(
+            user_id = %session.user_id,
+            session_id = %session.session_id,
+            "Session re-authenticated"
+        );
+
+        Ok(ReauthenticateOutput {
+            reauth_expires_at_ms: (now + window).timestamp_millis(),
+        })
+    }
+}
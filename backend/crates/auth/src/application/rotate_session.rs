@@ -0,0 +1,68 @@
+//! Rotate Session Use Case
+//!
+//! Issues a new session ID (and token) for an existing session after a
+//! privilege change, so a token captured before the change stops working.
+
+use std::sync::Arc;
+
+use crate::application::config::AuthConfig;
+use crate::application::sign_in::generate_session_token;
+use crate::domain::entity::auth_session::AuthSession;
+use crate::domain::repository::AuthSessionRepository;
+use crate::error::{AuthError, AuthResult};
+
+/// Rotate session output
+pub struct RotateSessionOutput {
+    /// New session token for cookie
+    pub session_token: String,
+    /// Session expiration (unchanged by rotation)
+    pub expires_at_ms: i64,
+}
+
+/// Rotate session use case
+pub struct RotateSessionUseCase<S>
+where
+    S: AuthSessionRepository,
+{
+    session_repo: Arc<S>,
+    config: Arc<AuthConfig>,
+}
+
+impl<S> RotateSessionUseCase<S>
+where
+    S: AuthSessionRepository,
+{
+    pub fn new(session_repo: Arc<S>, config: Arc<AuthConfig>) -> Self {
+        Self {
+            session_repo,
+            config,
+        }
+    }
+
+    /// Replace the session ID and return a token for the new ID
+    pub async fn execute(&self, session: &AuthSession) -> AuthResult<RotateSessionOutput> {
+        let mut session = session.clone();
+        let previous_id = session.rotate_id();
+
+        let rotated = self
+            .session_repo
+            .rotate_id(previous_id, session.session_id)
+            .await?;
+
+        if !rotated {
+            return Err(AuthError::SessionInvalid);
+        }
+
+        tracing::info!(
+            user_id = %session.user_id,
+            previous_session_id = %previous_id,
+            session_id = %session.session_id,
+            "Session ID rotated"
+        );
+
+        Ok(RotateSessionOutput {
+            session_token: generate_session_token(&self.config, &session)?,
+            expires_at_ms: session.expires_at_ms,
+        })
+    }
+}
@@ -4,6 +4,8 @@
 
 use std::sync::Arc;
 
+use chrono::Utc;
+
 use crate::application::config::AuthConfig;
 use crate::domain::entity::auth_session::AuthSession;
 use crate::domain::repository::{AuthRepository, AuthSessionRepository, UserRepository};
@@ -164,18 +166,12 @@ where
         self.user_repo.update(&user).await?;
 
         // Create session (TTL is driven by config to keep DB/session/cookie consistent)
-        let ttl_std = if input.remember_me {
-            self.config.session_ttl_long
-        } else {
-            self.config.session_ttl_short
-        };
-
-        let ttl = chrono::Duration::from_std(ttl_std)
+        let ttl = chrono::Duration::from_std(self.config.session_ttl(input.remember_me))
             .map_err(|e| AuthError::Internal(format!("Invalid session TTL: {e}")))?;
 
         let session = AuthSession::new(
-            user.user_id.clone(),
-            user.public_id.clone(),
+            user.user_id,
+            user.public_id,
             user.user_role,
             input.remember_me,
             fingerprint.hash_vec(),
@@ -187,7 +183,7 @@ where
         self.session_repo.create(&session).await?;
 
         // Generate session token
-        let session_token = self.generate_session_token(&session);
+        let session_token = generate_session_token(&self.config, &session)?;
 
         tracing::info!(
             public_id = %user.public_id,
@@ -212,28 +208,22 @@ where
         // For now, email login is not supported
         Ok(None)
     }
+}
 
-    /// Generate signed session token
-    fn generate_session_token(&self, session: &AuthSession) -> String {
-        use base64::Engine;
-        use hmac::{Hmac, Mac};
-        use sha2::Sha256;
-
-        let session_id = session.session_id.to_string();
-
-        // Create HMAC signature
-        let mut mac = Hmac::<Sha256>::new_from_slice(&self.config.session_secret)
-            .expect("HMAC can take key of any size");
-        mac.update(session_id.as_bytes());
-        let signature = mac.finalize().into_bytes();
-
-        // Combine session_id + signature
-        let combined = format!(
-            "{}.{}",
-            session_id,
-            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&signature)
-        );
-
-        combined
-    }
+/// Generate a signed session token
+///
+/// The token expires with the absolute session lifetime; the sliding
+/// expiration is enforced by the stored session.
+pub(crate) fn generate_session_token(
+    config: &AuthConfig,
+    session: &AuthSession,
+) -> AuthResult<String> {
+    let max_lifetime = chrono::Duration::from_std(config.session_max_lifetime)
+        .map_err(|e| AuthError::Internal(format!("Invalid max lifetime: {e}")))?;
+
+    Ok(config.session_keys.sign(
+        session.session_id,
+        Utc::now().timestamp_millis(),
+        session.absolute_expires_at_ms(max_lifetime),
+    ))
 }
@@ -4,10 +4,10 @@
 
 use std::sync::Arc;
 
+use crate::application::check_session::parse_session_token;
 use crate::application::config::AuthConfig;
 use crate::domain::repository::AuthSessionRepository;
 use crate::error::{AuthError, AuthResult};
-use uuid::Uuid;
 
 /// Sign out use case
 pub struct SignOutUseCase<S>
@@ -31,7 +31,7 @@ where
 
     /// Sign out from current session
     pub async fn execute(&self, session_token: &str) -> AuthResult<()> {
-        let session_id = self.parse_session_token(session_token)?;
+        let session_id = parse_session_token(&self.config, session_token)?;
         self.session_repo.delete(session_id).await?;
 
         tracing::info!(session_id = %session_id, "User signed out");
@@ -44,7 +44,7 @@ where
         session_token: &str,
         fingerprint_hash: &[u8],
     ) -> AuthResult<u64> {
-        let session_id = self.parse_session_token(session_token)?;
+        let session_id = parse_session_token(&self.config, session_token)?;
 
         // Get current session to find user_id
         let session = self
@@ -66,36 +66,4 @@ where
 
         Ok(deleted)
     }
-
-    /// Parse and verify session token
-    fn parse_session_token(&self, token: &str) -> AuthResult<Uuid> {
-        use base64::Engine;
-        use hmac::{Hmac, Mac};
-        use sha2::Sha256;
-
-        let parts: Vec<&str> = token.split('.').collect();
-        if parts.len() != 2 {
-            return Err(AuthError::SessionInvalid);
-        }
-
-        let session_id_str = parts[0];
-        let signature_b64 = parts[1];
-
-        // Verify signature
-        let mut mac = Hmac::<Sha256>::new_from_slice(&self.config.session_secret)
-            .expect("HMAC can take key of any size");
-        mac.update(session_id_str.as_bytes());
-
-        let signature = base64::engine::general_purpose::URL_SAFE_NO_PAD
-            .decode(signature_b64)
-            .map_err(|_| AuthError::SessionInvalid)?;
-
-        mac.verify_slice(&signature)
-            .map_err(|_| AuthError::SessionInvalid)?;
-
-        // Parse UUID
-        session_id_str
-            .parse()
-            .map_err(|_| AuthError::SessionInvalid)
-    }
 }
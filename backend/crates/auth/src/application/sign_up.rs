@@ -67,7 +67,7 @@ where
         let user = User::new(user_name);
 
         // Create auth credentials
-        let auth = Auth::new(user.user_id.clone(), password_hash);
+        let auth = Auth::new(user.user_id, password_hash);
 
         // Persist
         self.user_repo.create(&user).await?;
@@ -69,8 +69,15 @@ impl Auth {
     }
 
     /// Record a failed login attempt
+    ///
+    /// A lockout that has already expired is cleared first, so the count
+    /// starts over (as `cleanup_expired_auth_data` does in the database).
     pub fn record_failure(&mut self) {
         let now = Utc::now();
+        if self.locked_until.is_some_and(|until| until <= now) {
+            self.login_failed_count = 0;
+            self.locked_until = None;
+        }
         self.login_failed_count += 1;
         self.last_failed_at = Some(now);
         self.updated_at = now;
@@ -124,3 +131,33 @@ impl Auth {
         self.updated_at = Utc::now();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth() -> Auth {
+        Auth::new(UserId::new(), UserPassword::from_db("$argon2id$test"))
+    }
+
+    #[test]
+    fn test_lockout_after_max_failures() {
+        let mut auth = auth();
+        for _ in 0..Auth::MAX_LOGIN_FAILURES {
+            assert!(!auth.is_locked());
+            auth.record_failure();
+        }
+        assert!(auth.is_locked());
+    }
+
+    #[test]
+    fn test_expired_lockout_restarts_count() {
+        let mut auth = auth();
+        auth.login_failed_count = Auth::MAX_LOGIN_FAILURES;
+        auth.locked_until = Some(Utc::now() - chrono::Duration::minutes(1));
+
+        auth.record_failure();
+        assert_eq!(auth.login_failed_count, 1);
+        assert!(!auth.is_locked());
+    }
+}
@@ -33,12 +33,17 @@ pub struct AuthSession {
     pub created_at: DateTime<Utc>,
     /// Last activity timestamp
     pub last_activity_at: DateTime<Utc>,
+    /// Last time the user proved possession of a credential (password or TOTP)
+    ///
+    /// Sign-in counts as the first authentication; `/reauth` refreshes it.
+    pub last_reauthenticated_at: Option<DateTime<Utc>>,
 }
 
 impl AuthSession {
     /// Create a new auth session
     ///
     /// TTL is provided by the application layer (config), not hard-coded here.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         user_id: UserId,
         public_id: PublicId,
@@ -63,6 +68,7 @@ impl AuthSession {
             user_agent,
             created_at: now,
             last_activity_at: now,
+            last_reauthenticated_at: Some(now),
         }
     }
 
@@ -76,29 +82,82 @@ impl AuthSession {
         self.last_activity_at = Utc::now();
     }
 
+    /// Replace the session ID (after a privilege change)
+    ///
+    /// Returns the previous ID. Mitigates session fixation: a token captured
+    /// before the change no longer refers to the elevated session.
+    pub fn rotate_id(&mut self) -> Uuid {
+        std::mem::replace(&mut self.session_id, Uuid::new_v4())
+    }
+
+    /// Record a successful step-up re-authentication
+    pub fn mark_reauthenticated(&mut self) {
+        self.last_reauthenticated_at = Some(Utc::now());
+    }
+
+    /// Check if the session re-authenticated within the given window
+    pub fn is_recently_reauthenticated(&self, window: Duration) -> bool {
+        self.last_reauthenticated_at
+            .is_some_and(|at| Utc::now() - at <= window)
+    }
+
+    /// Get the time (Unix timestamp ms) until which step-up is satisfied
+    pub fn reauth_expires_at_ms(&self, window: Duration) -> Option<i64> {
+        self.last_reauthenticated_at
+            .map(|at| (at + window).timestamp_millis())
+    }
+
     /// Get remaining time until expiration
     pub fn remaining_ms(&self) -> i64 {
         let now_ms = Utc::now().timestamp_millis();
         (self.expires_at_ms - now_ms).max(0)
     }
 
+    /// Check if the session has been idle for longer than `idle_timeout`
+    pub fn is_idle(&self, idle_timeout: Duration) -> bool {
+        Utc::now() - self.last_activity_at > idle_timeout
+    }
+
+    /// Get the absolute expiration (Unix timestamp ms) that no extension may exceed
+    pub fn absolute_expires_at_ms(&self, max_lifetime: Duration) -> i64 {
+        (self.created_at + max_lifetime).timestamp_millis()
+    }
+
+    /// Check if the session has outlived its absolute maximum lifetime
+    pub fn exceeds_max_lifetime(&self, max_lifetime: Duration) -> bool {
+        Utc::now().timestamp_millis() > self.absolute_expires_at_ms(max_lifetime)
+    }
+
     /// Extend session if "Remember Me" is enabled
     ///
     /// The extension policy is intentionally simple:
     /// - only applies to remember_me sessions
     /// - extend to (now + ttl_long) when remaining time falls below half of ttl_long
-    pub fn extend_if_needed(&mut self, ttl_long: Duration) {
+    /// - never extend past (created_at + max_lifetime)
+    ///
+    /// Returns true if the expiration was moved forward.
+    pub fn extend_if_needed(&mut self, ttl_long: Duration, max_lifetime: Duration) -> bool {
         if !self.remember_me {
-            return;
+            return false;
         }
 
         let now = Utc::now();
-        let new_expires = (now + ttl_long).timestamp_millis();
 
         // Only extend if less than half the TTL remains
-        if self.expires_at_ms < (now + (ttl_long / 2)).timestamp_millis() {
-            self.expires_at_ms = new_expires;
+        if self.expires_at_ms >= (now + (ttl_long / 2)).timestamp_millis() {
+            return false;
+        }
+
+        let new_expires = (now + ttl_long)
+            .timestamp_millis()
+            .min(self.absolute_expires_at_ms(max_lifetime));
+
+        if new_expires <= self.expires_at_ms {
+            return false;
         }
+
+        self.expires_at_ms = new_expires;
+        true
     }
 }
 
@@ -125,3 +184,97 @@ impl From<&AuthSession> for SessionInfo {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> AuthSession {
+        AuthSession::new(
+            UserId::new(),
+            PublicId::new(),
+            UserRole::User,
+            false,
+            vec![0u8; 32],
+            None,
+            None,
+            Duration::hours(12),
+        )
+    }
+
+    #[test]
+    fn test_new_session_counts_as_reauthenticated() {
+        let session = session();
+        assert!(session.is_recently_reauthenticated(Duration::minutes(10)));
+    }
+
+    #[test]
+    fn test_reauth_window_expires() {
+        let mut session = session();
+        session.last_reauthenticated_at = Some(Utc::now() - Duration::minutes(11));
+        assert!(!session.is_recently_reauthenticated(Duration::minutes(10)));
+
+        session.mark_reauthenticated();
+        assert!(session.is_recently_reauthenticated(Duration::minutes(10)));
+    }
+
+    #[test]
+    fn test_idle_timeout() {
+        let mut session = session();
+        assert!(!session.is_idle(Duration::hours(1)));
+
+        session.last_activity_at = Utc::now() - Duration::hours(2);
+        assert!(session.is_idle(Duration::hours(1)));
+    }
+
+    #[test]
+    fn test_extend_only_remember_me() {
+        let mut session = session();
+        session.expires_at_ms = Utc::now().timestamp_millis() + 1_000;
+        assert!(!session.extend_if_needed(Duration::days(7), Duration::days(30)));
+    }
+
+    #[test]
+    fn test_extend_capped_by_max_lifetime() {
+        let mut session = session();
+        session.remember_me = true;
+        session.created_at = Utc::now() - Duration::days(29);
+        session.expires_at_ms = Utc::now().timestamp_millis() + 1_000;
+
+        assert!(session.extend_if_needed(Duration::days(7), Duration::days(30)));
+        assert_eq!(
+            session.expires_at_ms,
+            session.absolute_expires_at_ms(Duration::days(30))
+        );
+
+        // Already at the absolute cap: no further extension
+        assert!(!session.extend_if_needed(Duration::days(7), Duration::days(30)));
+    }
+
+    #[test]
+    fn test_exceeds_max_lifetime() {
+        let mut session = session();
+        assert!(!session.exceeds_max_lifetime(Duration::days(30)));
+
+        session.created_at = Utc::now() - Duration::days(31);
+        assert!(session.exceeds_max_lifetime(Duration::days(30)));
+    }
+
+    #[test]
+    fn test_rotate_id() {
+        let mut session = session();
+        let original = session.session_id;
+
+        let previous = session.rotate_id();
+        assert_eq!(previous, original);
+        assert_ne!(session.session_id, original);
+    }
+
+    #[test]
+    fn test_missing_reauth_timestamp() {
+        let mut session = session();
+        session.last_reauthenticated_at = None;
+        assert!(!session.is_recently_reauthenticated(Duration::minutes(10)));
+        assert!(session.reauth_expires_at_ms(Duration::minutes(10)).is_none());
+    }
+}
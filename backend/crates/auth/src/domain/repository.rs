@@ -5,8 +5,11 @@
 use crate::domain::entity::{
     auth::Auth, auth_session::AuthSession, user::User, user_details::UserDetails,
 };
-use crate::domain::value_object::{public_id::PublicId, user_id::UserId, user_name::UserName};
+use crate::domain::value_object::{
+    public_id::PublicId, user_id::UserId, user_name::UserName, user_role::UserRole,
+};
 use crate::error::AuthResult;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 /// User repository trait
@@ -79,8 +82,25 @@ pub trait LocalAuthSessionRepository {
     /// Update session (e.g., last activity)
     async fn update(&self, session: &AuthSession) -> AuthResult<()>;
 
-    /// Delete a session
+    /// Record a successful step-up re-authentication
+    ///
+    /// Kept separate from `update` so background activity updates
+    /// cannot overwrite a newer re-authentication timestamp.
+    async fn update_reauthenticated_at(
+        &self,
+        session_id: Uuid,
+        reauthenticated_at: DateTime<Utc>,
+    ) -> AuthResult<()>;
 
+    /// Replace a session ID, keeping all other session data
+    ///
+    /// Returns false if the old session no longer exists.
+    async fn rotate_id(&self, old_session_id: Uuid, new_session_id: Uuid) -> AuthResult<bool>;
+
+    /// Replace the role snapshot in all sessions of a user
+    async fn update_role_for_user(&self, user_id: &UserId, user_role: UserRole) -> AuthResult<u64>;
+
+    /// Delete a session
     async fn delete(&self, session_id: Uuid) -> AuthResult<()>;
 
     /// Delete all sessions for a user (except current)
@@ -375,7 +375,7 @@ impl UserName {
         }
 
         // Check reserved words
-        if reserved_words.iter().any(|&w| w == canonical) {
+        if reserved_words.contains(&canonical) {
             return Err(UserNameError::Reserved {
                 word: canonical.to_string(),
             });
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::str::FromStr;
+
+use crate::error::AuthError;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 #[repr(i16)]
@@ -77,6 +80,22 @@ impl UserRole {
     }
 }
 
+impl FromStr for UserRole {
+    type Err = AuthError;
+
+    /// Parse a role code, rejecting unknown codes instead of panicking
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        use UserRole::*;
+        match code {
+            "user" => Ok(User),
+            "moderator" => Ok(Moderator),
+            "admin" => Ok(Admin),
+            "super_admin" => Ok(SuperAdmin),
+            _ => Err(AuthError::InvalidRole(code.to_string())),
+        }
+    }
+}
+
 impl fmt::Display for UserRole {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str(self.code())
@@ -103,6 +122,22 @@ mod tests {
         assert_eq!(UserRole::from_code("super_admin"), UserRole::SuperAdmin);
     }
 
+    #[test]
+    fn test_user_role_from_str() {
+        assert_eq!(
+            "moderator".parse::<UserRole>().unwrap(),
+            UserRole::Moderator
+        );
+        assert_eq!(
+            "super_admin".parse::<UserRole>().unwrap(),
+            UserRole::SuperAdmin
+        );
+        assert!(matches!(
+            "root".parse::<UserRole>(),
+            Err(AuthError::InvalidRole(_))
+        ));
+    }
+
     #[test]
     fn test_user_role_display() {
         assert_eq!(UserRole::User.to_string(), "user");
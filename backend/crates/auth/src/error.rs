@@ -3,7 +3,7 @@
 //! This module provides auth-specific error variants that integrate
 //! with the unified `kernel::error::AppError` system.
 
-use axum::http::StatusCode;
+use axum::http::{HeaderValue, StatusCode};
 use axum::response::{IntoResponse, Response};
 use kernel::error::{app_error::AppError, kind::ErrorKind};
 use thiserror::Error;
@@ -46,6 +46,10 @@ pub enum AuthError {
     #[error("Two-factor authentication required")]
     TwoFactorRequired,
 
+    /// Sensitive operation requires a recent re-authentication (step-up)
+    #[error("Recent re-authentication required")]
+    ReauthenticationRequired,
+
     /// Invalid 2FA code
     #[error("Invalid two-factor authentication code")]
     InvalidTwoFactorCode,
@@ -58,6 +62,14 @@ pub enum AuthError {
     #[error("Email is required for this role")]
     EmailRequired,
 
+    /// Unknown role code
+    #[error("Invalid role: {0}")]
+    InvalidRole(String),
+
+    /// Role cannot be changed through this path (super admin)
+    #[error("Role change not allowed")]
+    RoleChangeForbidden,
+
     /// Missing required header
     #[error("Missing required header: {0}")]
     MissingHeader(String),
@@ -88,12 +100,14 @@ impl AuthError {
                 StatusCode::UNAUTHORIZED
             }
             AuthError::TwoFactorRequired => StatusCode::from_u16(428).unwrap(), // Precondition Required
+            AuthError::ReauthenticationRequired => StatusCode::FORBIDDEN,
             AuthError::InvalidTwoFactorCode => StatusCode::UNAUTHORIZED,
             AuthError::TwoFactorNotSetup => StatusCode::PRECONDITION_FAILED,
             AuthError::EmailRequired => StatusCode::PRECONDITION_FAILED,
-            AuthError::MissingHeader(_) | AuthError::PasswordValidation(_) => {
-                StatusCode::BAD_REQUEST
-            }
+            AuthError::RoleChangeForbidden => StatusCode::FORBIDDEN,
+            AuthError::InvalidRole(_)
+            | AuthError::MissingHeader(_)
+            | AuthError::PasswordValidation(_) => StatusCode::BAD_REQUEST,
             AuthError::Database(_) | AuthError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -109,17 +123,27 @@ impl AuthError {
             | AuthError::InvalidTwoFactorCode => ErrorKind::Unauthorized,
             AuthError::AccountLocked => ErrorKind::Forbidden,
             AuthError::AccountDisabled => ErrorKind::Forbidden,
+            AuthError::ReauthenticationRequired => ErrorKind::Forbidden,
+            AuthError::RoleChangeForbidden => ErrorKind::Forbidden,
             AuthError::TwoFactorRequired
             | AuthError::TwoFactorNotSetup
             | AuthError::EmailRequired => ErrorKind::UnprocessableEntity,
-            AuthError::MissingHeader(_) | AuthError::PasswordValidation(_) => ErrorKind::BadRequest,
+            AuthError::InvalidRole(_)
+            | AuthError::MissingHeader(_)
+            | AuthError::PasswordValidation(_) => ErrorKind::BadRequest,
             AuthError::Database(_) | AuthError::Internal(_) => ErrorKind::InternalServerError,
         }
     }
 
     /// Convert to AppError
     pub fn to_app_error(&self) -> AppError {
-        AppError::new(self.kind(), self.to_string())
+        let err = AppError::new(self.kind(), self.to_string());
+        match self {
+            AuthError::ReauthenticationRequired => {
+                err.with_action("Re-authenticate via POST /api/auth/reauth")
+            }
+            _ => err,
+        }
     }
 
     /// Log the error with appropriate level
@@ -150,7 +174,14 @@ impl AuthError {
 impl IntoResponse for AuthError {
     fn into_response(self) -> Response {
         self.log();
-        self.to_app_error().into_response()
+        let mut response = self.to_app_error().into_response();
+        if matches!(self, AuthError::ReauthenticationRequired) {
+            // Distinct marker so clients can prompt for step-up instead of sign-in
+            response
+                .headers_mut()
+                .insert("X-Reauth-Required", HeaderValue::from_static("true"));
+        }
+        response
     }
 }
 
@@ -15,6 +15,9 @@ use crate::domain::value_object::{
 };
 use crate::error::{AuthError, AuthResult};
 
+/// Rows deleted per statement by [`PgAuthRepository::cleanup_expired`]
+pub const DEFAULT_CLEANUP_BATCH_SIZE: i64 = 1_000;
+
 /// PostgreSQL-backed auth repository
 #[derive(Clone)]
 pub struct PgAuthRepository {
@@ -28,18 +31,68 @@ impl PgAuthRepository {
 
     /// Clean up expired sessions
     pub async fn cleanup_expired(&self) -> AuthResult<u64> {
-        let now_ms = Utc::now().timestamp_millis();
+        self.cleanup_expired_batched(DEFAULT_CLEANUP_BATCH_SIZE).await
+    }
 
-        let deleted = sqlx::query("DELETE FROM auth_sessions WHERE expires_at_ms < $1")
+    /// Clean up expired sessions, deleting at most `batch_size` rows per statement
+    ///
+    /// Short statements keep lock times and WAL bursts small when a large
+    /// backlog has built up.
+    pub async fn cleanup_expired_batched(&self, batch_size: i64) -> AuthResult<u64> {
+        let now_ms = Utc::now().timestamp_millis();
+        let batch_size = batch_size.max(1);
+
+        let mut deleted = 0;
+        loop {
+            let batch = sqlx::query(
+                r#"
+                DELETE FROM auth_sessions
+                WHERE ctid = ANY(ARRAY(
+                    SELECT ctid FROM auth_sessions
+                    WHERE expires_at_ms < $1
+                    LIMIT $2
+                ))
+                "#,
+            )
             .bind(now_ms)
+            .bind(batch_size)
             .execute(&self.pool)
             .await?
             .rows_affected();
+            deleted += batch;
+            if batch < batch_size as u64 {
+                break;
+            }
+        }
 
         tracing::info!(sessions_deleted = deleted, "Cleaned up expired auth sessions");
 
         Ok(deleted)
     }
+
+    /// Clear lockouts that have expired and restart their failure counts
+    ///
+    /// Mirrors `cleanup_expired_auth_data`. `Auth::record_failure` does the
+    /// same for a single account, so this only tidies up idle accounts.
+    pub async fn reset_expired_lockouts(&self) -> AuthResult<u64> {
+        let reset = sqlx::query(
+            r#"
+            UPDATE auth_credentials
+            SET locked_until = NULL,
+                login_failed_count = 0
+            WHERE locked_until IS NOT NULL
+              AND locked_until < $1
+            "#,
+        )
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        tracing::info!(lockouts_reset = reset, "Reset expired auth lockouts");
+
+        Ok(reset)
+    }
 }
 
 // ============================================================================
@@ -391,8 +444,9 @@ impl AuthSessionRepository for PgAuthRepository {
                 client_ip,
                 user_agent,
                 created_at,
-                last_activity_at
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                last_activity_at,
+                last_reauthenticated_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
             "#,
         )
         .bind(session.session_id)
@@ -406,6 +460,7 @@ impl AuthSessionRepository for PgAuthRepository {
         .bind(&session.user_agent)
         .bind(session.created_at)
         .bind(session.last_activity_at)
+        .bind(session.last_reauthenticated_at)
         .execute(&self.pool)
         .await?;
 
@@ -432,7 +487,8 @@ impl AuthSessionRepository for PgAuthRepository {
                 client_ip,
                 user_agent,
                 created_at,
-                last_activity_at
+                last_activity_at,
+                last_reauthenticated_at
             FROM auth_sessions
             WHERE session_id = $1 AND expires_at_ms > $2
             "#,
@@ -474,7 +530,8 @@ impl AuthSessionRepository for PgAuthRepository {
                 client_ip,
                 user_agent,
                 created_at,
-                last_activity_at
+                last_activity_at,
+                last_reauthenticated_at
             FROM auth_sessions
             WHERE user_id = $1 AND expires_at_ms > $2
             ORDER BY last_activity_at DESC
@@ -506,6 +563,42 @@ impl AuthSessionRepository for PgAuthRepository {
         Ok(())
     }
 
+    async fn update_reauthenticated_at(
+        &self,
+        session_id: Uuid,
+        reauthenticated_at: DateTime<Utc>,
+    ) -> AuthResult<()> {
+        sqlx::query("UPDATE auth_sessions SET last_reauthenticated_at = $2 WHERE session_id = $1")
+            .bind(session_id)
+            .bind(reauthenticated_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn rotate_id(&self, old_session_id: Uuid, new_session_id: Uuid) -> AuthResult<bool> {
+        let updated = sqlx::query("UPDATE auth_sessions SET session_id = $2 WHERE session_id = $1")
+            .bind(old_session_id)
+            .bind(new_session_id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(updated > 0)
+    }
+
+    async fn update_role_for_user(&self, user_id: &UserId, user_role: UserRole) -> AuthResult<u64> {
+        let updated = sqlx::query("UPDATE auth_sessions SET user_role = $2 WHERE user_id = $1")
+            .bind(user_id.as_uuid())
+            .bind(user_role.id())
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(updated)
+    }
+
     async fn delete(&self, session_id: Uuid) -> AuthResult<()> {
         sqlx::query("DELETE FROM auth_sessions WHERE session_id = $1")
             .bind(session_id)
@@ -631,6 +724,7 @@ struct AuthSessionRow {
     user_agent: Option<String>,
     created_at: DateTime<Utc>,
     last_activity_at: DateTime<Utc>,
+    last_reauthenticated_at: Option<DateTime<Utc>>,
 }
 
 impl AuthSessionRow {
@@ -652,6 +746,7 @@ impl AuthSessionRow {
             user_agent: self.user_agent,
             created_at: self.created_at,
             last_activity_at: self.last_activity_at,
+            last_reauthenticated_at: self.last_reauthenticated_at,
         })
     }
 }
@@ -17,6 +17,7 @@
 //! - Sessions bound to client fingerprint (User-Agent)
 //! - Automatic lockout after failed login attempts
 //! - Moderator+ roles require 2FA
+//! - Sensitive operations require a recent re-authentication (step-up)
 
 pub mod application;
 pub mod domain;
@@ -28,7 +29,7 @@ pub mod presentation;
 pub use application::config::AuthConfig;
 pub use error::{AuthError, AuthResult};
 pub use infra::postgres::PgAuthRepository;
-pub use presentation::router::auth_router;
+pub use presentation::router::{auth_admin_router, auth_router};
 
 // Re-export kernel error types for unified error handling
 pub use kernel::error::{
@@ -62,3 +63,6 @@ pub mod router {
 pub mod middleware {
     pub use crate::presentation::middleware::*;
 }
+
+#[cfg(test)]
+mod tests;
@@ -59,6 +59,50 @@ pub struct SessionStatusResponse {
     pub public_id: Option<String>,
     pub user_role: Option<String>,
     pub expires_at_ms: Option<i64>,
+    /// Step-up ("sudo mode") is satisfied until this time
+    pub reauth_expires_at_ms: Option<i64>,
+}
+
+// ============================================================================
+// Re-authentication (step-up)
+// ============================================================================
+
+/// Re-authentication request (password or TOTP code)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReauthRequest {
+    pub password: Option<String>,
+    pub totp_code: Option<String>,
+}
+
+/// Re-authentication response
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReauthResponse {
+    pub reauth_expires_at_ms: i64,
+}
+
+// ============================================================================
+// Password Change
+// ============================================================================
+
+/// Change password request (requires a recent re-authentication)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePasswordRequest {
+    pub new_password: String,
+}
+
+// ============================================================================
+// Role Change (admin)
+// ============================================================================
+
+/// Change role request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeRoleRequest {
+    /// Role code (`user`, `moderator`, `admin`)
+    pub user_role: String,
 }
 
 // ============================================================================
@@ -106,4 +150,3 @@ pub struct UserInfoResponse {
     pub totp_enabled: bool,
     pub last_login_at: Option<i64>,
 }
-
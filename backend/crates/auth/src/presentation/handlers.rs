@@ -1,23 +1,27 @@
 //! HTTP Handlers
 
-use axum::Json;
-use axum::extract::State;
+use axum::extract::{Path, State};
 use axum::http::{HeaderMap, StatusCode, header};
 use axum::response::IntoResponse;
+use axum::{Extension, Json};
 use std::sync::Arc;
 
-use platform::client::{extract_client_ip, extract_fingerprint};
+use platform::client::{ClientIp, extract_fingerprint};
 
 use crate::application::config::{AuthConfig, SameSite};
 use crate::application::{
-    CheckSessionUseCase, SignInInput, SignInUseCase, SignOutUseCase, SignUpInput, SignUpUseCase,
-    TotpSetupUseCase,
+    ChangePasswordInput, ChangePasswordUseCase, ChangeRoleUseCase, CheckSessionUseCase,
+    ReauthenticateInput, ReauthenticateUseCase, RotateSessionUseCase, SignInInput, SignInUseCase,
+    SignOutUseCase, SignUpInput, SignUpUseCase, TotpSetupUseCase,
 };
+use crate::domain::entity::auth_session::AuthSession;
 use crate::domain::repository::{AuthRepository, AuthSessionRepository, UserRepository};
+use crate::domain::value_object::{public_id::PublicId, user_role::UserRole};
 use crate::error::{AuthError, AuthResult};
 use crate::presentation::dto::{
-    SessionStatusResponse, SignInRequest, SignInResponse, SignUpRequest, SignUpResponse,
-    TotpDisableRequest, TotpSetupResponse, TotpVerifyRequest,
+    ChangePasswordRequest, ChangeRoleRequest, ReauthRequest, ReauthResponse, SessionStatusResponse,
+    SignInRequest, SignInResponse, SignUpRequest, SignUpResponse, TotpDisableRequest,
+    TotpSetupResponse, TotpVerifyRequest,
 };
 
 /// Shared state for auth handlers
@@ -64,13 +68,12 @@ where
 pub async fn sign_in<R>(
     State(state): State<AuthAppState<R>>,
     headers: HeaderMap,
-    axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
+    ClientIp(client_ip): ClientIp,
     Json(req): Json<SignInRequest>,
 ) -> AuthResult<impl IntoResponse>
 where
     R: UserRepository + AuthRepository + AuthSessionRepository + Clone + Send + Sync + 'static,
 {
-    let client_ip = extract_client_ip(&headers, Some(addr.ip()));
     let fingerprint = extract_fingerprint(&headers, client_ip)?;
 
     let use_case = SignInUseCase::new(
@@ -105,7 +108,8 @@ where
     }
 
     // Success - set session cookie (Max-Age must match remember_me)
-    let cookie = build_session_cookie(&state.config, &output.session_token, remember_me);
+    let max_age = state.config.session_ttl(remember_me).as_secs();
+    let cookie = build_session_cookie(&state.config, &output.session_token, max_age);
 
     Ok((
         StatusCode::OK,
@@ -151,54 +155,66 @@ where
 pub async fn session_status<R>(
     State(state): State<AuthAppState<R>>,
     headers: HeaderMap,
-    axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
-) -> AuthResult<Json<SessionStatusResponse>>
+    ClientIp(client_ip): ClientIp,
+) -> AuthResult<impl IntoResponse>
 where
     R: UserRepository + AuthRepository + AuthSessionRepository + Clone + Send + Sync + 'static,
 {
-    let client_ip = extract_client_ip(&headers, Some(addr.ip()));
     let fingerprint = extract_fingerprint(&headers, client_ip)?;
 
     let token = extract_session_cookie(&headers, &state.config.session_cookie_name);
 
     let use_case = CheckSessionUseCase::new(state.repo.clone(), state.config.clone());
 
-    let session_info = if let Some(token) = token {
-        use_case.execute(&token, &fingerprint.hash).await.ok()
+    let session_info = if let Some(token) = &token {
+        use_case.execute(token, &fingerprint.hash).await.ok()
     } else {
         None
     };
 
-    match session_info {
-        Some(info) => Ok(Json(SessionStatusResponse {
-            authenticated: true,
-            public_id: Some(info.public_id),
-            user_role: Some(info.user_role),
-            expires_at_ms: Some(info.expires_at_ms),
-        })),
-        None => Ok(Json(SessionStatusResponse {
+    match (session_info, token) {
+        (Some(info), Some(token)) => {
+            let body = Json(SessionStatusResponse {
+                authenticated: true,
+                public_id: Some(info.public_id),
+                user_role: Some(info.user_role),
+                expires_at_ms: Some(info.expires_at_ms),
+                reauth_expires_at_ms: info.reauth_expires_at_ms,
+            });
+
+            if info.renewed {
+                // Session was extended - re-issue cookie with the new Max-Age
+                let cookie = build_session_cookie_until(&state.config, &token, info.expires_at_ms);
+                return Ok(([(header::SET_COOKIE, cookie)], body).into_response());
+            }
+
+            Ok(body.into_response())
+        }
+        _ => Ok(Json(SessionStatusResponse {
             authenticated: false,
             public_id: None,
             user_role: None,
             expires_at_ms: None,
-        })),
+            reauth_expires_at_ms: None,
+        })
+        .into_response()),
     }
 }
 
 // ============================================================================
-// TOTP Setup (requires authentication)
+// Re-authentication (step-up)
 // ============================================================================
 
-/// POST /api/auth/totp/setup
-pub async fn totp_setup<R>(
+/// POST /api/auth/reauth
+pub async fn reauthenticate<R>(
     State(state): State<AuthAppState<R>>,
     headers: HeaderMap,
-    axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
-) -> AuthResult<Json<TotpSetupResponse>>
+    ClientIp(client_ip): ClientIp,
+    Json(req): Json<ReauthRequest>,
+) -> AuthResult<Json<ReauthResponse>>
 where
     R: UserRepository + AuthRepository + AuthSessionRepository + Clone + Send + Sync + 'static,
 {
-    let client_ip = extract_client_ip(&headers, Some(addr.ip()));
     let fingerprint = extract_fingerprint(&headers, client_ip)?;
 
     // Get current session
@@ -210,6 +226,113 @@ where
         .get_session(&token, &fingerprint.hash)
         .await?;
 
+    let use_case = ReauthenticateUseCase::new(
+        state.repo.clone(),
+        state.repo.clone(),
+        state.repo.clone(),
+        state.config.clone(),
+    );
+
+    let input = ReauthenticateInput {
+        password: req.password,
+        totp_code: req.totp_code,
+    };
+
+    let output = use_case.execute(&session, input).await?;
+
+    Ok(Json(ReauthResponse {
+        reauth_expires_at_ms: output.reauth_expires_at_ms,
+    }))
+}
+
+// ============================================================================
+// Password Change (requires step-up)
+// ============================================================================
+
+/// POST /api/auth/password
+pub async fn change_password<R>(
+    State(state): State<AuthAppState<R>>,
+    headers: HeaderMap,
+    ClientIp(client_ip): ClientIp,
+    Json(req): Json<ChangePasswordRequest>,
+) -> AuthResult<impl IntoResponse>
+where
+    R: UserRepository + AuthRepository + AuthSessionRepository + Clone + Send + Sync + 'static,
+{
+    let fingerprint = extract_fingerprint(&headers, client_ip)?;
+
+    // Get current session
+    let token = extract_session_cookie(&headers, &state.config.session_cookie_name)
+        .ok_or(AuthError::SessionInvalid)?;
+
+    let check_use_case = CheckSessionUseCase::new(state.repo.clone(), state.config.clone());
+    let session = check_use_case
+        .get_session_with_recent_reauth(&token, &fingerprint.hash)
+        .await?;
+
+    let use_case =
+        ChangePasswordUseCase::new(state.repo.clone(), state.repo.clone(), state.config.clone());
+
+    let input = ChangePasswordInput {
+        new_password: req.new_password,
+    };
+
+    use_case.execute(&session, input).await?;
+
+    // Credential change - rotate the session ID
+    let rotate_use_case = RotateSessionUseCase::new(state.repo.clone(), state.config.clone());
+    let rotated = rotate_use_case.execute(&session).await?;
+    let cookie =
+        build_session_cookie_until(&state.config, &rotated.session_token, rotated.expires_at_ms);
+
+    Ok((StatusCode::NO_CONTENT, [(header::SET_COOKIE, cookie)]))
+}
+
+// ============================================================================
+// Role Change (admin)
+// ============================================================================
+
+/// POST /api/admin/auth/users/{public_id}/role
+///
+/// Must be nested behind an admin-only guard.
+pub async fn change_role<R>(
+    State(state): State<AuthAppState<R>>,
+    Path(public_id): Path<String>,
+    Json(req): Json<ChangeRoleRequest>,
+) -> AuthResult<StatusCode>
+where
+    R: UserRepository + AuthRepository + AuthSessionRepository + Clone + Send + Sync + 'static,
+{
+    let public_id = PublicId::parse_str(&public_id).map_err(|_| AuthError::UserNotFound)?;
+    let new_role: UserRole = req.user_role.parse()?;
+
+    let use_case = ChangeRoleUseCase::new(state.repo.clone(), state.repo.clone());
+
+    use_case.execute(&public_id, new_role).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ============================================================================
+// TOTP Setup (requires authentication)
+// ============================================================================
+
+/// POST /api/auth/totp/setup
+///
+/// TOTP handlers use the session resolved by `check_auth_session`.
+pub async fn totp_setup<R>(
+    State(state): State<AuthAppState<R>>,
+    session: Option<Extension<AuthSession>>,
+) -> AuthResult<Json<TotpSetupResponse>>
+where
+    R: UserRepository + AuthRepository + AuthSessionRepository + Clone + Send + Sync + 'static,
+{
+    let Extension(session) = session.ok_or(AuthError::SessionInvalid)?;
+
+    // Replacing the TOTP secret disables existing 2FA, so require step-up
+    let check_use_case = CheckSessionUseCase::new(state.repo.clone(), state.config.clone());
+    let session = check_use_case.require_recent_reauth(session)?;
+
     // Setup TOTP
     let use_case =
         TotpSetupUseCase::new(state.repo.clone(), state.repo.clone(), state.config.clone());
@@ -226,24 +349,13 @@ where
 /// POST /api/auth/totp/verify
 pub async fn totp_verify<R>(
     State(state): State<AuthAppState<R>>,
-    headers: HeaderMap,
-    axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
+    session: Option<Extension<AuthSession>>,
     Json(req): Json<TotpVerifyRequest>,
-) -> AuthResult<StatusCode>
+) -> AuthResult<impl IntoResponse>
 where
     R: UserRepository + AuthRepository + AuthSessionRepository + Clone + Send + Sync + 'static,
 {
-    let client_ip = extract_client_ip(&headers, Some(addr.ip()));
-    let fingerprint = extract_fingerprint(&headers, client_ip)?;
-
-    // Get current session
-    let token = extract_session_cookie(&headers, &state.config.session_cookie_name)
-        .ok_or(AuthError::SessionInvalid)?;
-
-    let check_use_case = CheckSessionUseCase::new(state.repo.clone(), state.config.clone());
-    let session = check_use_case
-        .get_session(&token, &fingerprint.hash)
-        .await?;
+    let Extension(session) = session.ok_or(AuthError::SessionInvalid)?;
 
     // Verify TOTP
     let use_case =
@@ -251,30 +363,28 @@ where
 
     use_case.verify(&session.user_id, &req.code).await?;
 
-    Ok(StatusCode::NO_CONTENT)
+    // Enabling 2FA is a privilege change - rotate the session ID
+    let rotate_use_case = RotateSessionUseCase::new(state.repo.clone(), state.config.clone());
+    let rotated = rotate_use_case.execute(&session).await?;
+    let cookie =
+        build_session_cookie_until(&state.config, &rotated.session_token, rotated.expires_at_ms);
+
+    Ok((StatusCode::NO_CONTENT, [(header::SET_COOKIE, cookie)]))
 }
 
 /// POST /api/auth/totp/disable
 pub async fn totp_disable<R>(
     State(state): State<AuthAppState<R>>,
-    headers: HeaderMap,
-    axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
+    session: Option<Extension<AuthSession>>,
     Json(req): Json<TotpDisableRequest>,
 ) -> AuthResult<StatusCode>
 where
     R: UserRepository + AuthRepository + AuthSessionRepository + Clone + Send + Sync + 'static,
 {
-    let client_ip = extract_client_ip(&headers, Some(addr.ip()));
-    let fingerprint = extract_fingerprint(&headers, client_ip)?;
-
-    // Get current session
-    let token = extract_session_cookie(&headers, &state.config.session_cookie_name)
-        .ok_or(AuthError::SessionInvalid)?;
+    let Extension(session) = session.ok_or(AuthError::SessionInvalid)?;
 
     let check_use_case = CheckSessionUseCase::new(state.repo.clone(), state.config.clone());
-    let session = check_use_case
-        .get_session(&token, &fingerprint.hash)
-        .await?;
+    let session = check_use_case.require_recent_reauth(session)?;
 
     // Disable TOTP
     let use_case =
@@ -293,13 +403,7 @@ fn extract_session_cookie(headers: &HeaderMap, name: &str) -> Option<String> {
     platform::cookie::extract_cookie(headers, name)
 }
 
-fn build_session_cookie(config: &AuthConfig, token: &str, remember_me: bool) -> String {
-    let max_age = if remember_me {
-        config.session_ttl_long.as_secs()
-    } else {
-        config.session_ttl_short.as_secs()
-    };
-
+pub(crate) fn build_session_cookie(config: &AuthConfig, token: &str, max_age: u64) -> String {
     let mut parts = vec![
         format!("{}={}", config.session_cookie_name, token),
         "HttpOnly".to_string(),
@@ -320,6 +424,16 @@ fn build_session_cookie(config: &AuthConfig, token: &str, remember_me: bool) ->
     parts.join("; ")
 }
 
+/// Build a session cookie whose Max-Age matches the session expiration
+pub(crate) fn build_session_cookie_until(
+    config: &AuthConfig,
+    token: &str,
+    expires_at_ms: i64,
+) -> String {
+    let remaining_ms = (expires_at_ms - chrono::Utc::now().timestamp_millis()).max(0);
+    build_session_cookie(config, token, (remaining_ms / 1000) as u64)
+}
+
 fn build_clear_cookie(config: &AuthConfig) -> String {
     let mut parts = vec![
         format!("{}=", config.session_cookie_name),
@@ -3,16 +3,17 @@
 //! Middleware for requiring authentication on protected routes.
 
 use axum::body::Body;
-use axum::http::{Request, StatusCode};
+use axum::http::{HeaderValue, Request, StatusCode, header};
 use axum::middleware::Next;
 use axum::response::{IntoResponse, Response};
-use platform::client::{extract_client_ip, extract_fingerprint};
+use platform::client::{ClientIp, ClientRole, ClientUserId, extract_fingerprint};
 use std::sync::Arc;
 
 use crate::application::config::AuthConfig;
 use crate::application::CheckSessionUseCase;
 use crate::domain::repository::AuthSessionRepository;
 use crate::error::AuthError;
+use crate::presentation::handlers::build_session_cookie_until;
 
 /// Middleware state
 #[derive(Clone)]
@@ -25,9 +26,12 @@ where
 }
 
 /// Middleware that requires a valid auth session
+///
+/// The user's role and id are stored as `ClientRole` / `ClientUserId`
+/// extensions for downstream middleware (e.g. PoW bypasses, rate limits).
 pub async fn require_auth_session<R>(
     state: AuthMiddlewareState<R>,
-    req: Request<Body>,
+    mut req: Request<Body>,
     next: Next,
 ) -> Result<Response, Response>
 where
@@ -35,12 +39,7 @@ where
 {
     let headers = req.headers();
 
-    let client_ip = req
-        .extensions()
-        .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
-        .map(|info| info.0.ip());
-
-    let client_ip = extract_client_ip(headers, client_ip);
+    let ClientIp(client_ip) = ClientIp::from_extensions(req.extensions());
 
     let fingerprint = match extract_fingerprint(headers, client_ip) {
         Ok(fp) => fp,
@@ -51,25 +50,134 @@ where
 
     let use_case = CheckSessionUseCase::new(state.repo.clone(), state.config.clone());
 
-    let session_valid = if let Some(token) = token {
-        use_case.is_valid(&token, &fingerprint.hash).await
+    let checked = if let Some(token) = &token {
+        use_case
+            .get_session_with_renewal(token, &fingerprint.hash)
+            .await
+            .ok()
     } else {
-        false
+        None
     };
 
-    if !session_valid {
+    let (Some((session, renewed)), Some(token)) = (checked, token) else {
         return Err((
             StatusCode::UNAUTHORIZED,
             [("X-Auth-Required", "true")],
         )
             .into_response());
+    };
+
+    req.extensions_mut()
+        .insert(ClientRole(session.user_role.code().to_string()));
+    req.extensions_mut()
+        .insert(ClientUserId(session.user_id.as_uuid().to_string()));
+
+    let mut response = next.run(req).await;
+
+    if renewed {
+        // Session was extended - re-issue cookie with the new Max-Age
+        let cookie = build_session_cookie_until(&state.config, &token, session.expires_at_ms);
+        if let Ok(value) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(header::SET_COOKIE, value);
+        }
     }
 
-    Ok(next.run(req).await)
+    Ok(response)
+}
+
+/// Middleware that requires a valid auth session with a recent re-authentication
+///
+/// Guards sensitive routes ("sudo mode"). Responds with 403 and
+/// `X-Reauth-Required: true` when the step-up window has elapsed.
+pub async fn require_recent_reauth<R>(
+    state: AuthMiddlewareState<R>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, Response>
+where
+    R: AuthSessionRepository + Clone + Send + Sync + 'static,
+{
+    let headers = req.headers();
+
+    let ClientIp(client_ip) = ClientIp::from_extensions(req.extensions());
+
+    let fingerprint = match extract_fingerprint(headers, client_ip) {
+        Ok(fp) => fp,
+        Err(e) => return Err(AuthError::from(e).into_response()),
+    };
+
+    let Some(token) = platform::cookie::extract_cookie(headers, &state.config.session_cookie_name)
+    else {
+        return Err((StatusCode::UNAUTHORIZED, [("X-Auth-Required", "true")]).into_response());
+    };
+
+    let use_case = CheckSessionUseCase::new(state.repo.clone(), state.config.clone());
+
+    match use_case
+        .get_session_with_recent_reauth(&token, &fingerprint.hash)
+        .await
+    {
+        Ok(_) => Ok(next.run(req).await),
+        Err(AuthError::ReauthenticationRequired) => {
+            Err(AuthError::ReauthenticationRequired.into_response())
+        }
+        Err(_) => Err((StatusCode::UNAUTHORIZED, [("X-Auth-Required", "true")]).into_response()),
+    }
+}
+
+/// Middleware that requires an admin session with a recent re-authentication
+///
+/// Guards admin routes. Responds like `require_recent_reauth` and
+/// additionally with 403 when the user's role is below admin.
+pub async fn require_admin_session<R>(
+    state: AuthMiddlewareState<R>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, Response>
+where
+    R: AuthSessionRepository + Clone + Send + Sync + 'static,
+{
+    let headers = req.headers();
+
+    let ClientIp(client_ip) = ClientIp::from_extensions(req.extensions());
+
+    let fingerprint = match extract_fingerprint(headers, client_ip) {
+        Ok(fp) => fp,
+        Err(e) => return Err(AuthError::from(e).into_response()),
+    };
+
+    let Some(token) = platform::cookie::extract_cookie(headers, &state.config.session_cookie_name)
+    else {
+        return Err((StatusCode::UNAUTHORIZED, [("X-Auth-Required", "true")]).into_response());
+    };
+
+    let use_case = CheckSessionUseCase::new(state.repo.clone(), state.config.clone());
+
+    match use_case
+        .get_session_with_recent_reauth(&token, &fingerprint.hash)
+        .await
+    {
+        Ok(session) if session.user_role.is_admin_or_higher() => Ok(next.run(req).await),
+        Ok(session) => {
+            tracing::warn!(
+                session_id = %session.session_id,
+                "Admin route requested without admin role"
+            );
+            Err(StatusCode::FORBIDDEN.into_response())
+        }
+        Err(AuthError::ReauthenticationRequired) => {
+            Err(AuthError::ReauthenticationRequired.into_response())
+        }
+        Err(_) => Err((StatusCode::UNAUTHORIZED, [("X-Auth-Required", "true")]).into_response()),
+    }
 }
 
 /// Middleware that checks auth session but doesn't require it
 /// Sets X-Authenticated header for downstream handlers
+///
+/// The user's role and id are also stored as `ClientRole` / `ClientUserId`
+/// extensions, and the session itself as an `AuthSession` extension so
+/// handlers do not look it up again.
 pub async fn check_auth_session<R>(
     state: AuthMiddlewareState<R>,
     mut req: Request<Body>,
@@ -80,28 +188,54 @@ where
 {
     let headers = req.headers();
 
-    let client_ip = req
-        .extensions()
-        .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
-        .map(|info| info.0.ip());
-
-    let client_ip = extract_client_ip(headers, client_ip);
+    let ClientIp(client_ip) = ClientIp::from_extensions(req.extensions());
 
     let fingerprint = extract_fingerprint(headers, client_ip).ok();
 
     let token = platform::cookie::extract_cookie(headers, &state.config.session_cookie_name);
 
-    let is_authenticated = if let (Some(token), Some(fp)) = (token, fingerprint) {
+    let checked = if let (Some(token), Some(fp)) = (&token, fingerprint) {
         let use_case = CheckSessionUseCase::new(state.repo.clone(), state.config.clone());
-        use_case.is_valid(&token, &fp.hash).await
+        use_case
+            .get_session_with_renewal(token, &fp.hash)
+            .await
+            .ok()
     } else {
-        false
+        None
     };
 
     // Store authentication status in request extensions
-    req.extensions_mut().insert(AuthStatus { is_authenticated });
+    req.extensions_mut().insert(AuthStatus {
+        is_authenticated: checked.is_some(),
+    });
+    let (Some((session, renewed)), Some(token)) = (checked, token) else {
+        return next.run(req).await;
+    };
+
+    let expires_at_ms = session.expires_at_ms;
+    req.extensions_mut()
+        .insert(ClientRole(session.user_role.code().to_string()));
+    req.extensions_mut()
+        .insert(ClientUserId(session.user_id.as_uuid().to_string()));
+    req.extensions_mut().insert(session);
+
+    let mut response = next.run(req).await;
+
+    // Session was extended - re-issue the cookie unless the handler replaced it
+    let cookie_prefix = format!("{}=", state.config.session_cookie_name);
+    let replaced = response
+        .headers()
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .any(|v| v.as_bytes().starts_with(cookie_prefix.as_bytes()));
+    if renewed && !replaced {
+        let cookie = build_session_cookie_until(&state.config, &token, expires_at_ms);
+        if let Ok(value) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(header::SET_COOKIE, value);
+        }
+    }
 
-    next.run(req).await
+    response
 }
 
 /// Authentication status stored in request extensions
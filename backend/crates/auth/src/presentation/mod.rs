@@ -8,5 +8,8 @@ pub mod middleware;
 pub mod router;
 
 pub use handlers::AuthAppState;
-pub use middleware::{AuthMiddlewareState, AuthStatus, check_auth_session, require_auth_session};
-pub use router::{auth_router, auth_router_generic};
+pub use middleware::{
+    AuthMiddlewareState, AuthStatus, check_auth_session, require_admin_session,
+    require_auth_session, require_recent_reauth,
+};
+pub use router::{auth_admin_router, auth_admin_router_generic, auth_router, auth_router_generic};
@@ -2,50 +2,191 @@
 
 use axum::{
     Router,
-    routing::{get, post},
+    body::Body,
+    http::Request,
+    middleware::from_fn,
+    response::IntoResponse,
+    routing::{MethodRouter, Route, get, post},
 };
+use platform::rate_limit::RateLimitBackend;
+use platform::rate_limit_layer::RateLimitLayer;
+use std::convert::Infallible;
 use std::sync::Arc;
+use tower::{Layer, Service};
 
-use crate::application::config::AuthConfig;
+use crate::application::config::{AuthConfig, AuthRateLimits};
 use crate::domain::repository::{AuthRepository, AuthSessionRepository, UserRepository};
 use crate::infra::postgres::PgAuthRepository;
 use crate::presentation::handlers::{self, AuthAppState};
+use crate::presentation::middleware::{AuthMiddlewareState, check_auth_session};
+
+/// Routes that can be protected against automated abuse (e.g. with PoW)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardedRoute {
+    /// POST /signup
+    SignUp,
+    /// POST /signin
+    SignIn,
+}
+
+impl GuardedRoute {
+    /// Action name of the route (`signup`, `signin`)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GuardedRoute::SignUp => "signup",
+            GuardedRoute::SignIn => "signin",
+        }
+    }
+}
 
 /// Create the Auth router with PostgreSQL repository
 pub fn auth_router(repo: PgAuthRepository, config: AuthConfig) -> Router {
+    auth_router_generic(repo, config)
+}
+
+/// Create the Auth admin router (role management)
+///
+/// Must be nested behind an admin-only guard.
+pub fn auth_admin_router(repo: PgAuthRepository, config: AuthConfig) -> Router {
+    auth_admin_router_generic(repo, config)
+}
+
+/// Create a generic Auth admin router for any repository implementation
+pub fn auth_admin_router_generic<R>(repo: R, config: AuthConfig) -> Router
+where
+    R: UserRepository + AuthRepository + AuthSessionRepository + Clone + Send + Sync + 'static,
+{
     let state = AuthAppState {
         repo: Arc::new(repo),
         config: Arc::new(config),
     };
 
     Router::new()
-        .route("/signup", post(handlers::sign_up::<PgAuthRepository>))
-        .route("/signin", post(handlers::sign_in::<PgAuthRepository>))
-        .route("/signout", post(handlers::sign_out::<PgAuthRepository>))
-        .route("/status", get(handlers::session_status::<PgAuthRepository>))
-        .route("/totp/setup", post(handlers::totp_setup::<PgAuthRepository>))
-        .route("/totp/verify", post(handlers::totp_verify::<PgAuthRepository>))
-        .route("/totp/disable", post(handlers::totp_disable::<PgAuthRepository>))
+        .route("/users/{public_id}/role", post(handlers::change_role::<R>))
         .with_state(state)
 }
 
+/// Create the Auth router with a guard in front of sign-up and sign-in
+///
+/// `guard` is called once per [`GuardedRoute`] and the returned layer is
+/// applied to that route only, so other routes stay unaffected.
+///
+/// ## Usage
+/// ```rust,ignore
+/// let router = auth_router_guarded(repo, config, |route| {
+///     from_fn_with_state(pow_guard_for(route.as_str()), require_pow_action::<PgPowRepository>)
+/// });
+/// ```
+pub fn auth_router_guarded<F, L>(repo: PgAuthRepository, config: AuthConfig, guard: F) -> Router
+where
+    F: Fn(GuardedRoute) -> L,
+    L: Layer<Route> + Clone + Send + Sync + 'static,
+    L::Service: Service<Request<Body>, Error = Infallible> + Clone + Send + Sync + 'static,
+    <L::Service as Service<Request<Body>>>::Response: IntoResponse + 'static,
+    <L::Service as Service<Request<Body>>>::Future: Send + 'static,
+{
+    auth_router_guarded_generic(repo, config, guard)
+}
+
+/// Create a generic guarded Auth router for any repository implementation
+pub fn auth_router_guarded_generic<R, F, L>(repo: R, config: AuthConfig, guard: F) -> Router
+where
+    R: UserRepository + AuthRepository + AuthSessionRepository + Clone + Send + Sync + 'static,
+    F: Fn(GuardedRoute) -> L,
+    L: Layer<Route> + Clone + Send + Sync + 'static,
+    L::Service: Service<Request<Body>, Error = Infallible> + Clone + Send + Sync + 'static,
+    <L::Service as Service<Request<Body>>>::Response: IntoResponse + 'static,
+    <L::Service as Service<Request<Body>>>::Future: Send + 'static,
+{
+    let rate_limits = config.rate_limits.clone();
+    let state = AuthAppState {
+        repo: Arc::new(repo),
+        config: Arc::new(config),
+    };
+
+    routes(
+        state,
+        post(handlers::sign_up::<R>).route_layer(guard(GuardedRoute::SignUp)),
+        post(handlers::sign_in::<R>).route_layer(guard(GuardedRoute::SignIn)),
+        rate_limits.as_ref(),
+    )
+}
+
 /// Create a generic Auth router for any repository implementation
 pub fn auth_router_generic<R>(repo: R, config: AuthConfig) -> Router
 where
     R: UserRepository + AuthRepository + AuthSessionRepository + Clone + Send + Sync + 'static,
 {
+    let rate_limits = config.rate_limits.clone();
     let state = AuthAppState {
         repo: Arc::new(repo),
         config: Arc::new(config),
     };
 
+    routes(
+        state,
+        post(handlers::sign_up::<R>),
+        post(handlers::sign_in::<R>),
+        rate_limits.as_ref(),
+    )
+}
+
+/// Rate limits wrap the guards, so rejected floods never reach them
+///
+/// TOTP routes resolve the session once, before the limit (keyed on
+/// `ClientUserId`) and for the handler.
+fn routes<R>(
+    state: AuthAppState<R>,
+    sign_up: MethodRouter<AuthAppState<R>>,
+    sign_in: MethodRouter<AuthAppState<R>>,
+    rate_limits: Option<&AuthRateLimits>,
+) -> Router
+where
+    R: UserRepository + AuthRepository + AuthSessionRepository + Clone + Send + Sync + 'static,
+{
+    let session = AuthMiddlewareState {
+        repo: state.repo.clone(),
+        config: state.config.clone(),
+    };
+    let totp = |route: MethodRouter<AuthAppState<R>>| {
+        let session = session.clone();
+        limited(route, rate_limits.map(|l| &l.totp)).route_layer(from_fn(move |req, next| {
+            check_auth_session(session.clone(), req, next)
+        }))
+    };
     Router::new()
-        .route("/signup", post(handlers::sign_up::<R>))
-        .route("/signin", post(handlers::sign_in::<R>))
+        .route("/signup", limited(sign_up, rate_limits.map(|l| &l.sign_up)))
+        .route("/signin", limited(sign_in, rate_limits.map(|l| &l.sign_in)))
         .route("/signout", post(handlers::sign_out::<R>))
         .route("/status", get(handlers::session_status::<R>))
-        .route("/totp/setup", post(handlers::totp_setup::<R>))
-        .route("/totp/verify", post(handlers::totp_verify::<R>))
-        .route("/totp/disable", post(handlers::totp_disable::<R>))
+        .route("/reauth", post(handlers::reauthenticate::<R>))
+        .route("/password", post(handlers::change_password::<R>))
+        .route("/totp/setup", totp(post(handlers::totp_setup::<R>)))
+        .route("/totp/verify", totp(post(handlers::totp_verify::<R>)))
+        .route("/totp/disable", totp(post(handlers::totp_disable::<R>)))
         .with_state(state)
 }
+
+fn limited<S>(
+    route: MethodRouter<S>,
+    limit: Option<&RateLimitLayer<RateLimitBackend>>,
+) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    match limit {
+        Some(limit) => route.route_layer(limit.clone()),
+        None => route,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guarded_route_names() {
+        assert_eq!(GuardedRoute::SignUp.as_str(), "signup");
+        assert_eq!(GuardedRoute::SignIn.as_str(), "signin");
+    }
+}
@@ -0,0 +1,729 @@
+//! Unit tests for Auth crate (use cases and routers against an in-memory store)
+
+#[cfg(test)]
+pub(crate) mod memory {
+    use crate::domain::entity::{auth::Auth, auth_session::AuthSession, user::User};
+    use crate::domain::repository::{AuthRepository, AuthSessionRepository, UserRepository};
+    use crate::domain::value_object::{
+        public_id::PublicId, user_id::UserId, user_name::UserName, user_role::UserRole,
+    };
+    use crate::error::AuthResult;
+    use chrono::{DateTime, Utc};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
+
+    #[derive(Default)]
+    struct Tables {
+        users: HashMap<Uuid, User>,
+        auths: HashMap<Uuid, Auth>,
+        sessions: HashMap<Uuid, AuthSession>,
+        session_lookups: usize,
+    }
+
+    /// In-memory user, credential and session store
+    #[derive(Clone, Default)]
+    pub(crate) struct MemoryAuthStore(Arc<Mutex<Tables>>);
+
+    impl MemoryAuthStore {
+        /// Apply `f` to the stored credentials of a user
+        pub(crate) fn update_auth(&self, public_id: &str, f: impl FnOnce(&mut Auth)) {
+            let mut tables = self.0.lock().unwrap();
+            let user_id = *tables
+                .users
+                .values()
+                .find(|u| u.public_id.as_str() == public_id)
+                .unwrap()
+                .user_id
+                .as_uuid();
+            f(tables.auths.get_mut(&user_id).unwrap());
+        }
+
+        /// Apply `f` to every stored session
+        pub(crate) fn update_sessions(&self, f: impl FnMut(&mut AuthSession)) {
+            self.0.lock().unwrap().sessions.values_mut().for_each(f);
+        }
+
+        /// Number of session lookups so far
+        pub(crate) fn session_lookups(&self) -> usize {
+            self.0.lock().unwrap().session_lookups
+        }
+    }
+
+    impl UserRepository for MemoryAuthStore {
+        async fn create(&self, user: &User) -> AuthResult<()> {
+            let mut tables = self.0.lock().unwrap();
+            tables.users.insert(*user.user_id.as_uuid(), user.clone());
+            Ok(())
+        }
+
+        async fn find_by_id(&self, user_id: &UserId) -> AuthResult<Option<User>> {
+            Ok(self.0.lock().unwrap().users.get(user_id.as_uuid()).cloned())
+        }
+
+        async fn find_by_public_id(&self, public_id: &PublicId) -> AuthResult<Option<User>> {
+            let tables = self.0.lock().unwrap();
+            Ok(tables
+                .users
+                .values()
+                .find(|u| u.public_id == *public_id)
+                .cloned())
+        }
+
+        async fn find_by_user_name(&self, user_name: &UserName) -> AuthResult<Option<User>> {
+            let tables = self.0.lock().unwrap();
+            Ok(tables
+                .users
+                .values()
+                .find(|u| u.user_name.as_str() == user_name.as_str())
+                .cloned())
+        }
+
+        async fn exists_by_user_name(&self, user_name: &UserName) -> AuthResult<bool> {
+            UserRepository::find_by_user_name(self, user_name)
+                .await
+                .map(|u| u.is_some())
+        }
+
+        async fn update(&self, user: &User) -> AuthResult<()> {
+            UserRepository::create(self, user).await
+        }
+    }
+
+    impl AuthRepository for MemoryAuthStore {
+        async fn create(&self, auth: &Auth) -> AuthResult<()> {
+            let mut tables = self.0.lock().unwrap();
+            tables.auths.insert(*auth.user_id.as_uuid(), auth.clone());
+            Ok(())
+        }
+
+        async fn find_by_user_id(&self, user_id: &UserId) -> AuthResult<Option<Auth>> {
+            Ok(self.0.lock().unwrap().auths.get(user_id.as_uuid()).cloned())
+        }
+
+        async fn update(&self, auth: &Auth) -> AuthResult<()> {
+            AuthRepository::create(self, auth).await
+        }
+    }
+
+    impl AuthSessionRepository for MemoryAuthStore {
+        async fn create(&self, session: &AuthSession) -> AuthResult<()> {
+            let mut tables = self.0.lock().unwrap();
+            tables.sessions.insert(session.session_id, session.clone());
+            Ok(())
+        }
+
+        async fn find_by_id(
+            &self,
+            session_id: Uuid,
+            fingerprint_hash: &[u8],
+        ) -> AuthResult<Option<AuthSession>> {
+            let mut tables = self.0.lock().unwrap();
+            tables.session_lookups += 1;
+            Ok(tables
+                .sessions
+                .get(&session_id)
+                .filter(|s| s.client_fingerprint_hash == fingerprint_hash)
+                .cloned())
+        }
+
+        async fn find_by_user_id(&self, user_id: &UserId) -> AuthResult<Vec<AuthSession>> {
+            let tables = self.0.lock().unwrap();
+            Ok(tables
+                .sessions
+                .values()
+                .filter(|s| s.user_id.as_uuid() == user_id.as_uuid())
+                .cloned()
+                .collect())
+        }
+
+        async fn update(&self, session: &AuthSession) -> AuthResult<()> {
+            let mut tables = self.0.lock().unwrap();
+            if let Some(stored) = tables.sessions.get_mut(&session.session_id) {
+                stored.last_activity_at = session.last_activity_at;
+                stored.expires_at_ms = session.expires_at_ms;
+            }
+            Ok(())
+        }
+
+        async fn update_reauthenticated_at(
+            &self,
+            session_id: Uuid,
+            reauthenticated_at: DateTime<Utc>,
+        ) -> AuthResult<()> {
+            let mut tables = self.0.lock().unwrap();
+            if let Some(stored) = tables.sessions.get_mut(&session_id) {
+                stored.last_reauthenticated_at = Some(reauthenticated_at);
+            }
+            Ok(())
+        }
+
+        async fn rotate_id(&self, old_session_id: Uuid, new_session_id: Uuid) -> AuthResult<bool> {
+            let mut tables = self.0.lock().unwrap();
+            let Some(mut session) = tables.sessions.remove(&old_session_id) else {
+                return Ok(false);
+            };
+            session.session_id = new_session_id;
+            tables.sessions.insert(new_session_id, session);
+            Ok(true)
+        }
+
+        async fn update_role_for_user(
+            &self,
+            user_id: &UserId,
+            user_role: UserRole,
+        ) -> AuthResult<u64> {
+            let mut tables = self.0.lock().unwrap();
+            let mut updated = 0;
+            for session in tables.sessions.values_mut() {
+                if session.user_id.as_uuid() == user_id.as_uuid() {
+                    session.user_role = user_role;
+                    updated += 1;
+                }
+            }
+            Ok(updated)
+        }
+
+        async fn delete(&self, session_id: Uuid) -> AuthResult<()> {
+            self.0.lock().unwrap().sessions.remove(&session_id);
+            Ok(())
+        }
+
+        async fn delete_all_for_user(
+            &self,
+            user_id: &UserId,
+            except: Option<Uuid>,
+        ) -> AuthResult<u64> {
+            let mut tables = self.0.lock().unwrap();
+            let before = tables.sessions.len();
+            tables
+                .sessions
+                .retain(|id, s| s.user_id.as_uuid() != user_id.as_uuid() || Some(*id) == except);
+            Ok((before - tables.sessions.len()) as u64)
+        }
+
+        async fn cleanup_expired(&self) -> AuthResult<u64> {
+            let mut tables = self.0.lock().unwrap();
+            let before = tables.sessions.len();
+            tables.sessions.retain(|_, s| !s.is_expired());
+            Ok((before - tables.sessions.len()) as u64)
+        }
+    }
+}
+
+#[cfg(test)]
+mod role_change_tests {
+    use super::memory::MemoryAuthStore;
+    use crate::application::config::AuthConfig;
+    use crate::application::{
+        CheckSessionUseCase, ClientFingerprint, SignInInput, SignInUseCase, SignUpInput,
+        SignUpUseCase,
+    };
+    use crate::error::AuthError;
+    use crate::presentation::router::auth_admin_router_generic;
+    use axum::Router;
+    use axum::body::Body;
+    use axum::http::{Method, Request, StatusCode, header};
+    use platform::crypto::sha256;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    const USER_AGENT: &str = "role-test";
+    const USER_NAME: &str = "alice";
+    const PASSWORD: &str = "correct-horse-battery-staple";
+
+    fn fingerprint() -> ClientFingerprint {
+        ClientFingerprint::new(
+            sha256(USER_AGENT.as_bytes()),
+            None,
+            Some(USER_AGENT.to_string()),
+        )
+    }
+
+    /// Sign up a user with TOTP enabled and return its public ID
+    async fn sign_up(repo: &MemoryAuthStore, config: &Arc<AuthConfig>) -> String {
+        let repo = Arc::new(repo.clone());
+        let output = SignUpUseCase::new(repo.clone(), repo.clone(), config.clone())
+            .execute(SignUpInput {
+                user_name: USER_NAME.to_string(),
+                password: PASSWORD.to_string(),
+            })
+            .await
+            .unwrap();
+        repo.update_auth(&output.public_id, |auth| {
+            auth.setup_totp();
+            auth.enable_totp();
+        });
+        output.public_id
+    }
+
+    async fn sign_in(repo: &MemoryAuthStore, config: &Arc<AuthConfig>, public_id: &str) -> String {
+        let mut totp_code = None;
+        repo.update_auth(public_id, |auth| {
+            totp_code = auth
+                .totp_secret
+                .as_ref()
+                .map(|secret| secret.generate_current(USER_NAME).unwrap());
+        });
+        let repo = Arc::new(repo.clone());
+        let output = SignInUseCase::new(repo.clone(), repo.clone(), repo.clone(), config.clone())
+            .execute(
+                SignInInput {
+                    identifier: USER_NAME.to_string(),
+                    password: PASSWORD.to_string(),
+                    remember_me: false,
+                    totp_code,
+                },
+                fingerprint(),
+            )
+            .await
+            .unwrap();
+        assert!(!output.requires_2fa);
+        output.session_token
+    }
+
+    async fn session_role(
+        repo: &MemoryAuthStore,
+        config: &Arc<AuthConfig>,
+        token: &str,
+    ) -> Result<String, AuthError> {
+        CheckSessionUseCase::new(Arc::new(repo.clone()), config.clone())
+            .execute(token, &fingerprint().hash)
+            .await
+            .map(|info| info.user_role)
+    }
+
+    async fn change_role(app: &Router, public_id: &str, role: &str) -> StatusCode {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/users/{public_id}/role"))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(format!(r#"{{"userRole":"{role}"}}"#)))
+            .unwrap();
+        app.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_elevation_revokes_sessions() {
+        let config = Arc::new(AuthConfig::development());
+        let repo = MemoryAuthStore::default();
+        let app = auth_admin_router_generic(repo.clone(), (*config).clone());
+
+        let public_id = sign_up(&repo, &config).await;
+        let old_token = sign_in(&repo, &config, &public_id).await;
+        assert_eq!(
+            session_role(&repo, &config, &old_token).await.unwrap(),
+            "user"
+        );
+
+        assert_eq!(
+            change_role(&app, &public_id, "moderator").await,
+            StatusCode::NO_CONTENT
+        );
+
+        // The session minted before the change is gone
+        assert!(matches!(
+            session_role(&repo, &config, &old_token).await,
+            Err(AuthError::SessionInvalid)
+        ));
+
+        // A new sign-in carries the new role under a new session ID
+        let new_token = sign_in(&repo, &config, &public_id).await;
+        assert_ne!(new_token, old_token);
+        assert_eq!(
+            session_role(&repo, &config, &new_token).await.unwrap(),
+            "moderator"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_demotion_replaces_role_snapshot() {
+        let config = Arc::new(AuthConfig::development());
+        let repo = MemoryAuthStore::default();
+        let app = auth_admin_router_generic(repo.clone(), (*config).clone());
+
+        let public_id = sign_up(&repo, &config).await;
+        assert_eq!(
+            change_role(&app, &public_id, "admin").await,
+            StatusCode::NO_CONTENT
+        );
+        let token = sign_in(&repo, &config, &public_id).await;
+        assert_eq!(session_role(&repo, &config, &token).await.unwrap(), "admin");
+
+        assert_eq!(
+            change_role(&app, &public_id, "user").await,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(session_role(&repo, &config, &token).await.unwrap(), "user");
+    }
+
+    #[tokio::test]
+    async fn test_rejected_role_changes() {
+        let config = Arc::new(AuthConfig::development());
+        let repo = MemoryAuthStore::default();
+        let app = auth_admin_router_generic(repo.clone(), (*config).clone());
+
+        let public_id = sign_up(&repo, &config).await;
+        assert_eq!(
+            change_role(&app, &public_id, "root").await,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            change_role(&app, &public_id, "super_admin").await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            change_role(&app, "0123456789abcdefghi01", "admin").await,
+            StatusCode::NOT_FOUND
+        );
+    }
+}
+
+#[cfg(test)]
+mod guarded_router_tests {
+    use super::memory::MemoryAuthStore;
+    use crate::application::config::AuthConfig;
+    use crate::presentation::router::auth_router_guarded_generic;
+    use axum::Router;
+    use axum::body::Body;
+    use axum::http::{Method, Request, StatusCode, header};
+    use axum::middleware::from_fn_with_state;
+    use platform::crypto::sha256;
+    use pow::PowConfig;
+    use pow::PowResult;
+    use pow::domain::algorithm::PowAlgorithm;
+    use pow::domain::repository::PowSessionRepository;
+    use pow::middleware::{POW_PROOF_HEADER, PowActionGuardState, require_pow_action};
+    use pow::models::{Challenge, ClientFingerprint, PowAction, PowSession};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    const USER_AGENT: &str = "guarded-test";
+    const CREDENTIALS: &str = r#"{"userName":"alice","password":"correct-horse-battery-staple"}"#;
+    const SIGN_IN: &str = r#"{"identifier":"alice","password":"correct-horse-battery-staple"}"#;
+
+    /// In-memory PoW session store
+    #[derive(Clone, Default)]
+    struct MemoryPowSessions(Arc<Mutex<HashMap<Uuid, PowSession>>>);
+
+    impl PowSessionRepository for MemoryPowSessions {
+        async fn create(&self, pow_session: &PowSession) -> PowResult<()> {
+            let mut sessions = self.0.lock().unwrap();
+            sessions.insert(pow_session.id, pow_session.clone());
+            Ok(())
+        }
+
+        async fn get(
+            &self,
+            pow_session_id: Uuid,
+            fingerprint: &ClientFingerprint,
+        ) -> PowResult<Option<PowSession>> {
+            let sessions = self.0.lock().unwrap();
+            Ok(sessions
+                .get(&pow_session_id)
+                .filter(|s| s.client_fingerprint_hash == fingerprint.hash_vec())
+                .cloned())
+        }
+
+        async fn spend_request(
+            &self,
+            pow_session_id: Uuid,
+            fingerprint: &ClientFingerprint,
+        ) -> PowResult<Option<PowSession>> {
+            let sessions = self.0.lock().unwrap();
+            Ok(sessions
+                .get(&pow_session_id)
+                .filter(|s| {
+                    s.is_general()
+                        && !s.is_expired()
+                        && s.client_fingerprint_hash == fingerprint.hash_vec()
+                })
+                .cloned())
+        }
+
+        async fn renew(
+            &self,
+            _pow_session_id: Uuid,
+            _fingerprint: &ClientFingerprint,
+            _renewed: &PowSession,
+        ) -> PowResult<Option<PowSession>> {
+            Ok(None)
+        }
+
+        async fn consume(
+            &self,
+            pow_session_id: Uuid,
+            fingerprint: &ClientFingerprint,
+            action: &PowAction,
+        ) -> PowResult<Option<PowSession>> {
+            let mut sessions = self.0.lock().unwrap();
+            let valid = sessions.get(&pow_session_id).is_some_and(|s| {
+                s.action.as_ref() == Some(action)
+                    && s.client_fingerprint_hash == fingerprint.hash_vec()
+            });
+            Ok(valid.then(|| sessions.remove(&pow_session_id)).flatten())
+        }
+
+        async fn delete(&self, pow_session_id: Uuid) -> PowResult<()> {
+            self.0.lock().unwrap().remove(&pow_session_id);
+            Ok(())
+        }
+    }
+
+    /// Store a solved session (a proof if `action` is set) and return its token
+    fn pow_token(repo: &MemoryPowSessions, config: &PowConfig, action: Option<&str>) -> String {
+        let mut challenge = Challenge::new(
+            vec![0u8; 32],
+            PowAlgorithm::Sha256,
+            1,
+            60_000,
+            sha256(USER_AGENT.as_bytes()).to_vec(),
+            None,
+        );
+        if let Some(action) = action {
+            challenge = challenge.with_action(PowAction::new(action).unwrap(), None);
+        }
+        let session = PowSession::new(&challenge, 60_000);
+        repo.0.lock().unwrap().insert(session.id, session.clone());
+        config.session_keys.sign(
+            session.id,
+            session.created_at.timestamp_millis(),
+            session.expires_at_ms,
+        )
+    }
+
+    /// Auth router with the PoW action guard as wired in the API server
+    fn app(pow_repo: &MemoryPowSessions, pow_config: &Arc<PowConfig>) -> Router {
+        let pow_repo = Arc::new(pow_repo.clone());
+        auth_router_guarded_generic(
+            MemoryAuthStore::default(),
+            AuthConfig::development(),
+            |route| {
+                let action = PowAction::new(route.as_str()).unwrap();
+                from_fn_with_state(
+                    PowActionGuardState::new(pow_repo.clone(), pow_config.clone(), action)
+                        .or_session(),
+                    require_pow_action::<MemoryPowSessions>,
+                )
+            },
+        )
+    }
+
+    fn request(uri: &str) -> axum::http::request::Builder {
+        Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header(header::USER_AGENT, USER_AGENT)
+            .header(header::CONTENT_TYPE, "application/json")
+    }
+
+    async fn send(
+        app: &Router,
+        req: axum::http::request::Builder,
+        body: &'static str,
+    ) -> axum::response::Response {
+        app.clone()
+            .oneshot(req.body(Body::from(body)).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_guarded_routes_require_pow() {
+        let pow_config = Arc::new(PowConfig::development());
+        let app = app(&MemoryPowSessions::default(), &pow_config);
+
+        for (uri, body, action) in [
+            ("/signup", CREDENTIALS, "signup"),
+            ("/signin", SIGN_IN, "signin"),
+        ] {
+            let response = send(&app, request(uri), body).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(response.headers()["x-pow-required"], "true");
+            assert_eq!(response.headers()["x-pow-action"], action);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_guarded_routes_accept_proof() {
+        let pow_config = Arc::new(PowConfig::development());
+        let pow_repo = MemoryPowSessions::default();
+        let app = app(&pow_repo, &pow_config);
+
+        let proof = pow_token(&pow_repo, &pow_config, Some("signup"));
+        let response = send(
+            &app,
+            request("/signup").header(POW_PROOF_HEADER, &proof),
+            CREDENTIALS,
+        )
+        .await;
+        assert!(response.status().is_success());
+
+        // Proofs are single-use and bound to their action
+        let response = send(
+            &app,
+            request("/signup").header(POW_PROOF_HEADER, &proof),
+            CREDENTIALS,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let proof = pow_token(&pow_repo, &pow_config, Some("signup"));
+        let response = send(
+            &app,
+            request("/signin").header(POW_PROOF_HEADER, &proof),
+            SIGN_IN,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()["x-pow-action"], "signin");
+
+        let proof = pow_token(&pow_repo, &pow_config, Some("signin"));
+        let response = send(
+            &app,
+            request("/signin").header(POW_PROOF_HEADER, &proof),
+            SIGN_IN,
+        )
+        .await;
+        assert!(response.status().is_success());
+        assert!(response.headers().get("x-pow-required").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_guarded_routes_accept_session() {
+        let pow_config = Arc::new(PowConfig::development());
+        let pow_repo = MemoryPowSessions::default();
+        let app = app(&pow_repo, &pow_config);
+
+        let token = pow_token(&pow_repo, &pow_config, None);
+        let cookie = format!("{}={token}", pow_config.session_cookie_name);
+        for (uri, body) in [("/signup", CREDENTIALS), ("/signin", SIGN_IN)] {
+            let response = send(&app, request(uri).header(header::COOKIE, &cookie), body).await;
+            assert!(
+                response.status().is_success(),
+                "{uri}: {}",
+                response.status()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod totp_route_tests {
+    use super::memory::MemoryAuthStore;
+    use crate::application::config::AuthConfig;
+    use crate::application::{
+        ClientFingerprint, SignInInput, SignInUseCase, SignUpInput, SignUpUseCase,
+    };
+    use crate::presentation::router::auth_router_generic;
+    use axum::Router;
+    use axum::body::Body;
+    use axum::http::{Method, Request, StatusCode, header};
+    use axum::response::Response;
+    use chrono::Utc;
+    use platform::crypto::sha256;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    const USER_AGENT: &str = "totp-limit-test";
+    const PASSWORD: &str = "correct-horse-battery-staple";
+
+    /// Sign up and sign in a user, returning its session token
+    async fn session(repo: &MemoryAuthStore, config: &Arc<AuthConfig>, user_name: &str) -> String {
+        let repo = Arc::new(repo.clone());
+        SignUpUseCase::new(repo.clone(), repo.clone(), config.clone())
+            .execute(SignUpInput {
+                user_name: user_name.to_string(),
+                password: PASSWORD.to_string(),
+            })
+            .await
+            .unwrap();
+        SignInUseCase::new(repo.clone(), repo.clone(), repo.clone(), config.clone())
+            .execute(
+                SignInInput {
+                    identifier: user_name.to_string(),
+                    password: PASSWORD.to_string(),
+                    remember_me: false,
+                    totp_code: None,
+                },
+                ClientFingerprint::new(
+                    sha256(USER_AGENT.as_bytes()),
+                    None,
+                    Some(USER_AGENT.to_string()),
+                ),
+            )
+            .await
+            .unwrap()
+            .session_token
+    }
+
+    async fn totp_setup(app: &Router, config: &AuthConfig, token: &str) -> Response {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/totp/setup")
+            .header(header::USER_AGENT, USER_AGENT)
+            .header(
+                header::COOKIE,
+                format!("{}={token}", config.session_cookie_name),
+            )
+            .body(Body::empty())
+            .unwrap();
+        app.clone().oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_totp_limit_is_per_user() {
+        let config = Arc::new(AuthConfig::development());
+        let repo = MemoryAuthStore::default();
+        let app = auth_router_generic(repo.clone(), (*config).clone());
+
+        // Both users share an IP (none here), so only the user key separates them
+        let alice = session(&repo, &config, "alice").await;
+        let bob = session(&repo, &config, "bob").await;
+
+        for _ in 0..10 {
+            assert_ne!(
+                totp_setup(&app, &config, &alice).await.status(),
+                StatusCode::TOO_MANY_REQUESTS
+            );
+        }
+        assert_eq!(
+            totp_setup(&app, &config, &alice).await.status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+
+        assert_ne!(
+            totp_setup(&app, &config, &bob).await.status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_totp_session_is_resolved_once_and_renewed() {
+        let config = Arc::new(AuthConfig::development());
+        let repo = MemoryAuthStore::default();
+        let app = auth_router_generic(repo.clone(), (*config).clone());
+
+        let token = session(&repo, &config, "alice").await;
+        // A remember-me session past half its TTL is extended on use
+        repo.update_sessions(|session| {
+            session.remember_me = true;
+            session.expires_at_ms = Utc::now().timestamp_millis() + 60_000;
+        });
+
+        let lookups = repo.session_lookups();
+        let response = totp_setup(&app, &config, &token).await;
+        assert_ne!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(repo.session_lookups() - lookups, 1);
+
+        let cookie = response.headers().get(header::SET_COOKIE).unwrap();
+        assert!(
+            cookie
+                .to_str()
+                .unwrap()
+                .starts_with(&format!("{}={token};", config.session_cookie_name))
+        );
+    }
+}
@@ -1,11 +1,20 @@
 //! Client identification utilities
 //!
-//! Common functions for identifying clients via HTTP headers.
+//! Common functions for identifying clients via HTTP headers and the
+//! connection, behind trusted reverse proxies.
 
-use axum::http::{HeaderMap, header};
-use std::net::IpAddr;
+use axum::body::Body;
+use axum::extract::{ConnectInfo, FromRequestParts, State};
+use axum::http::request::Parts;
+use axum::http::{Extensions, HeaderMap, Request, header};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
 
 use crate::crypto::sha256;
+use crate::net::IpCidr;
 
 /// Client fingerprint derived from request headers
 ///
@@ -14,7 +23,7 @@ use crate::crypto::sha256;
 pub struct ClientFingerprint {
     /// SHA-256 hash of the User-Agent header
     pub hash: [u8; 32],
-    /// Client IP address (from X-Forwarded-For or direct connection)
+    /// Client IP address (see [`ClientIp`])
     pub ip: Option<IpAddr>,
     /// Original User-Agent string (for logging/display)
     pub user_agent: Option<String>,
@@ -37,6 +46,88 @@ impl ClientFingerprint {
     }
 }
 
+/// Groups client IPs into networks for limits and bans
+///
+/// A single party usually controls a whole network (an IPv6 site is often
+/// handed a /48 or /64), so keying on the exact address lets it dodge
+/// limits by rotating addresses. IPv4-mapped IPv6 addresses are grouped as
+/// IPv4.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientNetworkKey {
+    v4_prefix_len: u8,
+    v6_prefix_len: u8,
+}
+
+impl Default for ClientNetworkKey {
+    /// /24 for IPv4, /48 for IPv6
+    fn default() -> Self {
+        Self {
+            v4_prefix_len: 24,
+            v6_prefix_len: 48,
+        }
+    }
+}
+
+impl ClientNetworkKey {
+    /// Returns `None` if a prefix length exceeds the address width
+    pub fn new(v4_prefix_len: u8, v6_prefix_len: u8) -> Option<Self> {
+        (v4_prefix_len <= 32 && v6_prefix_len <= 128).then_some(Self {
+            v4_prefix_len,
+            v6_prefix_len,
+        })
+    }
+
+    pub fn v4_prefix_len(&self) -> u8 {
+        self.v4_prefix_len
+    }
+
+    pub fn v6_prefix_len(&self) -> u8 {
+        self.v6_prefix_len
+    }
+
+    /// Network containing `ip`
+    pub fn network(&self, ip: IpAddr) -> IpCidr {
+        let (ip, prefix_len) = match ip {
+            IpAddr::V4(_) => (ip, self.v4_prefix_len),
+            IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+                Some(v4) => (IpAddr::V4(v4), self.v4_prefix_len),
+                None => (ip, self.v6_prefix_len),
+            },
+        };
+        IpCidr::new(ip, prefix_len).expect("prefix lengths are validated in new")
+    }
+
+    /// Network of the fingerprint's IP in CIDR notation (e.g. `203.0.113.0/24`)
+    pub fn key(&self, fingerprint: &ClientFingerprint) -> Option<String> {
+        fingerprint.ip.map(|ip| self.network(ip).to_string())
+    }
+
+    /// Hash identifying one client: its fingerprint within its network
+    ///
+    /// Clients sharing a User-Agent are told apart by network, so limits
+    /// and bans of one client do not hit everyone using the same browser.
+    /// Without a client IP this is the fingerprint hash.
+    pub fn client_hash(&self, fingerprint: &ClientFingerprint) -> [u8; 32] {
+        match self.key(fingerprint) {
+            Some(network) => sha256(&[&fingerprint.hash[..], network.as_bytes()].concat()),
+            None => fingerprint.hash,
+        }
+    }
+}
+
+/// Role of the authenticated user making the request
+///
+/// Inserted into the request extensions by the auth middleware so other
+/// crates can act on it without depending on the auth crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientRole(pub String);
+
+/// Id of the authenticated user making the request
+///
+/// Inserted next to [`ClientRole`], e.g. for per-user rate limits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientUserId(pub String);
+
 /// Error when extracting client fingerprint
 #[derive(Debug, Clone, thiserror::Error)]
 pub enum FingerprintError {
@@ -51,7 +142,7 @@ pub enum FingerprintError {
 ///
 /// ## Arguments
 /// * `headers` - HTTP request headers
-/// * `client_ip` - Client IP address (from connection or X-Forwarded-For)
+/// * `client_ip` - Client IP address (see [`ClientIp`])
 ///
 /// ## Returns
 /// * `Ok(ClientFingerprint)` - Successfully extracted fingerprint
@@ -74,27 +165,180 @@ pub fn extract_fingerprint(
     ))
 }
 
-/// Extract client IP address from headers
-///
-/// Checks X-Forwarded-For header first (for reverse proxy setups),
-/// then falls back to direct connection IP.
+/// Reverse proxies whose forwarding headers are believed
 ///
-/// ## Arguments
-/// * `headers` - HTTP request headers
-/// * `direct_ip` - Direct connection IP address
+/// Without any, forwarding headers are ignored and the client is the peer
+/// of the connection. Otherwise the client is the right-most address of
+/// the forwarding chain (peer included) that is not a trusted proxy:
+/// everything left of it was written by the client and can be spoofed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustedProxies {
+    /// Networks of trusted proxies
+    pub networks: Vec<IpCidr>,
+    /// Proxies in front of the app that are trusted whatever their address
+    /// (e.g. a cloud load balancer), counted from the connection peer
+    pub hops: usize,
+    /// Header the proxies write the chain to
+    pub header: ForwardingHeader,
+}
+
+/// Forwarding header written by the trusted proxies
 ///
-/// ## Returns
-/// The client IP address, or None if not determinable
-pub fn extract_client_ip(headers: &HeaderMap, direct_ip: Option<IpAddr>) -> Option<IpAddr> {
-    // Check X-Forwarded-For header (first IP in the list)
-    if let Some(xff) = headers.get("x-forwarded-for").and_then(|v| v.to_str().ok()) {
-        if let Some(first_ip) = xff.split(',').next() {
-            if let Ok(ip) = first_ip.trim().parse::<IpAddr>() {
+/// Only this header is read: proxies pass the others through untouched,
+/// so a client could otherwise pick its own address with any of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ForwardingHeader {
+    /// `X-Forwarded-For: client, proxy1`
+    #[default]
+    XForwardedFor,
+    /// `Forwarded: for=client, for=proxy1` (RFC 7239)
+    Forwarded,
+    /// `X-Real-IP: client`
+    XRealIp,
+}
+
+impl ForwardingHeader {
+    /// Parse a header name (case-insensitive), e.g. `x-forwarded-for`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "x-forwarded-for" => Some(Self::XForwardedFor),
+            "forwarded" => Some(Self::Forwarded),
+            "x-real-ip" => Some(Self::XRealIp),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::XForwardedFor => "x-forwarded-for",
+            Self::Forwarded => "forwarded",
+            Self::XRealIp => "x-real-ip",
+        }
+    }
+
+    /// Addresses the proxies reported, client first (`None` = not an IP)
+    fn chain(&self, headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+        let entries = headers
+            .get_all(self.as_str())
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty());
+
+        match self {
+            Self::Forwarded => entries
+                .map(|element| {
+                    element
+                        .split(';')
+                        .filter_map(|pair| pair.split_once('='))
+                        .find(|(name, _)| name.trim().eq_ignore_ascii_case("for"))
+                        .and_then(|(_, node)| parse_node(node))
+                })
+                .collect(),
+            Self::XForwardedFor | Self::XRealIp => entries.map(parse_node).collect(),
+        }
+    }
+}
+
+impl TrustedProxies {
+    pub fn is_enabled(&self) -> bool {
+        !self.networks.is_empty() || self.hops > 0
+    }
+
+    /// Resolve the client IP of a request received from `peer`
+    ///
+    /// The chain is read from [`Self::header`] only. Returns `None` if the
+    /// client's entry is not an IP address (e.g. `for=unknown`).
+    pub fn client_ip(&self, headers: &HeaderMap, peer: Option<IpAddr>) -> Option<IpAddr> {
+        let peer = peer?;
+        if !self.is_enabled() {
+            return Some(peer);
+        }
+
+        let mut chain = self.header.chain(headers);
+        chain.push(Some(peer));
+
+        let mut nearest = peer;
+        for (hop, entry) in chain.into_iter().rev().enumerate() {
+            let ip = entry?;
+            if hop >= self.hops && !self.is_trusted(&ip) {
                 return Some(ip);
             }
+            nearest = ip;
         }
+        // Every entry is a proxy: the left-most one is the closest to the client
+        Some(nearest)
+    }
+
+    fn is_trusted(&self, ip: &IpAddr) -> bool {
+        self.networks.iter().any(|network| network.contains(ip))
+    }
+}
+
+/// Parse a forwarded node: `192.0.2.1`, `192.0.2.1:8080`, `"[2001:db8::1]:8080"`
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+    if let Ok(ip) = node.parse() {
+        return Some(ip);
+    }
+    if let Ok(addr) = node.parse::<SocketAddr>() {
+        return Some(addr.ip());
+    }
+    node.strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .and_then(|ip| ip.parse().ok())
+}
+
+/// Client IP of a request
+///
+/// Set by the [`resolve_client_ip`] middleware. Without it, the connection
+/// peer is used and forwarding headers are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl ClientIp {
+    pub fn from_extensions(extensions: &Extensions) -> Self {
+        extensions.get::<ClientIp>().copied().unwrap_or_else(|| {
+            ClientIp(
+                extensions
+                    .get::<ConnectInfo<SocketAddr>>()
+                    .map(|info| info.0.ip()),
+            )
+        })
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_extensions(&parts.extensions))
     }
-    direct_ip
+}
+
+/// Middleware resolving the [`ClientIp`] of every request
+///
+/// ## Usage
+/// ```rust,ignore
+/// let proxies = Arc::new(TrustedProxies {
+///     networks: parse_cidr_list("10.0.0.0/8")?,
+///     ..TrustedProxies::default()
+/// });
+/// let app = app.layer(from_fn_with_state(proxies, resolve_client_ip));
+/// ```
+pub async fn resolve_client_ip(
+    State(proxies): State<Arc<TrustedProxies>>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Response {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip());
+    let client_ip = proxies.client_ip(req.headers(), peer);
+    req.extensions_mut().insert(ClientIp(client_ip));
+    next.run(req).await
 }
 
 #[cfg(test)]
@@ -123,23 +367,209 @@ mod tests {
     }
 
     #[test]
-    fn test_extract_client_ip_xff() {
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            "x-forwarded-for",
-            HeaderValue::from_static("192.168.1.1, 10.0.0.1"),
+    fn test_client_network_key() {
+        let key = ClientNetworkKey::default();
+        let network = |ip: &str| key.network(ip.parse().unwrap()).to_string();
+        assert_eq!(network("203.0.113.77"), "203.0.113.0/24");
+        assert_eq!(network("2001:db8:abcd:12::1"), "2001:db8:abcd::/48");
+        assert_eq!(network("::ffff:203.0.113.77"), "203.0.113.0/24");
+
+        let key = ClientNetworkKey::new(32, 64).unwrap();
+        let a = key.network("2001:db8:abcd:12::1".parse().unwrap());
+        let b = key.network("2001:db8:abcd:12:ffff::9".parse().unwrap());
+        assert_eq!(a, b);
+        assert_eq!(a.to_string(), "2001:db8:abcd:12::/64");
+
+        let fp = ClientFingerprint::new([0u8; 32], Some("198.51.100.7".parse().unwrap()), None);
+        assert_eq!(key.key(&fp).as_deref(), Some("198.51.100.7/32"));
+        let fp = ClientFingerprint::new([0u8; 32], None, None);
+        assert_eq!(key.key(&fp), None);
+
+        assert!(ClientNetworkKey::new(33, 64).is_none());
+        assert!(ClientNetworkKey::new(24, 129).is_none());
+    }
+
+    #[test]
+    fn test_client_hash() {
+        let key = ClientNetworkKey::default();
+        let fp = |ip: &str| ClientFingerprint::new([7u8; 32], Some(ip.parse().unwrap()), None);
+
+        // Same User-Agent: told apart by network, grouped within one
+        assert_ne!(
+            key.client_hash(&fp("203.0.113.7")),
+            key.client_hash(&fp("198.51.100.7"))
         );
+        assert_eq!(
+            key.client_hash(&fp("203.0.113.7")),
+            key.client_hash(&fp("203.0.113.9"))
+        );
+        assert_ne!(key.client_hash(&fp("203.0.113.7")), [7u8; 32]);
 
-        let ip = extract_client_ip(&headers, None);
-        assert_eq!(ip, Some("192.168.1.1".parse().unwrap()));
+        let fp = ClientFingerprint::new([7u8; 32], None, None);
+        assert_eq!(key.client_hash(&fp), [7u8; 32]);
     }
 
     #[test]
-    fn test_extract_client_ip_direct() {
-        let headers = HeaderMap::new();
-        let direct: IpAddr = "127.0.0.1".parse().unwrap();
+    fn test_client_ip_ignores_headers_without_trusted_proxies() {
+        let headers = headers(&[("x-forwarded-for", "192.0.2.1")]);
+        let peer = ip("203.0.113.9");
+
+        let ip = TrustedProxies::default().client_ip(&headers, Some(peer));
+        assert_eq!(ip, Some(peer));
+    }
+
+    #[test]
+    fn test_client_ip_right_most_untrusted() {
+        let proxies = proxies("10.0.0.0/8", 0);
+        let headers = headers(&[("x-forwarded-for", "192.0.2.66, 198.51.100.7, 10.0.0.3")]);
+
+        // The spoofed left-most entry is skipped
+        let resolved = proxies.client_ip(&headers, Some(ip("10.0.0.2")));
+        assert_eq!(resolved, Some(ip("198.51.100.7")));
+
+        // Headers from an untrusted peer are ignored
+        let resolved = proxies.client_ip(&headers, Some(ip("203.0.113.9")));
+        assert_eq!(resolved, Some(ip("203.0.113.9")));
+    }
+
+    #[test]
+    fn test_client_ip_hops() {
+        let proxies = proxies("", 2);
+        let headers = headers(&[("x-forwarded-for", "192.0.2.66, 198.51.100.7, 203.0.113.5")]);
+
+        let resolved = proxies.client_ip(&headers, Some(ip("203.0.113.9")));
+        assert_eq!(resolved, Some(ip("198.51.100.7")));
+    }
+
+    #[test]
+    fn test_client_ip_forwarded() {
+        let proxies = TrustedProxies {
+            header: ForwardingHeader::Forwarded,
+            ..proxies("10.0.0.0/8", 0)
+        };
+        let headers = headers(&[
+            (
+                "forwarded",
+                r#"for=192.0.2.66;proto=https, for="[2001:db8::7]:4711""#,
+            ),
+            ("forwarded", "for=10.0.0.3;by=10.0.0.2"),
+            ("x-forwarded-for", "198.51.100.7"),
+        ]);
+
+        let resolved = proxies.client_ip(&headers, Some(ip("10.0.0.2")));
+        assert_eq!(resolved, Some(ip("2001:db8::7")));
+    }
+
+    #[test]
+    fn test_client_ip_unknown_node() {
+        let proxies = TrustedProxies {
+            header: ForwardingHeader::Forwarded,
+            ..proxies("10.0.0.0/8", 0)
+        };
+        let headers = headers(&[("forwarded", "for=unknown, for=10.0.0.3")]);
+
+        assert_eq!(proxies.client_ip(&headers, Some(ip("10.0.0.2"))), None);
+    }
+
+    #[test]
+    fn test_client_ip_real_ip_and_all_trusted() {
+        let proxies = proxies("10.0.0.0/8", 0);
+        let peer = Some(ip("10.0.0.2"));
+
+        let real_ip = TrustedProxies {
+            header: ForwardingHeader::XRealIp,
+            ..proxies.clone()
+        };
+        let reported = headers(&[("x-real-ip", "198.51.100.7:5000")]);
+        assert_eq!(real_ip.client_ip(&reported, peer), Some(ip("198.51.100.7")));
+
+        let internal = headers(&[("x-forwarded-for", "10.1.2.3")]);
+        assert_eq!(proxies.client_ip(&internal, peer), Some(ip("10.1.2.3")));
+    }
+
+    #[test]
+    fn test_client_ip_ignores_other_forwarding_headers() {
+        let peer = Some(ip("10.0.0.2"));
+
+        // The proxy writes X-Forwarded-For; the client adds the others
+        let proxies = proxies("10.0.0.0/8", 0);
+        let spoofed = headers(&[
+            ("forwarded", "for=192.0.2.66"),
+            ("x-real-ip", "192.0.2.66"),
+            ("x-forwarded-for", "198.51.100.7"),
+        ]);
+        assert_eq!(proxies.client_ip(&spoofed, peer), Some(ip("198.51.100.7")));
+
+        let unknown = headers(&[
+            ("forwarded", "for=unknown"),
+            ("x-forwarded-for", "198.51.100.7"),
+        ]);
+        assert_eq!(proxies.client_ip(&unknown, peer), Some(ip("198.51.100.7")));
+
+        // The proxy writes Forwarded; the client adds X-Forwarded-For
+        let proxies = TrustedProxies {
+            header: ForwardingHeader::Forwarded,
+            ..proxies
+        };
+        let spoofed = headers(&[
+            ("x-forwarded-for", "192.0.2.66"),
+            ("forwarded", "for=198.51.100.7"),
+        ]);
+        assert_eq!(proxies.client_ip(&spoofed, peer), Some(ip("198.51.100.7")));
+    }
+
+    #[test]
+    fn test_forwarding_header_from_name() {
+        assert_eq!(
+            ForwardingHeader::from_name("X-Forwarded-For"),
+            Some(ForwardingHeader::XForwardedFor)
+        );
+        assert_eq!(
+            ForwardingHeader::from_name(" forwarded "),
+            Some(ForwardingHeader::Forwarded)
+        );
+        assert_eq!(
+            ForwardingHeader::from_name("x-real-ip"),
+            Some(ForwardingHeader::XRealIp)
+        );
+        assert_eq!(ForwardingHeader::from_name("x-client-ip"), None);
+    }
+
+    #[test]
+    fn test_client_ip_extractor() {
+        let mut extensions = Extensions::new();
+        assert_eq!(ClientIp::from_extensions(&extensions), ClientIp(None));
+
+        extensions.insert(ConnectInfo(SocketAddr::from(([203, 0, 113, 9], 443))));
+        assert_eq!(
+            ClientIp::from_extensions(&extensions),
+            ClientIp(Some(ip("203.0.113.9")))
+        );
+
+        extensions.insert(ClientIp(Some(ip("198.51.100.7"))));
+        assert_eq!(
+            ClientIp::from_extensions(&extensions),
+            ClientIp(Some(ip("198.51.100.7")))
+        );
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn proxies(networks: &str, hops: usize) -> TrustedProxies {
+        TrustedProxies {
+            networks: crate::net::parse_cidr_list(networks).unwrap(),
+            hops,
+            header: ForwardingHeader::default(),
+        }
+    }
 
-        let ip = extract_client_ip(&headers, Some(direct));
-        assert_eq!(ip, Some(direct));
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_static(value));
+        }
+        headers
     }
 }
@@ -0,0 +1,452 @@
+//! CSRF Protection
+//!
+//! Defense for cookie-authenticated, state-changing endpoints:
+//! - Origin / Sec-Fetch-Site checks reject cross-site requests early
+//! - Signed double-submit token: a random value + HMAC stored in a
+//!   JS-readable cookie, which the client must echo in a request header
+//!
+//! Requests authenticated with `Authorization: Bearer ...` are exempt
+//! (browsers never attach that header automatically).
+//!
+//! ## Usage
+//! ```rust,ignore
+//! use axum::middleware::from_fn_with_state;
+//! use platform::csrf::{CsrfConfig, csrf_protect};
+//!
+//! // The secret must be shared by all instances and survive restarts
+//! let csrf = Arc::new(CsrfConfig::new(secret).with_trusted_origins(["https://example.com"]));
+//! let router = router.layer(from_fn_with_state(csrf, csrf_protect));
+//! ```
+
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{HeaderMap, HeaderValue, Method, Request, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use std::sync::Arc;
+
+use crate::cookie::{CookieConfig, SameSite, extract_cookie};
+use crate::crypto::{constant_time_eq, hmac_sha256, random_bytes};
+
+/// Random part length of a CSRF token
+const TOKEN_NONCE_LEN: usize = 16;
+
+/// Response header carrying the current token (for clients that cannot read the cookie)
+pub const CSRF_TOKEN_RESPONSE_HEADER: &str = "x-csrf-token";
+
+/// CSRF protection configuration
+///
+/// Each router can be protected with its own configuration.
+#[derive(Debug, Clone)]
+pub struct CsrfConfig {
+    /// Secret key for signing tokens (32 bytes)
+    pub secret: [u8; 32],
+    /// Cookie holding the token (must not be HttpOnly)
+    pub cookie: CookieConfig,
+    /// Request header the client echoes the token in
+    pub header_name: String,
+    /// Origins allowed to send state-changing requests
+    ///
+    /// When empty, only same-origin requests (Origin matching Host) pass.
+    pub trusted_origins: Vec<String>,
+    /// Whether the double-submit token is required (Origin checks always apply)
+    pub require_token: bool,
+    /// Skip all checks for `Authorization: Bearer` requests
+    pub exempt_bearer: bool,
+}
+
+impl Default for CsrfConfig {
+    fn default() -> Self {
+        Self {
+            secret: [0u8; 32],
+            cookie: CookieConfig {
+                name: "csrf_token".to_string(),
+                http_only: false,
+                same_site: SameSite::Lax,
+                ..CookieConfig::default()
+            },
+            header_name: "x-csrf-token".to_string(),
+            trusted_origins: Vec::new(),
+            require_token: true,
+            exempt_bearer: true,
+        }
+    }
+}
+
+impl CsrfConfig {
+    /// Create config with the given signing secret
+    pub fn new(secret: [u8; 32]) -> Self {
+        Self {
+            secret,
+            ..Default::default()
+        }
+    }
+
+    /// Create config with a random signing secret
+    ///
+    /// Tokens stop verifying after a restart and on other instances,
+    /// so this is only suitable for development and tests.
+    pub fn with_random_secret() -> Self {
+        let mut secret = [0u8; 32];
+        secret.copy_from_slice(&random_bytes(32));
+        Self::new(secret)
+    }
+
+    /// Set the origins allowed to send state-changing requests
+    pub fn with_trusted_origins<I, S>(mut self, origins: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.trusted_origins = origins.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set whether the token cookie requires HTTPS
+    pub fn with_cookie_secure(mut self, secure: bool) -> Self {
+        self.cookie.secure = secure;
+        self
+    }
+}
+
+/// Reason a request was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum CsrfError {
+    #[error("Origin is not trusted")]
+    UntrustedOrigin,
+    #[error("Cross-site request")]
+    CrossSite,
+    #[error("Missing CSRF token")]
+    MissingToken,
+    #[error("Invalid CSRF token")]
+    InvalidToken,
+}
+
+impl IntoResponse for CsrfError {
+    fn into_response(self) -> Response {
+        (StatusCode::FORBIDDEN, [("X-CSRF-Invalid", "true")]).into_response()
+    }
+}
+
+/// Generate a signed token: base64url(nonce || HMAC(secret, nonce))
+pub fn generate_token(secret: &[u8; 32]) -> String {
+    let nonce = random_bytes(TOKEN_NONCE_LEN);
+    let signature = hmac_sha256(secret, &nonce);
+
+    let mut data = Vec::with_capacity(TOKEN_NONCE_LEN + 32);
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&signature);
+    URL_SAFE_NO_PAD.encode(data)
+}
+
+/// Verify that a token was signed with the secret
+pub fn verify_token(secret: &[u8; 32], token: &str) -> bool {
+    let Ok(data) = URL_SAFE_NO_PAD.decode(token) else {
+        return false;
+    };
+    if data.len() != TOKEN_NONCE_LEN + 32 {
+        return false;
+    }
+
+    let (nonce, signature) = data.split_at(TOKEN_NONCE_LEN);
+    constant_time_eq(signature, &hmac_sha256(secret, nonce))
+}
+
+/// Check a request against the CSRF policy
+///
+/// Safe methods (GET, HEAD, OPTIONS, TRACE) always pass.
+pub fn check_request(
+    config: &CsrfConfig,
+    method: &Method,
+    headers: &HeaderMap,
+) -> Result<(), CsrfError> {
+    if is_safe_method(method) {
+        return Ok(());
+    }
+
+    if config.exempt_bearer && has_bearer_token(headers) {
+        return Ok(());
+    }
+
+    match headers.get(header::ORIGIN).and_then(|v| v.to_str().ok()) {
+        Some(origin) => {
+            let trusted = if config.trusted_origins.is_empty() {
+                is_same_origin(origin, headers)
+            } else {
+                config.trusted_origins.iter().any(|o| o == origin)
+            };
+            if !trusted {
+                return Err(CsrfError::UntrustedOrigin);
+            }
+        }
+        None => {
+            // No Origin: fall back to Fetch Metadata when the browser sends it
+            let site = headers.get("sec-fetch-site").and_then(|v| v.to_str().ok());
+            if site == Some("cross-site") {
+                return Err(CsrfError::CrossSite);
+            }
+        }
+    }
+
+    if !config.require_token {
+        return Ok(());
+    }
+
+    let cookie_token =
+        extract_cookie(headers, &config.cookie.name).ok_or(CsrfError::MissingToken)?;
+    let header_token = headers
+        .get(config.header_name.as_str())
+        .and_then(|v| v.to_str().ok())
+        .ok_or(CsrfError::MissingToken)?;
+
+    if !constant_time_eq(cookie_token.as_bytes(), header_token.as_bytes())
+        || !verify_token(&config.secret, header_token)
+    {
+        return Err(CsrfError::InvalidToken);
+    }
+
+    Ok(())
+}
+
+/// Middleware enforcing the CSRF policy and issuing tokens
+///
+/// On every passing request the current token is returned in the
+/// `X-CSRF-Token` response header; a new token cookie is set when the
+/// request carries none (or an invalid one).
+pub async fn csrf_protect(
+    State(config): State<Arc<CsrfConfig>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    if let Err(e) = check_request(&config, req.method(), req.headers()) {
+        tracing::warn!(
+            method = %req.method(),
+            path = %req.uri().path(),
+            reason = %e,
+            "CSRF check failed"
+        );
+        return e.into_response();
+    }
+
+    let existing = extract_cookie(req.headers(), &config.cookie.name)
+        .filter(|token| verify_token(&config.secret, token));
+
+    let mut response = next.run(req).await;
+
+    let token = match existing {
+        Some(token) => token,
+        None => {
+            let token = generate_token(&config.secret);
+            if let Ok(value) = HeaderValue::from_str(&config.cookie.build_set_cookie(&token)) {
+                response.headers_mut().append(header::SET_COOKIE, value);
+            }
+            token
+        }
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&token) {
+        response
+            .headers_mut()
+            .insert(CSRF_TOKEN_RESPONSE_HEADER, value);
+    }
+
+    response
+}
+
+fn is_safe_method(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    )
+}
+
+/// Check whether the Origin's host (and port) matches the Host header
+fn is_same_origin(origin: &str, headers: &HeaderMap) -> bool {
+    let Some((_, authority)) = origin.split_once("://") else {
+        return false;
+    };
+    headers
+        .get(header::HOST)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|host| host.eq_ignore_ascii_case(authority))
+}
+
+fn has_bearer_token(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.len() > 7 && v[..7].eq_ignore_ascii_case("bearer "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> CsrfConfig {
+        CsrfConfig::with_random_secret().with_trusted_origins(["https://example.com"])
+    }
+
+    fn headers_with_token(config: &CsrfConfig, token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_str(&format!("{}={}", config.cookie.name, token)).unwrap(),
+        );
+        headers.insert(
+            config
+                .header_name
+                .as_str()
+                .parse::<header::HeaderName>()
+                .unwrap(),
+            HeaderValue::from_str(token).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_token_roundtrip() {
+        let secret = [7u8; 32];
+        let token = generate_token(&secret);
+        assert!(verify_token(&secret, &token));
+        assert!(!verify_token(&[8u8; 32], &token));
+        assert!(!verify_token(&secret, "garbage"));
+    }
+
+    #[test]
+    fn test_safe_methods_pass() {
+        let config = config();
+        assert!(check_request(&config, &Method::GET, &HeaderMap::new()).is_ok());
+        assert!(check_request(&config, &Method::OPTIONS, &HeaderMap::new()).is_ok());
+    }
+
+    #[test]
+    fn test_valid_double_submit() {
+        let config = config();
+        let token = generate_token(&config.secret);
+        let mut headers = headers_with_token(&config, &token);
+        headers.insert(
+            header::ORIGIN,
+            HeaderValue::from_static("https://example.com"),
+        );
+
+        assert!(check_request(&config, &Method::POST, &headers).is_ok());
+    }
+
+    #[test]
+    fn test_missing_token() {
+        let config = config();
+        assert_eq!(
+            check_request(&config, &Method::POST, &HeaderMap::new()),
+            Err(CsrfError::MissingToken)
+        );
+    }
+
+    #[test]
+    fn test_mismatched_or_forged_token() {
+        let config = config();
+        let token = generate_token(&config.secret);
+        let mut headers = headers_with_token(&config, &token);
+        headers.insert(
+            config
+                .header_name
+                .as_str()
+                .parse::<header::HeaderName>()
+                .unwrap(),
+            HeaderValue::from_str(&generate_token(&config.secret)).unwrap(),
+        );
+        assert_eq!(
+            check_request(&config, &Method::POST, &headers),
+            Err(CsrfError::InvalidToken)
+        );
+
+        // Matching cookie/header but signed with another secret
+        let forged = generate_token(&[1u8; 32]);
+        let headers = headers_with_token(&config, &forged);
+        assert_eq!(
+            check_request(&config, &Method::POST, &headers),
+            Err(CsrfError::InvalidToken)
+        );
+    }
+
+    #[test]
+    fn test_untrusted_origin() {
+        let config = config();
+        let token = generate_token(&config.secret);
+        let mut headers = headers_with_token(&config, &token);
+        headers.insert(
+            header::ORIGIN,
+            HeaderValue::from_static("https://evil.example"),
+        );
+
+        assert_eq!(
+            check_request(&config, &Method::POST, &headers),
+            Err(CsrfError::UntrustedOrigin)
+        );
+    }
+
+    #[test]
+    fn test_no_trusted_origins_requires_same_origin() {
+        let config = CsrfConfig::with_random_secret();
+        let token = generate_token(&config.secret);
+        let mut headers = headers_with_token(&config, &token);
+        headers.insert(header::HOST, HeaderValue::from_static("example.com"));
+
+        headers.insert(
+            header::ORIGIN,
+            HeaderValue::from_static("https://evil.example"),
+        );
+        assert_eq!(
+            check_request(&config, &Method::POST, &headers),
+            Err(CsrfError::UntrustedOrigin)
+        );
+
+        headers.insert(
+            header::ORIGIN,
+            HeaderValue::from_static("https://example.com"),
+        );
+        assert!(check_request(&config, &Method::POST, &headers).is_ok());
+
+        // "null" and other opaque origins never match
+        headers.insert(header::ORIGIN, HeaderValue::from_static("null"));
+        assert_eq!(
+            check_request(&config, &Method::POST, &headers),
+            Err(CsrfError::UntrustedOrigin)
+        );
+    }
+
+    #[test]
+    fn test_cross_site_fetch_metadata() {
+        let config = config();
+        let token = generate_token(&config.secret);
+        let mut headers = headers_with_token(&config, &token);
+        headers.insert("sec-fetch-site", HeaderValue::from_static("cross-site"));
+
+        assert_eq!(
+            check_request(&config, &Method::POST, &headers),
+            Err(CsrfError::CrossSite)
+        );
+    }
+
+    #[test]
+    fn test_bearer_exempt() {
+        let config = config();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer abc"),
+        );
+        headers.insert(
+            header::ORIGIN,
+            HeaderValue::from_static("https://evil.example"),
+        );
+
+        assert!(check_request(&config, &Method::POST, &headers).is_ok());
+
+        let config = CsrfConfig {
+            exempt_bearer: false,
+            ..config
+        };
+        assert!(check_request(&config, &Method::POST, &headers).is_err());
+    }
+}
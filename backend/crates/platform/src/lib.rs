@@ -4,13 +4,20 @@
 //! - Cryptographic utilities (SHA-256, HMAC, Base64)
 //! - Password hashing (Argon2id, NIST SP 800-63B compliant)
 //! - Cookie management
-//! - Client identification (fingerprinting, IP extraction)
-//! - Rate limiting infrastructure
+//! - Session token format (versioned, expiring, key-rotatable)
+//! - CSRF protection (signed double-submit token, Origin checks)
+//! - Client identification (fingerprinting, trusted-proxy IP resolution)
+//! - IP networks (CIDR matching)
+//! - Rate limiting infrastructure (GCRA stores, tower layer)
 //! - Common middleware components
 
 pub mod client;
 pub mod config;
 pub mod cookie;
+pub mod csrf;
 pub mod crypto;
+pub mod net;
 pub mod password;
 pub mod rate_limit;
+pub mod rate_limit_layer;
+pub mod session_token;
@@ -0,0 +1,168 @@
+//! IP Networks
+//!
+//! CIDR ranges for allowlists and trusted-proxy lists.
+
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// An IP network in CIDR notation (e.g. `10.0.0.0/8`, `2001:db8::/32`)
+///
+/// A bare address parses as a single-host network (/32 or /128).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpCidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+/// Error when parsing an `IpCidr`
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid CIDR: {0}")]
+pub struct InvalidCidr(pub String);
+
+impl IpCidr {
+    /// Network containing `addr`, with the host bits cleared
+    ///
+    /// Returns `None` if `prefix_len` exceeds the address width.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Option<Self> {
+        if prefix_len > max_prefix_len(&addr) {
+            return None;
+        }
+        Some(Self {
+            network: mask(addr, prefix_len),
+            prefix_len,
+        })
+    }
+
+    pub fn network(&self) -> IpAddr {
+        self.network
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Whether `ip` lies in this network
+    ///
+    /// IPv4-mapped IPv6 addresses (`::ffff:a.b.c.d`) match IPv4 networks.
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        let ip = match ip {
+            IpAddr::V6(v6) if self.network.is_ipv4() => match v6.to_ipv4_mapped() {
+                Some(v4) => IpAddr::V4(v4),
+                None => return false,
+            },
+            other => *other,
+        };
+        ip.is_ipv4() == self.network.is_ipv4() && mask(ip, self.prefix_len) == self.network
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = InvalidCidr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidCidr(s.to_string());
+        let (addr, prefix_len) = match s.trim().split_once('/') {
+            Some((addr, len)) => {
+                let addr: IpAddr = addr.parse().map_err(|_| invalid())?;
+                (addr, len.parse().map_err(|_| invalid())?)
+            }
+            None => {
+                let addr: IpAddr = s.trim().parse().map_err(|_| invalid())?;
+                (addr, max_prefix_len(&addr))
+            }
+        };
+        Self::new(addr, prefix_len).ok_or_else(invalid)
+    }
+}
+
+impl fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+/// Parse a comma-separated list of CIDRs (empty entries are skipped)
+pub fn parse_cidr_list(list: &str) -> Result<Vec<IpCidr>, InvalidCidr> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+fn max_prefix_len(addr: &IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn mask(addr: IpAddr, prefix_len: u8) -> IpAddr {
+    match addr {
+        IpAddr::V4(v4) => {
+            let bits = u32::from(v4)
+                & u32::MAX
+                    .checked_shl(32 - u32::from(prefix_len))
+                    .unwrap_or(0);
+            IpAddr::V4(bits.into())
+        }
+        IpAddr::V6(v6) => {
+            let bits = u128::from(v6)
+                & u128::MAX
+                    .checked_shl(128 - u32::from(prefix_len))
+                    .unwrap_or(0);
+            IpAddr::V6(bits.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let cidr: IpCidr = "10.1.2.3/8".parse().unwrap();
+        assert_eq!(cidr.to_string(), "10.0.0.0/8");
+
+        let host: IpCidr = "192.0.2.7".parse().unwrap();
+        assert_eq!(host.to_string(), "192.0.2.7/32");
+
+        let v6: IpCidr = "2001:db8:abcd::1/48".parse().unwrap();
+        assert_eq!(v6.to_string(), "2001:db8:abcd::/48");
+
+        assert!("10.0.0.0/33".parse::<IpCidr>().is_err());
+        assert!("10.0.0.0/x".parse::<IpCidr>().is_err());
+        assert!("example.com".parse::<IpCidr>().is_err());
+    }
+
+    #[test]
+    fn test_contains() {
+        let cidr: IpCidr = "10.0.0.0/8".parse().unwrap();
+        assert!(cidr.contains(&ip("10.255.0.1")));
+        assert!(!cidr.contains(&ip("11.0.0.1")));
+        assert!(cidr.contains(&ip("::ffff:10.0.0.1")));
+        assert!(!cidr.contains(&ip("2001:db8::1")));
+
+        let v6: IpCidr = "2001:db8::/32".parse().unwrap();
+        assert!(v6.contains(&ip("2001:db8:ffff::1")));
+        assert!(!v6.contains(&ip("2001:db9::1")));
+        assert!(!v6.contains(&ip("10.0.0.1")));
+
+        let all: IpCidr = "0.0.0.0/0".parse().unwrap();
+        assert!(all.contains(&ip("203.0.113.9")));
+    }
+
+    #[test]
+    fn test_parse_cidr_list() {
+        let list = parse_cidr_list(" 127.0.0.1, 10.0.0.0/8,,::1 ").unwrap();
+        assert_eq!(list.len(), 3);
+        assert!(parse_cidr_list("10.0.0.0/8, nope").is_err());
+        assert!(parse_cidr_list("").unwrap().is_empty());
+    }
+}
@@ -100,8 +100,11 @@ pub enum PasswordHashError {
 /// ```rust
 /// use platform::password::ClearTextPassword;
 ///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
 /// let password = ClearTextPassword::new("my_secure_password".to_string())?;
 /// // Password is automatically zeroized when dropped
+/// # Ok(())
+/// # }
 /// ```
 #[derive(Zeroize, ZeroizeOnDrop)]
 pub struct ClearTextPassword(String);
@@ -251,10 +254,10 @@ impl ClearTextPassword {
         // Check if our hash suffix appears in response
         // Format: SUFFIX:COUNT\r\n
         for line in body.lines() {
-            if let Some((hash_suffix, _count)) = line.split_once(':') {
-                if hash_suffix.eq_ignore_ascii_case(suffix) {
-                    return Ok(true); // Password is compromised
-                }
+            if let Some((hash_suffix, _count)) = line.split_once(':')
+                && hash_suffix.eq_ignore_ascii_case(suffix)
+            {
+                return Ok(true); // Password is compromised
             }
         }
 
@@ -287,11 +290,14 @@ impl fmt::Debug for ClearTextPassword {
 /// ```rust
 /// use platform::password::{ClearTextPassword, HashedPassword};
 ///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
 /// let password = ClearTextPassword::new("my_secure_password".to_string())?;
 /// let hashed = password.hash(None)?;
 ///
 /// // Later, verify
 /// assert!(hashed.verify(&password, None));
+/// # Ok(())
+/// # }
 /// ```
 #[derive(Clone, PartialEq, Eq)]
 pub struct HashedPassword {
@@ -381,10 +387,11 @@ fn is_common_pattern(password: &str) -> bool {
 
     // Check for all same character (e.g., "aaaaaaaa")
     let chars: Vec<char> = lower.chars().collect();
-    if chars.len() >= 3 && chars.windows(3).all(|w| w[0] == w[1] && w[1] == w[2]) {
-        if chars.iter().all(|&c| c == chars[0]) {
-            return true;
-        }
+    if chars.len() >= 3
+        && chars.windows(3).all(|w| w[0] == w[1] && w[1] == w[2])
+        && chars.iter().all(|&c| c == chars[0])
+    {
+        return true;
     }
 
     // Check for sequential numbers (e.g., "12345678")
@@ -1,8 +1,24 @@
 //! Rate Limiting Infrastructure
 //!
 //! Common rate limiting abstractions and implementations.
+//!
+//! Limits are enforced with GCRA (generic cell rate algorithm): each key
+//! stores a single "theoretical arrival time" (TAT). A limit of N requests
+//! per window lets a fresh key burst N requests, after which requests are
+//! admitted at a steady window/N rate. Unlike fixed windows, there is no
+//! boundary where 2N requests can slip through.
+//!
+//! Backends:
+//! - [`MemoryRateLimitStore`]: sharded in-process map with eviction
+//! - `PgRateLimitStore` (feature `sqlx`): shared between instances
+
+use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use std::time::Duration;
+/// Error returned by rate limit stores
+pub type RateLimitError = Box<dyn std::error::Error + Send + Sync>;
 
 /// Rate limit configuration
 #[derive(Debug, Clone)]
@@ -39,18 +55,453 @@ impl RateLimitConfig {
 #[derive(Debug, Clone)]
 pub struct RateLimitResult {
     pub allowed: bool,
+    /// Requests that would still be admitted right now
     pub remaining: u32,
+    /// When the full burst is available again
     pub reset_at_ms: i64,
+    /// How long a rejected request should wait (0 if allowed)
+    pub retry_after_ms: i64,
 }
 
 /// Trait for rate limit storage backends
 #[trait_variant::make(RateLimitStore: Send)]
 pub trait LocalRateLimitStore {
     /// Check and increment rate limit counter
-    /// Returns (allowed, remaining_requests)
+    ///
+    /// Rejected requests are not counted. A key must always be checked with
+    /// the same config; namespace keys per limit (e.g. `"pow:challenge:…"`).
     async fn check_and_increment(
         &self,
         key: &str,
         config: &RateLimitConfig,
-    ) -> Result<RateLimitResult, Box<dyn std::error::Error + Send + Sync>>;
+    ) -> Result<RateLimitResult, RateLimitError>;
+}
+
+/// Current Unix time in milliseconds
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64)
+}
+
+// ============================================================================
+// GCRA
+// ============================================================================
+
+/// GCRA parameters derived from a [`RateLimitConfig`]
+#[derive(Debug, Clone, Copy)]
+pub struct Gcra {
+    limit: u32,
+    /// Time one request "costs"
+    interval_ms: i64,
+    /// Time the full burst spans (`limit * interval_ms`)
+    window_ms: i64,
+}
+
+impl Gcra {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        let limit = config.max_requests;
+        let interval_ms = (config.window_ms() / i64::from(limit.max(1))).max(1);
+        Self {
+            limit,
+            interval_ms,
+            window_ms: interval_ms * i64::from(limit),
+        }
+    }
+
+    pub fn interval_ms(&self) -> i64 {
+        self.interval_ms
+    }
+
+    pub fn window_ms(&self) -> i64 {
+        self.window_ms
+    }
+
+    /// TAT after admitting one more request on top of `tat_ms`
+    pub fn next_tat(&self, tat_ms: Option<i64>, now_ms: i64) -> i64 {
+        tat_ms.map_or(now_ms, |tat| tat.max(now_ms)) + self.interval_ms
+    }
+
+    /// Decide a request against the stored TAT
+    ///
+    /// Returns the result and, if the request is admitted, the TAT to store.
+    pub fn check(&self, tat_ms: Option<i64>, now_ms: i64) -> (RateLimitResult, Option<i64>) {
+        let next = self.next_tat(tat_ms, now_ms);
+        if self.limit > 0 && next - self.window_ms <= now_ms {
+            (self.allowed(next, now_ms), Some(next))
+        } else {
+            (self.denied(next, now_ms), None)
+        }
+    }
+
+    /// Result for an admitted request that moved the TAT to `tat_ms`
+    pub fn allowed(&self, tat_ms: i64, now_ms: i64) -> RateLimitResult {
+        RateLimitResult {
+            allowed: true,
+            remaining: ((now_ms + self.window_ms - tat_ms) / self.interval_ms).max(0) as u32,
+            reset_at_ms: tat_ms,
+            retry_after_ms: 0,
+        }
+    }
+
+    /// Result for a rejected request that would have moved the TAT to `next_tat_ms`
+    pub fn denied(&self, next_tat_ms: i64, now_ms: i64) -> RateLimitResult {
+        if self.limit == 0 {
+            return RateLimitResult {
+                allowed: false,
+                remaining: 0,
+                reset_at_ms: now_ms + self.window_ms.max(self.interval_ms),
+                retry_after_ms: self.window_ms.max(self.interval_ms),
+            };
+        }
+        RateLimitResult {
+            allowed: false,
+            remaining: 0,
+            reset_at_ms: next_tat_ms - self.interval_ms,
+            retry_after_ms: (next_tat_ms - self.window_ms - now_ms).max(0),
+        }
+    }
+}
+
+// ============================================================================
+// In-memory store
+// ============================================================================
+
+/// In-process GCRA store
+///
+/// Keys are spread over independently locked shards. Expired keys (whose
+/// burst has fully refilled, so they carry no state) are swept when a
+/// shard fills up; if it is still full, the key closest to expiry is
+/// evicted, which can only make the limit more lenient for that key.
+///
+/// Limits are per process: with several instances, use the Postgres store.
+pub struct MemoryRateLimitStore {
+    shards: Box<[Mutex<HashMap<String, i64>>]>,
+    max_keys_per_shard: usize,
+    hasher: RandomState,
+}
+
+impl std::fmt::Debug for MemoryRateLimitStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoryRateLimitStore")
+            .field("shards", &self.shards.len())
+            .field("max_keys_per_shard", &self.max_keys_per_shard)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for MemoryRateLimitStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryRateLimitStore {
+    pub const DEFAULT_SHARDS: usize = 16;
+    pub const DEFAULT_MAX_KEYS_PER_SHARD: usize = 8_192;
+
+    pub fn new() -> Self {
+        Self::with_capacity(Self::DEFAULT_SHARDS, Self::DEFAULT_MAX_KEYS_PER_SHARD)
+    }
+
+    /// Store holding at most `shards * max_keys_per_shard` keys
+    pub fn with_capacity(shards: usize, max_keys_per_shard: usize) -> Self {
+        Self {
+            shards: (0..shards.max(1))
+                .map(|_| Mutex::new(HashMap::new()))
+                .collect(),
+            max_keys_per_shard: max_keys_per_shard.max(1),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Number of keys currently tracked
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap_or_else(|e| e.into_inner()).len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check and count a request at `now_ms`
+    pub fn check_at(&self, key: &str, config: &RateLimitConfig, now_ms: i64) -> RateLimitResult {
+        let gcra = Gcra::new(config);
+        let index = (self.hasher.hash_one(key) % self.shards.len() as u64) as usize;
+        let mut shard = self.shards[index].lock().unwrap_or_else(|e| e.into_inner());
+
+        let (result, tat) = gcra.check(shard.get(key).copied(), now_ms);
+        if let Some(tat) = tat {
+            if !shard.contains_key(key) && shard.len() >= self.max_keys_per_shard {
+                evict(&mut shard, now_ms);
+            }
+            shard.insert(key.to_string(), tat);
+        }
+        result
+    }
+}
+
+/// Make room in a full shard
+fn evict(shard: &mut HashMap<String, i64>, now_ms: i64) {
+    shard.retain(|_, tat| *tat > now_ms);
+    if let Some(key) = shard
+        .iter()
+        .min_by_key(|(_, tat)| **tat)
+        .map(|(key, _)| key.clone())
+    {
+        shard.remove(&key);
+    }
+}
+
+impl RateLimitStore for MemoryRateLimitStore {
+    async fn check_and_increment(
+        &self,
+        key: &str,
+        config: &RateLimitConfig,
+    ) -> Result<RateLimitResult, RateLimitError> {
+        Ok(self.check_at(key, config, now_ms()))
+    }
+}
+
+// ============================================================================
+// Postgres store
+// ============================================================================
+
+/// GCRA store in the `rate_limit_buckets` table
+///
+/// Each check is a single upsert, so concurrent instances never admit more
+/// than the limit.
+#[cfg(feature = "sqlx")]
+#[derive(Debug, Clone)]
+pub struct PgRateLimitStore {
+    pool: sqlx::PgPool,
+}
+
+#[cfg(feature = "sqlx")]
+impl PgRateLimitStore {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Delete buckets that have fully refilled, `batch_size` rows per statement
+    pub async fn cleanup_expired_batched(&self, batch_size: i64) -> Result<u64, sqlx::Error> {
+        let now_ms = now_ms();
+        let batch_size = batch_size.max(1);
+
+        let mut deleted = 0;
+        loop {
+            let batch = sqlx::query(
+                r#"
+                DELETE FROM rate_limit_buckets
+                WHERE ctid = ANY(ARRAY(
+                    SELECT ctid FROM rate_limit_buckets
+                    WHERE tat_ms < $1
+                    LIMIT $2
+                ))
+                "#,
+            )
+            .bind(now_ms)
+            .bind(batch_size)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+            deleted += batch;
+            if batch < batch_size as u64 {
+                break;
+            }
+        }
+
+        tracing::info!(
+            buckets_deleted = deleted,
+            "Cleaned up expired rate limit buckets"
+        );
+
+        Ok(deleted)
+    }
+}
+
+#[cfg(feature = "sqlx")]
+impl RateLimitStore for PgRateLimitStore {
+    async fn check_and_increment(
+        &self,
+        key: &str,
+        config: &RateLimitConfig,
+    ) -> Result<RateLimitResult, RateLimitError> {
+        let gcra = Gcra::new(config);
+        let now_ms = now_ms();
+        if config.max_requests == 0 {
+            return Ok(gcra.denied(now_ms, now_ms));
+        }
+
+        // Admit and advance the TAT in one statement; no row means rejected
+        let admitted = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO rate_limit_buckets AS b (bucket_key, tat_ms)
+            VALUES ($1, $2 + $3)
+            ON CONFLICT (bucket_key) DO UPDATE
+            SET tat_ms = GREATEST(b.tat_ms, $2) + $3
+            WHERE GREATEST(b.tat_ms, $2) + $3 - $4 <= $2
+            RETURNING tat_ms
+            "#,
+        )
+        .bind(key)
+        .bind(now_ms)
+        .bind(gcra.interval_ms())
+        .bind(gcra.window_ms())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(tat) = admitted {
+            return Ok(gcra.allowed(tat, now_ms));
+        }
+
+        let tat = sqlx::query_scalar::<_, i64>(
+            "SELECT tat_ms FROM rate_limit_buckets WHERE bucket_key = $1",
+        )
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(gcra.denied(gcra.next_tat(tat, now_ms), now_ms))
+    }
+}
+
+// ============================================================================
+// Backend selection
+// ============================================================================
+
+/// Rate limit store chosen at startup
+#[derive(Debug, Clone)]
+pub enum RateLimitBackend {
+    Memory(Arc<MemoryRateLimitStore>),
+    #[cfg(feature = "sqlx")]
+    Postgres(PgRateLimitStore),
+}
+
+impl RateLimitBackend {
+    /// A fresh in-memory store
+    pub fn memory() -> Self {
+        Self::Memory(Arc::new(MemoryRateLimitStore::new()))
+    }
+}
+
+impl RateLimitStore for RateLimitBackend {
+    async fn check_and_increment(
+        &self,
+        key: &str,
+        config: &RateLimitConfig,
+    ) -> Result<RateLimitResult, RateLimitError> {
+        match self {
+            Self::Memory(store) => {
+                RateLimitStore::check_and_increment(store.as_ref(), key, config).await
+            }
+            #[cfg(feature = "sqlx")]
+            Self::Postgres(store) => RateLimitStore::check_and_increment(store, key, config).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> RateLimitConfig {
+        RateLimitConfig::new(10, 60)
+    }
+
+    #[test]
+    fn test_burst_then_steady_rate() {
+        let store = MemoryRateLimitStore::new();
+        let now = 1_000_000;
+
+        for i in 0..10 {
+            let result = store.check_at("k", &config(), now);
+            assert!(result.allowed, "request {i} should be admitted");
+            assert_eq!(result.remaining, 9 - i);
+        }
+
+        let denied = store.check_at("k", &config(), now);
+        assert!(!denied.allowed);
+        assert_eq!(denied.remaining, 0);
+        assert_eq!(denied.retry_after_ms, 6_000);
+
+        // One request is earned back every window / limit
+        assert!(!store.check_at("k", &config(), now + 5_999).allowed);
+        assert!(store.check_at("k", &config(), now + 6_000).allowed);
+    }
+
+    #[test]
+    fn test_no_double_burst_at_window_boundary() {
+        let store = MemoryRateLimitStore::new();
+        let start = 59_000;
+
+        let admitted = (0..20)
+            .filter(|_| store.check_at("k", &config(), start).allowed)
+            .count()
+            + (0..20)
+                .filter(|_| store.check_at("k", &config(), start + 1_000).allowed)
+                .count();
+        assert_eq!(admitted, 10);
+    }
+
+    #[test]
+    fn test_rejected_requests_are_not_counted() {
+        let store = MemoryRateLimitStore::new();
+        for _ in 0..100 {
+            store.check_at("k", &config(), 0);
+        }
+        assert!(store.check_at("k", &config(), 6_000).allowed);
+    }
+
+    #[test]
+    fn test_keys_are_independent() {
+        let store = MemoryRateLimitStore::new();
+        for _ in 0..10 {
+            store.check_at("a", &config(), 0);
+        }
+        assert!(!store.check_at("a", &config(), 0).allowed);
+        assert!(store.check_at("b", &config(), 0).allowed);
+    }
+
+    #[test]
+    fn test_reset_after_idle() {
+        let store = MemoryRateLimitStore::new();
+        for _ in 0..10 {
+            store.check_at("k", &config(), 0);
+        }
+        let result = store.check_at("k", &config(), 60_000);
+        assert!(result.allowed);
+        assert_eq!(result.remaining, 9);
+        assert_eq!(result.reset_at_ms, 66_000);
+    }
+
+    #[test]
+    fn test_zero_limit_rejects() {
+        let store = MemoryRateLimitStore::new();
+        let result = store.check_at("k", &RateLimitConfig::new(0, 60), 0);
+        assert!(!result.allowed);
+        assert_eq!(result.retry_after_ms, 60_000);
+    }
+
+    #[test]
+    fn test_eviction_bounds_memory() {
+        let store = MemoryRateLimitStore::with_capacity(1, 4);
+        for i in 0..4 {
+            store.check_at(&format!("old{i}"), &config(), 0);
+        }
+        assert_eq!(store.len(), 4);
+
+        // Expired keys are swept first
+        store.check_at("new", &config(), 10_000);
+        assert_eq!(store.len(), 1);
+
+        // A full shard of live keys drops the one closest to expiry
+        for i in 0..4 {
+            store.check_at(&format!("live{i}"), &config(), 10_000 + i);
+        }
+        assert_eq!(store.len(), 4);
+    }
 }
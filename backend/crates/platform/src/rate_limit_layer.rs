@@ -0,0 +1,422 @@
+//! Rate Limit Layer
+//!
+//! `tower::Layer` enforcing a [`RateLimitConfig`] per client on the wrapped
+//! routes, backed by any [`RateLimitStore`].
+//!
+//! Every response carries the standard `RateLimit-Limit`,
+//! `RateLimit-Remaining` and `RateLimit-Reset` headers; rejected requests
+//! get `429 Too Many Requests` with `Retry-After`. If the store fails, the
+//! request is let through (rate limiting must not take the API down).
+//!
+//! ## Usage
+//! ```rust,ignore
+//! let signin_limit = RateLimitLayer::new("auth:signin", store, RateLimitConfig::new(10, 60))
+//!     .key(RateLimitKey::Ip);
+//! let router = Router::new()
+//!     .route("/signin", post(sign_in))
+//!     .route_layer(signin_limit);
+//! ```
+
+use axum::body::Body;
+use axum::extract::MatchedPath;
+use axum::http::{HeaderMap, HeaderValue, Method, Request, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use std::convert::Infallible;
+use std::fmt;
+use std::future::Future;
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+use crate::client::{ClientIp, ClientNetworkKey, ClientUserId, extract_fingerprint};
+use crate::crypto::to_base64;
+use crate::rate_limit::{RateLimitConfig, RateLimitResult, RateLimitStore};
+
+/// Response header with the request quota of the window
+pub const RATE_LIMIT_LIMIT_HEADER: &str = "ratelimit-limit";
+
+/// Response header with the requests left right now
+pub const RATE_LIMIT_REMAINING_HEADER: &str = "ratelimit-remaining";
+
+/// Response header with the seconds until the full quota is available again
+pub const RATE_LIMIT_RESET_HEADER: &str = "ratelimit-reset";
+
+/// What a client's requests are counted by
+///
+/// Several keys can be combined (e.g. user id and route).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RateLimitKey {
+    /// Client IP address
+    Ip,
+    /// Network of the client IP
+    Network(ClientNetworkKey),
+    /// User-Agent fingerprint (falls back to the client IP without one)
+    Fingerprint,
+    /// Authenticated user (`ClientUserId` extension, falls back to the client IP)
+    User,
+    /// Matched route path
+    Route,
+}
+
+impl RateLimitKey {
+    /// Network key with the default /24 and /48 networks
+    pub fn network() -> Self {
+        Self::Network(ClientNetworkKey::default())
+    }
+
+    /// This key's part of the bucket key for `req`
+    fn extract(&self, req: &Request<Body>, client_ip: Option<IpAddr>) -> String {
+        let ip = || client_ip.map_or_else(|| "unknown".to_string(), |ip| ip.to_string());
+        match self {
+            Self::Ip => ip(),
+            Self::Network(key) => client_ip.map_or_else(ip, |addr| key.network(addr).to_string()),
+            Self::Fingerprint => extract_fingerprint(req.headers(), client_ip)
+                .map_or_else(|_| ip(), |fp| format!("ua:{}", to_base64(&fp.hash))),
+            Self::User => req
+                .extensions()
+                .get::<ClientUserId>()
+                .map_or_else(ip, |user| format!("user:{}", user.0)),
+            Self::Route => req
+                .extensions()
+                .get::<MatchedPath>()
+                .map_or_else(|| req.uri().path().to_string(), |p| p.as_str().to_string()),
+        }
+    }
+}
+
+/// Layer rate limiting the wrapped routes per client
+///
+/// Defaults: keyed by client IP, `OPTIONS` exempt (so CORS preflights pass).
+pub struct RateLimitLayer<S> {
+    store: Arc<S>,
+    name: Arc<str>,
+    config: RateLimitConfig,
+    keys: Vec<RateLimitKey>,
+    exempt_methods: Vec<Method>,
+}
+
+impl<S> Clone for RateLimitLayer<S> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            name: self.name.clone(),
+            config: self.config.clone(),
+            keys: self.keys.clone(),
+            exempt_methods: self.exempt_methods.clone(),
+        }
+    }
+}
+
+impl<S> fmt::Debug for RateLimitLayer<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimitLayer")
+            .field("name", &self.name)
+            .field("config", &self.config)
+            .field("keys", &self.keys)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> RateLimitLayer<S>
+where
+    S: RateLimitStore + Sync + 'static,
+{
+    /// Limit named `name` (the bucket key namespace, unique per limit)
+    pub fn new(name: impl Into<Arc<str>>, store: Arc<S>, config: RateLimitConfig) -> Self {
+        Self {
+            store,
+            name: name.into(),
+            config,
+            keys: Vec::new(),
+            exempt_methods: vec![Method::OPTIONS],
+        }
+    }
+
+    /// Also count requests by `key` (replaces the default IP key)
+    pub fn key(mut self, key: RateLimitKey) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// Methods not counted (replaces the default `OPTIONS`)
+    pub fn exempt_methods(mut self, methods: impl IntoIterator<Item = Method>) -> Self {
+        self.exempt_methods = methods.into_iter().collect();
+        self
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Bucket key of `req`
+    fn bucket_key(&self, req: &Request<Body>) -> String {
+        let ClientIp(client_ip) = ClientIp::from_extensions(req.extensions());
+
+        let parts = if self.keys.is_empty() {
+            vec![RateLimitKey::Ip.extract(req, client_ip)]
+        } else {
+            self.keys
+                .iter()
+                .map(|key| key.extract(req, client_ip))
+                .collect()
+        };
+        format!("{}:{}", self.name, parts.join("|"))
+    }
+}
+
+impl<S, I> Layer<I> for RateLimitLayer<S> {
+    type Service = RateLimit<I, S>;
+
+    fn layer(&self, inner: I) -> Self::Service {
+        RateLimit {
+            inner,
+            limit: self.clone(),
+        }
+    }
+}
+
+/// Service produced by [`RateLimitLayer`]
+pub struct RateLimit<I, S> {
+    inner: I,
+    limit: RateLimitLayer<S>,
+}
+
+impl<I: Clone, S> Clone for RateLimit<I, S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            limit: self.limit.clone(),
+        }
+    }
+}
+
+impl<I, S> Service<Request<Body>> for RateLimit<I, S>
+where
+    I: Service<Request<Body>, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    I::Future: Send + 'static,
+    S: RateLimitStore + Sync + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // The ready service must handle this request; leave a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let limit = self.limit.clone();
+
+        Box::pin(async move {
+            if limit.exempt_methods.contains(req.method()) {
+                return inner.call(req).await;
+            }
+
+            let key = limit.bucket_key(&req);
+            let result = match limit.store.check_and_increment(&key, &limit.config).await {
+                Ok(result) => result,
+                Err(e) => {
+                    tracing::error!(limit = %limit.name, error = %e, "Rate limit store failed");
+                    return inner.call(req).await;
+                }
+            };
+
+            if !result.allowed {
+                tracing::warn!(
+                    limit = %limit.name,
+                    path = %req.uri().path(),
+                    retry_after_ms = result.retry_after_ms,
+                    "Rate limit exceeded"
+                );
+                let mut response = StatusCode::TOO_MANY_REQUESTS.into_response();
+                set_rate_limit_headers(response.headers_mut(), &limit.config, &result);
+                return Ok(response);
+            }
+
+            let mut response = inner.call(req).await?;
+            set_rate_limit_headers(response.headers_mut(), &limit.config, &result);
+            Ok(response)
+        })
+    }
+}
+
+/// Write the `RateLimit-*` headers (and `Retry-After` for rejections)
+///
+/// Times are whole seconds, rounded up so clients never retry too early.
+pub fn set_rate_limit_headers(
+    headers: &mut HeaderMap,
+    config: &RateLimitConfig,
+    result: &RateLimitResult,
+) {
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64);
+    let reset_secs = ceil_secs(result.reset_at_ms - now_ms);
+
+    headers.insert(
+        RATE_LIMIT_LIMIT_HEADER,
+        HeaderValue::from(config.max_requests),
+    );
+    headers.insert(
+        RATE_LIMIT_REMAINING_HEADER,
+        HeaderValue::from(result.remaining),
+    );
+    headers.insert(RATE_LIMIT_RESET_HEADER, HeaderValue::from(reset_secs));
+    if !result.allowed {
+        headers.insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(ceil_secs(result.retry_after_ms).max(1)),
+        );
+    }
+}
+
+fn ceil_secs(ms: i64) -> u64 {
+    (ms.max(0) as u64).div_ceil(1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rate_limit::MemoryRateLimitStore;
+    use axum::Router;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    fn app(limit: RateLimitLayer<MemoryRateLimitStore>) -> Router {
+        Router::new()
+            .route("/a", get(|| async { "a" }))
+            .route("/b", get(|| async { "b" }))
+            .route_layer(limit)
+    }
+
+    fn request(path: &str, ip: &str) -> Request<Body> {
+        Request::builder()
+            .uri(path)
+            .extension(ClientIp(Some(ip.parse().unwrap())))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn limit(max_requests: u32) -> RateLimitLayer<MemoryRateLimitStore> {
+        RateLimitLayer::new(
+            "test",
+            Arc::new(MemoryRateLimitStore::new()),
+            RateLimitConfig::new(max_requests, 60),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_headers_and_rejection() {
+        let app = app(limit(2));
+
+        let response = app
+            .clone()
+            .oneshot(request("/a", "192.0.2.1"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[RATE_LIMIT_LIMIT_HEADER], "2");
+        assert_eq!(response.headers()[RATE_LIMIT_REMAINING_HEADER], "1");
+        assert!(response.headers().contains_key(RATE_LIMIT_RESET_HEADER));
+        assert!(!response.headers().contains_key(header::RETRY_AFTER));
+
+        app.clone()
+            .oneshot(request("/a", "192.0.2.1"))
+            .await
+            .unwrap();
+        let response = app.oneshot(request("/a", "192.0.2.1")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[RATE_LIMIT_REMAINING_HEADER], "0");
+        assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+    }
+
+    #[tokio::test]
+    async fn test_clients_are_counted_separately() {
+        let app = app(limit(1));
+
+        let first = app
+            .clone()
+            .oneshot(request("/a", "192.0.2.1"))
+            .await
+            .unwrap();
+        let other = app
+            .clone()
+            .oneshot(request("/a", "192.0.2.2"))
+            .await
+            .unwrap();
+        let again = app.oneshot(request("/a", "192.0.2.1")).await.unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(other.status(), StatusCode::OK);
+        assert_eq!(again.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_network_key() {
+        let app = app(limit(1).key(RateLimitKey::network()));
+
+        let first = app
+            .clone()
+            .oneshot(request("/a", "192.0.2.1"))
+            .await
+            .unwrap();
+        let neighbour = app.oneshot(request("/a", "192.0.2.200")).await.unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(neighbour.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_route_key() {
+        let app = app(limit(1).key(RateLimitKey::Ip).key(RateLimitKey::Route));
+
+        let a = app
+            .clone()
+            .oneshot(request("/a", "192.0.2.1"))
+            .await
+            .unwrap();
+        let b = app
+            .clone()
+            .oneshot(request("/b", "192.0.2.1"))
+            .await
+            .unwrap();
+        let a_again = app.oneshot(request("/a", "192.0.2.1")).await.unwrap();
+        assert_eq!(a.status(), StatusCode::OK);
+        assert_eq!(b.status(), StatusCode::OK);
+        assert_eq!(a_again.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_user_key_falls_back_to_ip() {
+        let app = app(limit(1).key(RateLimitKey::User));
+
+        let mut req = request("/a", "192.0.2.1");
+        req.extensions_mut().insert(ClientUserId("alice".into()));
+        let user = app.clone().oneshot(req).await.unwrap();
+        let anonymous = app
+            .clone()
+            .oneshot(request("/a", "192.0.2.1"))
+            .await
+            .unwrap();
+        let anonymous_again = app.oneshot(request("/a", "192.0.2.1")).await.unwrap();
+        assert_eq!(user.status(), StatusCode::OK);
+        assert_eq!(anonymous.status(), StatusCode::OK);
+        assert_eq!(anonymous_again.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_exempt_methods() {
+        let app = app(limit(0));
+
+        let req = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/a")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(req).await.unwrap();
+        assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+}
@@ -0,0 +1,282 @@
+//! Session Token Format
+//!
+//! Signed, versioned session tokens shared by the auth and PoW crates.
+//!
+//! ## Layout (v1)
+//! ```text
+//! base64url( version:u8 | key_id:u8 | session_id:16 | issued_at_ms:i64 | expires_at_ms:i64 | hmac:32 )
+//! ```
+//! The HMAC-SHA256 covers every preceding byte. Integers are big-endian.
+//!
+//! ## Key rotation
+//! Tokens are always signed with the current key. Verification also accepts
+//! previous keys (selected by `key_id`), so a new secret can be rolled out
+//! while tokens signed with the old one stay valid until they expire.
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use uuid::Uuid;
+
+use crate::crypto::{constant_time_eq, hmac_sha256, random_bytes};
+
+/// Current token format version
+pub const TOKEN_VERSION: u8 = 1;
+
+const HEADER_LEN: usize = 1 + 1 + 16 + 8 + 8;
+const SIGNATURE_LEN: usize = 32;
+const TOKEN_LEN: usize = HEADER_LEN + SIGNATURE_LEN;
+
+/// Session token errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum SessionTokenError {
+    #[error("Malformed session token")]
+    Malformed,
+    #[error("Unsupported session token version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("Unknown session token key: {0}")]
+    UnknownKey(u8),
+    #[error("Invalid session token signature")]
+    InvalidSignature,
+    #[error("Session token expired")]
+    Expired,
+}
+
+/// A signing key with its identifier
+#[derive(Debug, Clone)]
+pub struct SessionKey {
+    /// Key identifier embedded in tokens
+    pub id: u8,
+    /// Secret key for HMAC signing (32 bytes)
+    pub secret: [u8; 32],
+}
+
+impl SessionKey {
+    pub fn new(id: u8, secret: [u8; 32]) -> Self {
+        Self { id, secret }
+    }
+
+    /// Create a key with a random secret
+    pub fn random(id: u8) -> Self {
+        let mut secret = [0u8; 32];
+        secret.copy_from_slice(&random_bytes(32));
+        Self { id, secret }
+    }
+}
+
+/// Set of keys: one for signing, any number accepted for verification
+#[derive(Debug, Clone)]
+pub struct SessionKeyRing {
+    current: SessionKey,
+    previous: Vec<SessionKey>,
+}
+
+impl SessionKeyRing {
+    /// Create a key ring that signs with `current`
+    pub fn new(current: SessionKey) -> Self {
+        Self {
+            current,
+            previous: Vec::new(),
+        }
+    }
+
+    /// Additionally accept tokens signed with `key`
+    ///
+    /// Keys with the same id as the current key are ignored.
+    pub fn with_previous(mut self, key: SessionKey) -> Self {
+        if key.id != self.current.id {
+            self.previous.retain(|k| k.id != key.id);
+            self.previous.push(key);
+        }
+        self
+    }
+
+    /// Key used for signing new tokens
+    pub fn current(&self) -> &SessionKey {
+        &self.current
+    }
+
+    /// Look up a key accepted for verification
+    pub fn get(&self, id: u8) -> Option<&SessionKey> {
+        self.keys().find(|k| k.id == id)
+    }
+
+    /// Whether any key of this ring has the same secret as a key of `other`
+    ///
+    /// Rings for different token kinds must not share secrets, or tokens of
+    /// one kind would verify as the other.
+    pub fn shares_secret_with(&self, other: &SessionKeyRing) -> bool {
+        self.keys()
+            .any(|key| other.keys().any(|k| k.secret == key.secret))
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &SessionKey> {
+        std::iter::once(&self.current).chain(self.previous.iter())
+    }
+
+    /// Sign a new token with the current key
+    pub fn sign(&self, session_id: Uuid, issued_at_ms: i64, expires_at_ms: i64) -> String {
+        let mut data = Vec::with_capacity(TOKEN_LEN);
+        data.push(TOKEN_VERSION);
+        data.push(self.current.id);
+        data.extend_from_slice(session_id.as_bytes());
+        data.extend_from_slice(&issued_at_ms.to_be_bytes());
+        data.extend_from_slice(&expires_at_ms.to_be_bytes());
+
+        let signature = hmac_sha256(&self.current.secret, &data);
+        data.extend_from_slice(&signature);
+        URL_SAFE_NO_PAD.encode(data)
+    }
+
+    /// Verify a token and return its claims
+    ///
+    /// `now_ms` is compared against the embedded expiry.
+    pub fn verify(&self, token: &str, now_ms: i64) -> Result<SessionClaims, SessionTokenError> {
+        let data = URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|_| SessionTokenError::Malformed)?;
+
+        if data.is_empty() {
+            return Err(SessionTokenError::Malformed);
+        }
+        if data[0] != TOKEN_VERSION {
+            return Err(SessionTokenError::UnsupportedVersion(data[0]));
+        }
+        if data.len() != TOKEN_LEN {
+            return Err(SessionTokenError::Malformed);
+        }
+
+        let (header, signature) = data.split_at(HEADER_LEN);
+        let key_id = header[1];
+        let key = self
+            .get(key_id)
+            .ok_or(SessionTokenError::UnknownKey(key_id))?;
+
+        if !constant_time_eq(signature, &hmac_sha256(&key.secret, header)) {
+            return Err(SessionTokenError::InvalidSignature);
+        }
+
+        let session_id = Uuid::from_bytes(header[2..18].try_into().expect("16 bytes"));
+        let issued_at_ms = i64::from_be_bytes(header[18..26].try_into().expect("8 bytes"));
+        let expires_at_ms = i64::from_be_bytes(header[26..34].try_into().expect("8 bytes"));
+
+        if now_ms >= expires_at_ms {
+            return Err(SessionTokenError::Expired);
+        }
+
+        Ok(SessionClaims {
+            key_id,
+            session_id,
+            issued_at_ms,
+            expires_at_ms,
+        })
+    }
+}
+
+/// Verified contents of a session token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionClaims {
+    pub key_id: u8,
+    pub session_id: Uuid,
+    pub issued_at_ms: i64,
+    pub expires_at_ms: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000_000;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let ring = SessionKeyRing::new(SessionKey::new(1, [7u8; 32]));
+        let id = Uuid::new_v4();
+        let token = ring.sign(id, NOW, NOW + 60_000);
+
+        let claims = ring.verify(&token, NOW + 1).unwrap();
+        assert_eq!(claims.session_id, id);
+        assert_eq!(claims.key_id, 1);
+        assert_eq!(claims.issued_at_ms, NOW);
+        assert_eq!(claims.expires_at_ms, NOW + 60_000);
+    }
+
+    #[test]
+    fn test_expired() {
+        let ring = SessionKeyRing::new(SessionKey::new(1, [7u8; 32]));
+        let token = ring.sign(Uuid::new_v4(), NOW, NOW + 60_000);
+
+        assert_eq!(
+            ring.verify(&token, NOW + 60_000),
+            Err(SessionTokenError::Expired)
+        );
+    }
+
+    #[test]
+    fn test_tampered() {
+        let ring = SessionKeyRing::new(SessionKey::new(1, [7u8; 32]));
+        let token = ring.sign(Uuid::new_v4(), NOW, NOW + 60_000);
+
+        let mut data = URL_SAFE_NO_PAD.decode(&token).unwrap();
+        data[30] ^= 0x01; // extend expiry
+        let tampered = URL_SAFE_NO_PAD.encode(data);
+
+        assert_eq!(
+            ring.verify(&tampered, NOW),
+            Err(SessionTokenError::InvalidSignature)
+        );
+        assert_eq!(
+            ring.verify("not-a-token", NOW),
+            Err(SessionTokenError::Malformed)
+        );
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let ring = SessionKeyRing::new(SessionKey::new(1, [7u8; 32]));
+        let mut data = URL_SAFE_NO_PAD
+            .decode(ring.sign(Uuid::new_v4(), NOW, NOW + 60_000))
+            .unwrap();
+        data[0] = 2;
+
+        assert_eq!(
+            ring.verify(&URL_SAFE_NO_PAD.encode(data), NOW),
+            Err(SessionTokenError::UnsupportedVersion(2))
+        );
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let old = SessionKey::new(1, [1u8; 32]);
+        let new = SessionKey::new(2, [2u8; 32]);
+
+        let old_ring = SessionKeyRing::new(old.clone());
+        let old_token = old_ring.sign(Uuid::new_v4(), NOW, NOW + 60_000);
+
+        // During rotation: sign with new key, still accept the old one
+        let ring = SessionKeyRing::new(new.clone()).with_previous(old);
+        assert!(ring.verify(&old_token, NOW).is_ok());
+
+        let new_token = ring.sign(Uuid::new_v4(), NOW, NOW + 60_000);
+        assert_eq!(ring.verify(&new_token, NOW).unwrap().key_id, 2);
+
+        // After the old key is retired
+        let ring = SessionKeyRing::new(new);
+        assert_eq!(
+            ring.verify(&old_token, NOW),
+            Err(SessionTokenError::UnknownKey(1))
+        );
+    }
+
+    #[test]
+    fn test_shares_secret_with() {
+        let ring = SessionKeyRing::new(SessionKey::new(2, [2u8; 32]))
+            .with_previous(SessionKey::new(1, [1u8; 32]));
+
+        let other = SessionKeyRing::new(SessionKey::new(1, [3u8; 32]));
+        assert!(!ring.shares_secret_with(&other));
+
+        // Key ids do not matter, retired keys count
+        let other = SessionKeyRing::new(SessionKey::new(7, [1u8; 32]));
+        assert!(ring.shares_secret_with(&other));
+        assert!(other.shares_secret_with(&ring));
+    }
+}
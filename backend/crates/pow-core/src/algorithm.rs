@@ -0,0 +1,192 @@
+//! PoW Algorithms
+//!
+//! The hash function a client must evaluate for `(challenge, nonce)`.
+//! Every algorithm yields a 32-byte digest that is checked against the
+//! same leading-zero-bits target.
+//!
+//! - `sha256`: cheap, default. Heavily favours GPUs/ASICs.
+//! - `argon2id`: memory-hard, so a phone and a GPU farm are much closer
+//!   in hashes per second. Each hash is far more expensive, so it must be
+//!   paired with a much lower difficulty.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+
+use crate::services::{Nonce, compute_pow_hash, verify_difficulty};
+
+/// Suggested baseline difficulty for Argon2id with default parameters
+pub const ARGON2ID_DEFAULT_DIFFICULTY_BITS: u8 = 8;
+
+/// Argon2id cost parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2Params {
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    /// Number of passes
+    pub iterations: u32,
+    /// Degree of parallelism (lanes)
+    pub parallelism: u32,
+}
+
+impl Default for Argon2Params {
+    /// Small parameters that stay fast enough on mobile browsers (4 MiB, 1 pass)
+    fn default() -> Self {
+        Self {
+            memory_kib: 4096,
+            iterations: 1,
+            parallelism: 1,
+        }
+    }
+}
+
+impl Argon2Params {
+    /// Create parameters, rejecting values Argon2 does not accept
+    pub fn new(memory_kib: u32, iterations: u32, parallelism: u32) -> Option<Self> {
+        let params = Self {
+            memory_kib,
+            iterations,
+            parallelism,
+        };
+        params.to_argon2().map(|_| params)
+    }
+
+    fn to_argon2(self) -> Option<Params> {
+        Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32)).ok()
+    }
+}
+
+/// Hash algorithm of a challenge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PowAlgorithm {
+    /// SHA-256(challenge || nonce_be)
+    ///
+    /// The nonce encoding depends on the protocol version (see [`Nonce`]).
+    #[default]
+    Sha256,
+    /// Argon2id(password = nonce_be, salt = challenge)
+    Argon2id(Argon2Params),
+}
+
+impl PowAlgorithm {
+    /// Algorithm identifier (stored in DB and returned to clients)
+    pub fn id(&self) -> &'static str {
+        match self {
+            PowAlgorithm::Sha256 => "sha256",
+            PowAlgorithm::Argon2id(_) => "argon2id",
+        }
+    }
+
+    /// Algorithm parameters, if any
+    pub fn argon2_params(&self) -> Option<Argon2Params> {
+        match self {
+            PowAlgorithm::Sha256 => None,
+            PowAlgorithm::Argon2id(params) => Some(*params),
+        }
+    }
+
+    /// Restore an algorithm from its identifier and parameters
+    pub fn from_parts(id: &str, argon2_params: Option<Argon2Params>) -> Option<Self> {
+        match (id, argon2_params) {
+            ("sha256", _) => Some(PowAlgorithm::Sha256),
+            ("argon2id", Some(params)) => Some(PowAlgorithm::Argon2id(params)),
+            _ => None,
+        }
+    }
+
+    /// Whether evaluating a single hash is expensive (should run off the async executor)
+    pub fn is_memory_hard(&self) -> bool {
+        matches!(self, PowAlgorithm::Argon2id(_))
+    }
+
+    /// Compute the digest for a nonce (None if the parameters are invalid)
+    pub fn compute_hash(&self, challenge_bytes: &[u8], nonce: Nonce) -> Option<[u8; 32]> {
+        match self {
+            PowAlgorithm::Sha256 => Some(compute_pow_hash(challenge_bytes, nonce)),
+            PowAlgorithm::Argon2id(params) => {
+                let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params.to_argon2()?);
+                let mut out = [0u8; 32];
+                nonce
+                    .with_be_bytes(|password| {
+                        argon2.hash_password_into(password, challenge_bytes, &mut out)
+                    })
+                    .ok()?;
+                Some(out)
+            }
+        }
+    }
+
+    /// Verify a solution against the difficulty target
+    pub fn verify(&self, challenge_bytes: &[u8], nonce: Nonce, difficulty_bits: u8) -> bool {
+        self.compute_hash(challenge_bytes, nonce)
+            .is_some_and(|hash| verify_difficulty(&hash, difficulty_bits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::verify_pow;
+
+    fn small_argon2() -> PowAlgorithm {
+        PowAlgorithm::Argon2id(Argon2Params::new(64, 1, 1).unwrap())
+    }
+
+    #[test]
+    fn test_sha256_matches_legacy_verification() {
+        let challenge = [7u8; 32];
+        for nonce in 0..64 {
+            let nonce = Nonce::V1(nonce);
+            assert_eq!(
+                PowAlgorithm::Sha256.verify(&challenge, nonce, 4),
+                verify_pow(&challenge, nonce, 4)
+            );
+        }
+    }
+
+    #[test]
+    fn test_argon2id_is_deterministic() {
+        let algorithm = small_argon2();
+        let challenge = [1u8; 32];
+
+        let a = algorithm.compute_hash(&challenge, Nonce::V1(42)).unwrap();
+        let b = algorithm.compute_hash(&challenge, Nonce::V1(42)).unwrap();
+        let c = algorithm.compute_hash(&challenge, Nonce::V1(43)).unwrap();
+        let d = algorithm.compute_hash(&challenge, Nonce::V2(42)).unwrap();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(a, d);
+        assert_ne!(a, compute_pow_hash(&challenge, Nonce::V1(42)));
+    }
+
+    #[test]
+    fn test_argon2id_solve_and_verify() {
+        let algorithm = small_argon2();
+        let challenge = [3u8; 32];
+
+        let nonce = (0..10_000)
+            .map(Nonce::V2)
+            .find(|&n| algorithm.verify(&challenge, n, 4))
+            .expect("solution within 10k attempts");
+
+        assert!(algorithm.verify(&challenge, nonce, 4));
+    }
+
+    #[test]
+    fn test_invalid_params_rejected() {
+        assert!(Argon2Params::new(0, 1, 1).is_none());
+        assert!(Argon2Params::new(4096, 0, 1).is_none());
+        assert!(Argon2Params::new(4096, 1, 1).is_some());
+    }
+
+    #[test]
+    fn test_id_roundtrip() {
+        for algorithm in [PowAlgorithm::Sha256, small_argon2()] {
+            assert_eq!(
+                PowAlgorithm::from_parts(algorithm.id(), algorithm.argon2_params()),
+                Some(algorithm)
+            );
+        }
+        assert_eq!(PowAlgorithm::from_parts("argon2id", None), None);
+        assert_eq!(PowAlgorithm::from_parts("scrypt", None), None);
+    }
+}
@@ -0,0 +1,12 @@
+//! PoW Core
+//!
+//! The hash and difficulty rules of the PoW protocol, with no I/O:
+//! - `services` - SHA-256 hashing, leading-zero-bit target, sub-puzzles
+//! - `algorithm` - hash algorithms (SHA-256, Argon2id)
+//!
+//! The backend verifies with this code (`pow::domain::services`,
+//! `pow::domain::algorithm`) and the solvers search with it, so clients and
+//! server cannot disagree on encoding or endianness.
+
+pub mod algorithm;
+pub mod services;
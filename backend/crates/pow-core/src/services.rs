@@ -0,0 +1,237 @@
+//! Domain Services
+//!
+//! Pure domain logic for PoW verification.
+
+use sha2::{Digest, Sha256};
+
+/// Count leading zero bits in a SHA-256 hash
+pub fn count_leading_zero_bits(hash: &[u8; 32]) -> u8 {
+    let mut count = 0u8;
+    for &byte in hash {
+        if byte == 0 {
+            count = count.saturating_add(8);
+        } else {
+            count = count.saturating_add(byte.leading_zeros() as u8);
+            break;
+        }
+    }
+    count
+}
+
+/// Verify that a hash meets the difficulty requirement
+pub fn verify_difficulty(hash: &[u8; 32], difficulty_bits: u8) -> bool {
+    count_leading_zero_bits(hash) >= difficulty_bits
+}
+
+/// Wire protocol version of a challenge
+///
+/// The version fixes how a nonce is encoded into the hash input. Clients
+/// opt into newer versions when requesting a challenge; v1 stays the
+/// default so existing clients keep working.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum ProtocolVersion {
+    /// 32-bit nonce, 4 bytes big-endian
+    #[default]
+    V1,
+    /// 64-bit nonce, 8 bytes big-endian
+    V2,
+}
+
+impl ProtocolVersion {
+    /// Newest version this build understands
+    pub const LATEST: Self = ProtocolVersion::V2;
+
+    pub fn as_u8(self) -> u8 {
+        match self {
+            ProtocolVersion::V1 => 1,
+            ProtocolVersion::V2 => 2,
+        }
+    }
+
+    pub fn from_u8(version: u8) -> Option<Self> {
+        match version {
+            1 => Some(ProtocolVersion::V1),
+            2 => Some(ProtocolVersion::V2),
+            _ => None,
+        }
+    }
+
+    /// Largest nonce the version can encode
+    pub fn max_nonce(self) -> u64 {
+        match self {
+            ProtocolVersion::V1 => u32::MAX as u64,
+            ProtocolVersion::V2 => u64::MAX,
+        }
+    }
+}
+
+/// A nonce in the encoding of its protocol version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Nonce {
+    V1(u32),
+    V2(u64),
+}
+
+impl Nonce {
+    /// Encode `value` for `version` (None if it does not fit)
+    pub fn new(version: ProtocolVersion, value: u64) -> Option<Self> {
+        match version {
+            ProtocolVersion::V1 => u32::try_from(value).ok().map(Nonce::V1),
+            ProtocolVersion::V2 => Some(Nonce::V2(value)),
+        }
+    }
+
+    pub fn version(self) -> ProtocolVersion {
+        match self {
+            Nonce::V1(_) => ProtocolVersion::V1,
+            Nonce::V2(_) => ProtocolVersion::V2,
+        }
+    }
+
+    pub fn value(self) -> u64 {
+        match self {
+            Nonce::V1(nonce) => nonce as u64,
+            Nonce::V2(nonce) => nonce,
+        }
+    }
+
+    /// Call `f` with the big-endian encoding of the nonce
+    pub fn with_be_bytes<T>(self, f: impl FnOnce(&[u8]) -> T) -> T {
+        match self {
+            Nonce::V1(nonce) => f(&nonce.to_be_bytes()),
+            Nonce::V2(nonce) => f(&nonce.to_be_bytes()),
+        }
+    }
+}
+
+impl From<u32> for Nonce {
+    fn from(nonce: u32) -> Self {
+        Nonce::V1(nonce)
+    }
+}
+
+/// Compute SHA-256 of concatenated challenge bytes and encoded nonce
+pub fn compute_pow_hash(challenge_bytes: &[u8], nonce: Nonce) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(challenge_bytes);
+    nonce.with_be_bytes(|bytes| hasher.update(bytes));
+    hasher.finalize().into()
+}
+
+/// Maximum number of sub-puzzles in one challenge
+pub const MAX_PUZZLE_COUNT: u8 = 64;
+
+/// Challenge bytes of one sub-puzzle
+///
+/// A single-puzzle challenge uses the challenge bytes unchanged, so the
+/// original single-nonce protocol stays valid. Otherwise sub-puzzle `index`
+/// uses SHA-256(challenge || index_be), making the puzzles independent.
+pub fn sub_challenge(challenge_bytes: &[u8], index: u32, puzzle_count: u8) -> Vec<u8> {
+    if puzzle_count <= 1 {
+        return challenge_bytes.to_vec();
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(challenge_bytes);
+    hasher.update(index.to_be_bytes());
+    hasher.finalize().to_vec()
+}
+
+/// Per-puzzle difficulty when `total_bits` of work is split into `puzzle_count` puzzles
+///
+/// k puzzles of (d - log2 k) bits take the same expected work as one puzzle
+/// of d bits, but the total solve time varies far less. For a k that is not
+/// a power of two the expected work rounds up.
+pub fn split_difficulty(total_bits: u8, puzzle_count: u8) -> u8 {
+    let k = puzzle_count.max(1);
+    total_bits.saturating_sub(k.ilog2() as u8).max(1)
+}
+
+/// Verify a PoW solution
+pub fn verify_pow(challenge_bytes: &[u8], nonce: Nonce, difficulty_bits: u8) -> bool {
+    let hash = compute_pow_hash(challenge_bytes, nonce);
+    verify_difficulty(&hash, difficulty_bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leading_zero_bits() {
+        // All zeros wraps around in u8, use saturating_add
+        let hash = [0u8; 32];
+        assert_eq!(count_leading_zero_bits(&hash), 255); // saturating at 255
+
+        let mut hash = [0u8; 32];
+        hash[0] = 0x01;
+        assert_eq!(count_leading_zero_bits(&hash), 7);
+
+        hash[0] = 0x80;
+        assert_eq!(count_leading_zero_bits(&hash), 0);
+
+        hash[0] = 0x00;
+        hash[1] = 0x01;
+        assert_eq!(count_leading_zero_bits(&hash), 15);
+    }
+
+    #[test]
+    fn test_verify_difficulty() {
+        let mut hash = [0u8; 32];
+        hash[2] = 0x01; // 23 zero bits (8 + 8 + 7)
+        assert!(verify_difficulty(&hash, 23));
+        assert!(!verify_difficulty(&hash, 24));
+    }
+
+    #[test]
+    fn test_pow_hash_big_endian() {
+        let challenge = vec![0u8; 32];
+        let nonce: u32 = 0x01020304;
+        let hash = compute_pow_hash(&challenge, Nonce::V1(nonce));
+
+        // Verify it's using big-endian
+        let mut data = vec![0u8; 32];
+        data.extend_from_slice(&[0x01, 0x02, 0x03, 0x04]);
+        let mut hasher = Sha256::new();
+        hasher.update(&data);
+        let expected: [u8; 32] = hasher.finalize().into();
+
+        assert_eq!(hash, expected);
+    }
+
+    #[test]
+    fn test_pow_hash_v2_uses_eight_bytes() {
+        let challenge = vec![0u8; 32];
+        let hash = compute_pow_hash(&challenge, Nonce::V2(0x0102_0304_0506_0708));
+
+        let mut data = vec![0u8; 32];
+        data.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        let expected: [u8; 32] = Sha256::digest(&data).into();
+        assert_eq!(hash, expected);
+
+        // The same value hashes differently per version
+        assert_ne!(
+            compute_pow_hash(&challenge, Nonce::V1(7)),
+            compute_pow_hash(&challenge, Nonce::V2(7))
+        );
+    }
+
+    #[test]
+    fn test_nonce_range_per_version() {
+        let max_u32 = u32::MAX as u64;
+        assert_eq!(
+            Nonce::new(ProtocolVersion::V1, max_u32),
+            Some(Nonce::V1(u32::MAX))
+        );
+        assert_eq!(Nonce::new(ProtocolVersion::V1, max_u32 + 1), None);
+        assert_eq!(
+            Nonce::new(ProtocolVersion::V2, u64::MAX).map(Nonce::value),
+            Some(u64::MAX)
+        );
+
+        for version in [ProtocolVersion::V1, ProtocolVersion::V2] {
+            assert_eq!(ProtocolVersion::from_u8(version.as_u8()), Some(version));
+        }
+        assert_eq!(ProtocolVersion::from_u8(0), None);
+        assert_eq!(ProtocolVersion::from_u8(3), None);
+    }
+}
@@ -0,0 +1,355 @@
+//! PoW HTTP Client
+//!
+//! Runs the browser flow against a server: fetch a challenge, solve it on
+//! all cores, submit the nonces. Cookies (PoW session, CSRF token) are kept
+//! per client, so the same client can then call PoW-protected endpoints.
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use pow::crypto::{from_base64, sha256};
+use pow::models::{ChallengeResponse, ProofResponse, StatusResponse, SubmitRequest};
+use pow_core::algorithm::{Argon2Params, PowAlgorithm};
+use pow_core::services::ProtocolVersion;
+use reqwest::StatusCode;
+use reqwest::header::{CONTENT_TYPE, COOKIE, HeaderMap, SET_COOKIE, USER_AGENT};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::solver::{CancelToken, Progress, Puzzle, Solution, SolveError, Solver};
+
+/// Header echoing the CSRF token on state-changing requests
+const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Client errors
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Unexpected status {status} from {path}")]
+    Status { status: StatusCode, path: String },
+
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+
+    #[error(transparent)]
+    Solve(#[from] SolveError),
+}
+
+/// Result of a successful submit
+#[derive(Debug, Clone)]
+pub enum SubmitOutcome {
+    /// General session; the cookie is kept by the client
+    Session,
+    /// Single-use proof for an action (send it in `X-PoW-Proof`)
+    Proof(ProofResponse),
+}
+
+/// PoW client for one simulated user
+pub struct PowClient {
+    http: reqwest::Client,
+    base_url: String,
+    user_agent: String,
+    cookies: Mutex<BTreeMap<String, String>>,
+    csrf_token: Mutex<Option<String>>,
+}
+
+impl PowClient {
+    /// Client for the PoW API at `base_url` (e.g. `http://localhost:31113/api/pow`)
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            user_agent: concat!("pow-solver/", env!("CARGO_PKG_VERSION")).to_string(),
+            cookies: Mutex::new(BTreeMap::new()),
+            csrf_token: Mutex::new(None),
+        }
+    }
+
+    /// User-Agent to send (sessions are bound to it)
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// `Cookie` header with every cookie received so far
+    pub fn cookie_header(&self) -> String {
+        let cookies = self.cookies.lock().expect("cookie jar poisoned");
+        cookies
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// GET /challenge, optionally scoped to an action and bound to a request body
+    ///
+    /// Asks for the latest protocol version (64-bit nonces).
+    pub async fn challenge(
+        &self,
+        action: Option<&str>,
+        payload: Option<&[u8]>,
+    ) -> Result<ChallengeResponse, ClientError> {
+        let mut query = vec![(
+            "protocolVersion",
+            ProtocolVersion::LATEST.as_u8().to_string(),
+        )];
+        if let Some(action) = action {
+            query.push(("action", action.to_string()));
+        }
+        if let Some(payload) = payload {
+            query.push(("payloadHash", URL_SAFE_NO_PAD.encode(sha256(payload))));
+        }
+
+        let response = self
+            .request(reqwest::Method::GET, "/challenge")
+            .query(&query)
+            .send()
+            .await?;
+        let body = self.read(response, "/challenge").await?;
+        serde_json::from_slice(&body).map_err(|e| ClientError::InvalidResponse(e.to_string()))
+    }
+
+    /// POST /submit
+    pub async fn submit(
+        &self,
+        challenge: &ChallengeResponse,
+        solution: &Solution,
+    ) -> Result<SubmitOutcome, ClientError> {
+        let mut request = SubmitRequest {
+            challenge_id: challenge.pow_challenge_id,
+            challenge_token: challenge.pow_challenge_token.clone(),
+            protocol_version: None,
+            nonce_u32: None,
+            nonces_u32: None,
+            nonces_u64: None,
+            elapsed_ms: Some(solution.elapsed.as_millis() as i64),
+            total_hashes: Some(solution.total_hashes as i64),
+        };
+        match ProtocolVersion::from_u8(challenge.pow_protocol_version) {
+            Some(ProtocolVersion::V1) => {
+                let nonces = solution.nonces.iter().map(|&n| u32::try_from(n).ok());
+                request.nonces_u32 = nonces.collect::<Option<_>>();
+                if request.nonces_u32.is_none() {
+                    return Err(ClientError::InvalidResponse(
+                        "nonce out of the v1 range".into(),
+                    ));
+                }
+            }
+            Some(version) => {
+                request.protocol_version = Some(version.as_u8());
+                request.nonces_u64 = Some(solution.nonces.clone());
+            }
+            None => {
+                return Err(ClientError::InvalidResponse(format!(
+                    "unsupported protocol version {}",
+                    challenge.pow_protocol_version
+                )));
+            }
+        }
+        let body = serde_json::to_vec(&request)
+            .map_err(|e| ClientError::InvalidResponse(e.to_string()))?;
+
+        let mut builder = self
+            .request(reqwest::Method::POST, "/submit")
+            .header(CONTENT_TYPE, "application/json")
+            .body(body);
+        if let Some(token) = self.csrf_token.lock().expect("csrf poisoned").clone() {
+            builder = builder.header(CSRF_HEADER, token);
+        }
+
+        let response = builder.send().await?;
+        let status = response.status();
+        let body = self.read(response, "/submit").await?;
+        if status == StatusCode::NO_CONTENT {
+            return Ok(SubmitOutcome::Session);
+        }
+        serde_json::from_slice(&body)
+            .map(SubmitOutcome::Proof)
+            .map_err(|e| ClientError::InvalidResponse(e.to_string()))
+    }
+
+    /// GET /status
+    pub async fn status(&self) -> Result<StatusResponse, ClientError> {
+        let response = self.request(reqwest::Method::GET, "/status").send().await?;
+        let body = self.read(response, "/status").await?;
+        serde_json::from_slice(&body).map_err(|e| ClientError::InvalidResponse(e.to_string()))
+    }
+
+    /// Challenge → solve → submit
+    ///
+    /// The search runs on a blocking thread; `on_progress` is called from it.
+    pub async fn solve(
+        &self,
+        solver: &Solver,
+        action: Option<&str>,
+        payload: Option<&[u8]>,
+        cancel: &CancelToken,
+        on_progress: impl FnMut(&Progress) + Send + 'static,
+    ) -> Result<(Solution, SubmitOutcome), ClientError> {
+        let challenge = self.challenge(action, payload).await?;
+        let puzzle = puzzle_from_response(&challenge)?;
+
+        let solver = solver.clone();
+        let cancel = cancel.clone();
+        let solution =
+            tokio::task::spawn_blocking(move || solver.solve(&puzzle, &cancel, on_progress))
+                .await
+                .map_err(|e| ClientError::InvalidResponse(format!("solver task failed: {e}")))??;
+
+        let outcome = self.submit(&challenge, &solution).await?;
+        Ok((solution, outcome))
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let builder = self
+            .http
+            .request(method, format!("{}{path}", self.base_url))
+            .header(USER_AGENT, &self.user_agent);
+        let cookies = self.cookie_header();
+        if cookies.is_empty() {
+            builder
+        } else {
+            builder.header(COOKIE, cookies)
+        }
+    }
+
+    /// Keep cookies and the CSRF token, then return the body of a successful response
+    async fn read(&self, response: reqwest::Response, path: &str) -> Result<Vec<u8>, ClientError> {
+        self.store_cookies(response.headers());
+        if let Some(token) = response
+            .headers()
+            .get(CSRF_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            *self.csrf_token.lock().expect("csrf poisoned") = Some(token.to_string());
+        }
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(ClientError::Status {
+                status,
+                path: path.to_string(),
+            });
+        }
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    fn store_cookies(&self, headers: &HeaderMap) {
+        let mut cookies = self.cookies.lock().expect("cookie jar poisoned");
+        for value in headers.get_all(SET_COOKIE) {
+            let Some((name, value)) = value
+                .to_str()
+                .ok()
+                .and_then(|v| v.split(';').next())
+                .and_then(|pair| pair.split_once('='))
+            else {
+                continue;
+            };
+            if value.is_empty() {
+                cookies.remove(name.trim());
+            } else {
+                cookies.insert(name.trim().to_string(), value.trim().to_string());
+            }
+        }
+    }
+}
+
+/// Build the puzzle described by a challenge response
+pub fn puzzle_from_response(challenge: &ChallengeResponse) -> Result<Puzzle, ClientError> {
+    let challenge_bytes = from_base64(&challenge.pow_challenge_b64)
+        .map_err(|e| ClientError::InvalidResponse(format!("powChallengeB64: {e}")))?;
+
+    let params = challenge
+        .pow_algorithm_params
+        .as_ref()
+        .and_then(|p| Argon2Params::new(p.memory_kib, p.iterations, p.parallelism));
+    let algorithm =
+        PowAlgorithm::from_parts(&challenge.pow_algorithm, params).ok_or_else(|| {
+            ClientError::InvalidResponse(format!(
+                "unsupported algorithm {}",
+                challenge.pow_algorithm
+            ))
+        })?;
+    let protocol_version =
+        ProtocolVersion::from_u8(challenge.pow_protocol_version).ok_or_else(|| {
+            ClientError::InvalidResponse(format!(
+                "unsupported protocol version {}",
+                challenge.pow_protocol_version
+            ))
+        })?;
+
+    Ok(Puzzle {
+        challenge_bytes,
+        algorithm,
+        protocol_version,
+        puzzle_count: challenge.pow_puzzle_count,
+        difficulty_bits: challenge.pow_difficulty_bits,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pow::crypto::to_base64;
+    use pow::models::PowAlgorithmParams;
+    use uuid::Uuid;
+
+    fn response(algorithm: &str, params: Option<PowAlgorithmParams>) -> ChallengeResponse {
+        ChallengeResponse {
+            pow_challenge_id: Uuid::nil(),
+            pow_challenge_b64: to_base64(&[1, 2, 3, 4, 5, 6, 7, 8]),
+            pow_protocol_version: 2,
+            pow_algorithm: algorithm.to_string(),
+            pow_algorithm_params: params,
+            pow_puzzle_count: 2,
+            pow_difficulty_bits: 9,
+            pow_expires_at_ms: 0,
+            pow_action: None,
+            pow_challenge_token: None,
+        }
+    }
+
+    #[test]
+    fn test_puzzle_from_response() {
+        let puzzle = puzzle_from_response(&response("sha256", None)).unwrap();
+        assert_eq!(puzzle.challenge_bytes, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(puzzle.algorithm, PowAlgorithm::Sha256);
+        assert_eq!(puzzle.protocol_version, ProtocolVersion::V2);
+        assert_eq!(puzzle.puzzle_count, 2);
+        assert_eq!(puzzle.difficulty_bits, 9);
+
+        let params = PowAlgorithmParams {
+            memory_kib: 4096,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let puzzle = puzzle_from_response(&response("argon2id", Some(params))).unwrap();
+        assert!(puzzle.algorithm.is_memory_hard());
+
+        assert!(puzzle_from_response(&response("argon2id", None)).is_err());
+        assert!(puzzle_from_response(&response("scrypt", None)).is_err());
+
+        let mut v9 = response("sha256", None);
+        v9.pow_protocol_version = 9;
+        assert!(puzzle_from_response(&v9).is_err());
+    }
+
+    #[test]
+    fn test_cookie_jar() {
+        let client = PowClient::new("http://localhost/api/pow/");
+        let mut headers = HeaderMap::new();
+        headers.append(
+            SET_COOKIE,
+            "pow_session=abc; HttpOnly; Path=/".parse().unwrap(),
+        );
+        headers.append(SET_COOKIE, "csrf=xyz; Path=/".parse().unwrap());
+        client.store_cookies(&headers);
+        assert_eq!(client.cookie_header(), "csrf=xyz; pow_session=abc");
+
+        let mut headers = HeaderMap::new();
+        headers.append(SET_COOKIE, "pow_session=; Max-Age=0".parse().unwrap());
+        client.store_cookies(&headers);
+        assert_eq!(client.cookie_header(), "csrf=xyz");
+        assert_eq!(client.base_url, "http://localhost/api/pow");
+    }
+}
@@ -0,0 +1,30 @@
+//! Native PoW Solver
+//!
+//! Solves PoW challenges outside the browser: load tests, integration
+//! tests, non-browser clients and difficulty benchmarks.
+//!
+//! - `solver` - multithreaded nonce search with cancellation and progress
+//! - `client` - challenge → solve → submit over HTTP (feature `client`)
+//! - `wasm` - batched search API for browser workers (feature `wasm`)
+//!
+//! Hashing comes from `pow-core`, the same code the backend verifies with
+//! (`pow::domain::services`, `pow::domain::algorithm`), so a solution
+//! found here is valid on the server by construction.
+//!
+//! ## Usage
+//! ```rust,ignore
+//! let solver = Solver::new();
+//! let solution = solver.solve(&puzzle, &CancelToken::new(), |p| {
+//!     eprintln!("{} hashes, {:.0} H/s", p.hashes, p.hash_rate());
+//! })?;
+//! ```
+
+#[cfg(feature = "client")]
+pub mod client;
+pub mod solver;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use solver::{
+    CancelToken, Progress, Puzzle, RangeResult, Solution, SolveError, Solver, search_range,
+};
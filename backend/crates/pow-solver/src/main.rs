@@ -0,0 +1,202 @@
+//! PoW Solver CLI
+//!
+//! ## Usage
+//! ```text
+//! pow-solver solve [--url URL] [--action NAME] [--payload-file PATH]
+//!                  [--threads N] [--user-agent UA]
+//! pow-solver bench --bits N [--algorithm sha256|argon2id] [--puzzles K]
+//!                  [--rounds R] [--threads N]
+//! ```
+//!
+//! `solve` runs challenge → solve → submit against a running server and
+//! prints the session cookie or action proof. `bench` solves random local
+//! challenges to measure what a difficulty costs on this machine.
+
+use anyhow::Context;
+use pow::crypto::random_bytes;
+use pow_core::algorithm::{Argon2Params, PowAlgorithm};
+use pow_core::services::{ProtocolVersion, split_difficulty};
+use pow_solver::client::{PowClient, SubmitOutcome};
+use pow_solver::{CancelToken, Progress, Puzzle, Solver};
+use std::io::Write;
+use std::time::Duration;
+
+const USAGE: &str = "usage:
+  pow-solver solve [--url URL] [--action NAME] [--payload-file PATH] [--threads N] [--user-agent UA]
+  pow-solver bench --bits N [--algorithm sha256|argon2id] [--puzzles K] [--rounds R] [--threads N]";
+
+const DEFAULT_URL: &str = "http://localhost:31113/api/pow";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let command = args.next().unwrap_or_default();
+    let options = Options::parse(args)?;
+
+    let mut solver = Solver::new();
+    if let Some(threads) = options.threads {
+        solver = solver.with_threads(threads);
+    }
+
+    // Ctrl-C stops the search instead of killing the process mid-request
+    let cancel = CancelToken::new();
+    let on_signal = cancel.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            on_signal.cancel();
+        }
+    });
+
+    match command.as_str() {
+        "solve" => solve(&options, &solver, &cancel).await,
+        "bench" => bench(&options, &solver, &cancel).await,
+        _ => {
+            eprintln!("{USAGE}");
+            std::process::exit(2);
+        }
+    }
+}
+
+async fn solve(options: &Options, solver: &Solver, cancel: &CancelToken) -> anyhow::Result<()> {
+    let mut client = PowClient::new(options.url.as_deref().unwrap_or(DEFAULT_URL));
+    if let Some(user_agent) = &options.user_agent {
+        client = client.with_user_agent(user_agent);
+    }
+    let payload = match &options.payload_file {
+        Some(path) => Some(std::fs::read(path).with_context(|| format!("reading {path}"))?),
+        None => None,
+    };
+
+    eprintln!("Solving with {} threads", solver.threads());
+    let (solution, outcome) = client
+        .solve(
+            solver,
+            options.action.as_deref(),
+            payload.as_deref(),
+            cancel,
+            print_progress,
+        )
+        .await?;
+    eprintln!();
+
+    println!("nonces: {:?}", solution.nonces);
+    println!("hashes: {}", solution.total_hashes);
+    println!("elapsed_ms: {}", solution.elapsed.as_millis());
+    match outcome {
+        SubmitOutcome::Session => println!("cookie: {}", client.cookie_header()),
+        SubmitOutcome::Proof(proof) => {
+            println!("action: {}", proof.pow_action);
+            println!("proof: {}", proof.pow_proof);
+            println!("expires_at_ms: {}", proof.pow_expires_at_ms);
+        }
+    }
+    Ok(())
+}
+
+async fn bench(options: &Options, solver: &Solver, cancel: &CancelToken) -> anyhow::Result<()> {
+    let total_bits = options.bits.context("--bits is required for bench")?;
+    let puzzle_count = options.puzzles.unwrap_or(1);
+    let rounds = options.rounds.unwrap_or(5).max(1);
+    let algorithm = match options.algorithm.as_deref().unwrap_or("sha256") {
+        "sha256" => PowAlgorithm::Sha256,
+        "argon2id" => PowAlgorithm::Argon2id(Argon2Params::default()),
+        other => anyhow::bail!("unsupported algorithm: {other}"),
+    };
+    let difficulty_bits = split_difficulty(total_bits, puzzle_count);
+
+    eprintln!(
+        "{} bits as {puzzle_count} x {difficulty_bits} bits ({}), {rounds} rounds, {} threads",
+        total_bits,
+        algorithm.id(),
+        solver.threads()
+    );
+
+    let mut times = Vec::with_capacity(rounds);
+    let mut hashes = 0;
+    for round in 1..=rounds {
+        let puzzle = Puzzle {
+            challenge_bytes: random_bytes(32),
+            algorithm,
+            protocol_version: ProtocolVersion::LATEST,
+            puzzle_count,
+            difficulty_bits,
+        };
+        let worker = solver.clone();
+        let worker_cancel = cancel.clone();
+        let solution = tokio::task::spawn_blocking(move || {
+            worker.solve(&puzzle, &worker_cancel, print_progress)
+        })
+        .await??;
+        eprintln!();
+        println!(
+            "round {round}: {} ms, {} hashes",
+            solution.elapsed.as_millis(),
+            solution.total_hashes
+        );
+        hashes += solution.total_hashes;
+        times.push(solution.elapsed);
+    }
+
+    let total: Duration = times.iter().sum();
+    times.sort();
+    println!("median: {} ms", times[times.len() / 2].as_millis());
+    println!("mean: {} ms", (total / rounds as u32).as_millis());
+    println!("hash rate: {:.0} H/s", hashes as f64 / total.as_secs_f64());
+    Ok(())
+}
+
+fn print_progress(progress: &Progress) {
+    eprint!(
+        "\r  puzzle {}/{}  {} hashes  {:.0} H/s  {:.1}s",
+        progress.puzzles_solved + 1,
+        progress.puzzle_count,
+        progress.hashes,
+        progress.hash_rate(),
+        progress.elapsed.as_secs_f64()
+    );
+    let _ = std::io::stderr().flush();
+}
+
+#[derive(Debug, Default)]
+struct Options {
+    url: Option<String>,
+    action: Option<String>,
+    payload_file: Option<String>,
+    user_agent: Option<String>,
+    threads: Option<usize>,
+    bits: Option<u8>,
+    algorithm: Option<String>,
+    puzzles: Option<u8>,
+    rounds: Option<usize>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> anyhow::Result<Self> {
+        let mut options = Self::default();
+
+        while let Some(flag) = args.next() {
+            let mut value = || {
+                args.next()
+                    .with_context(|| format!("{flag} needs a value\n{USAGE}"))
+            };
+            match flag.as_str() {
+                "--url" => options.url = Some(value()?),
+                "--action" => options.action = Some(value()?),
+                "--payload-file" => options.payload_file = Some(value()?),
+                "--user-agent" => options.user_agent = Some(value()?),
+                "--algorithm" => options.algorithm = Some(value()?),
+                "--threads" => options.threads = Some(value()?.parse()?),
+                "--bits" => options.bits = Some(value()?.parse()?),
+                "--puzzles" => options.puzzles = Some(value()?.parse()?),
+                "--rounds" => options.rounds = Some(value()?.parse()?),
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    std::process::exit(0);
+                }
+                _ => anyhow::bail!("unknown argument: {flag}\n{USAGE}"),
+            }
+        }
+
+        Ok(options)
+    }
+}
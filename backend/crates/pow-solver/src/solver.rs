@@ -0,0 +1,400 @@
+//! Multithreaded Nonce Search
+//!
+//! Sub-puzzles are solved one after another. For each, worker threads take
+//! batches of consecutive nonces from a shared counter and search them with
+//! [`search_range`] until one finds a hash with enough leading zero bits.
+//! [`search_range`] is also the entry point of the WebAssembly build, where
+//! the browser worker drives the batches itself.
+
+use pow_core::algorithm::PowAlgorithm;
+use pow_core::services::{Nonce, ProtocolVersion, sub_challenge, verify_difficulty};
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Nonces per batch for cheap algorithms (between checks of the stop flag)
+const BATCH_SIZE: u64 = 4096;
+
+/// Nonces per batch for memory-hard algorithms
+const MEMORY_HARD_BATCH_SIZE: u64 = 4;
+
+/// Outcome of searching one batch of nonces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeResult {
+    /// First nonce in the batch that meets the difficulty
+    pub nonce: Option<u64>,
+    /// Hashes evaluated
+    pub hashes: u64,
+}
+
+/// Try nonces `start..start + count` of one sub-puzzle
+///
+/// The range is clamped to the nonce space of the protocol version. Stops
+/// at the first nonce that meets the difficulty.
+pub fn search_range(
+    algorithm: &PowAlgorithm,
+    version: ProtocolVersion,
+    challenge_bytes: &[u8],
+    difficulty_bits: u8,
+    start: u64,
+    count: u64,
+) -> Result<RangeResult, SolveError> {
+    let mut hashes = 0;
+    for nonce in (start..=version.max_nonce()).take(count as usize) {
+        let encoded = Nonce::new(version, nonce).ok_or(SolveError::Exhausted)?;
+        let hash = algorithm
+            .compute_hash(challenge_bytes, encoded)
+            .ok_or(SolveError::InvalidAlgorithm)?;
+        hashes += 1;
+        if verify_difficulty(&hash, difficulty_bits) {
+            return Ok(RangeResult {
+                nonce: Some(nonce),
+                hashes,
+            });
+        }
+    }
+    Ok(RangeResult {
+        nonce: None,
+        hashes,
+    })
+}
+
+/// A challenge to solve
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Puzzle {
+    pub challenge_bytes: Vec<u8>,
+    pub algorithm: PowAlgorithm,
+    /// Nonce encoding
+    pub protocol_version: ProtocolVersion,
+    /// Number of sub-puzzles (one nonce each)
+    pub puzzle_count: u8,
+    /// Difficulty of each sub-puzzle
+    pub difficulty_bits: u8,
+}
+
+/// One nonce per sub-puzzle, in order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub nonces: Vec<u64>,
+    pub total_hashes: u64,
+    pub elapsed: Duration,
+}
+
+/// Progress of a running search
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub hashes: u64,
+    pub elapsed: Duration,
+    pub puzzles_solved: u8,
+    pub puzzle_count: u8,
+}
+
+impl Progress {
+    /// Hashes per second so far
+    pub fn hash_rate(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.hashes as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Solver errors
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SolveError {
+    #[error("Search cancelled")]
+    Cancelled,
+
+    #[error("No nonce in the nonce space meets the difficulty")]
+    Exhausted,
+
+    #[error("Invalid algorithm parameters")]
+    InvalidAlgorithm,
+}
+
+/// Cancels a running search from another thread
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Multithreaded PoW solver
+#[derive(Debug, Clone)]
+pub struct Solver {
+    threads: usize,
+    progress_interval: Duration,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Solver {
+    /// Solver using all available cores, reporting progress every 250 ms
+    pub fn new() -> Self {
+        Self {
+            threads: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            progress_interval: Duration::from_millis(250),
+        }
+    }
+
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    pub fn with_progress_interval(mut self, interval: Duration) -> Self {
+        self.progress_interval = interval;
+        self
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Find one nonce per sub-puzzle
+    ///
+    /// Blocks the calling thread; `on_progress` is called on it every
+    /// progress interval.
+    pub fn solve(
+        &self,
+        puzzle: &Puzzle,
+        cancel: &CancelToken,
+        mut on_progress: impl FnMut(&Progress),
+    ) -> Result<Solution, SolveError> {
+        let started = Instant::now();
+        let hashes = AtomicU64::new(0);
+        let puzzle_count = puzzle.puzzle_count.max(1);
+
+        let mut nonces = Vec::with_capacity(puzzle_count as usize);
+        for index in 0..puzzle_count {
+            let bytes = sub_challenge(&puzzle.challenge_bytes, index as u32, puzzle_count);
+            if puzzle
+                .algorithm
+                .compute_hash(&bytes, Nonce::V1(0))
+                .is_none()
+            {
+                return Err(SolveError::InvalidAlgorithm);
+            }
+
+            let nonce = self.search(puzzle, &bytes, &hashes, cancel, |hashes| {
+                on_progress(&Progress {
+                    hashes,
+                    elapsed: started.elapsed(),
+                    puzzles_solved: index,
+                    puzzle_count,
+                })
+            })?;
+            nonces.push(nonce);
+        }
+
+        Ok(Solution {
+            nonces,
+            total_hashes: hashes.load(Ordering::Relaxed),
+            elapsed: started.elapsed(),
+        })
+    }
+
+    /// Search the nonce of one sub-puzzle on all worker threads
+    fn search(
+        &self,
+        puzzle: &Puzzle,
+        bytes: &[u8],
+        hashes: &AtomicU64,
+        cancel: &CancelToken,
+        mut on_tick: impl FnMut(u64),
+    ) -> Result<u64, SolveError> {
+        let stop = AtomicBool::new(false);
+        let next_nonce = AtomicU64::new(0);
+        let (found_tx, found_rx) = mpsc::channel();
+        let max_nonce = puzzle.protocol_version.max_nonce();
+        let batch = if puzzle.algorithm.is_memory_hard() {
+            MEMORY_HARD_BATCH_SIZE
+        } else {
+            BATCH_SIZE
+        };
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads {
+                let found_tx = found_tx.clone();
+                let (stop, next_nonce) = (&stop, &next_nonce);
+                scope.spawn(move || {
+                    while !stop.load(Ordering::Relaxed) && !cancel.is_cancelled() {
+                        let start = next_nonce.fetch_add(batch, Ordering::Relaxed);
+                        if start > max_nonce {
+                            return;
+                        }
+                        let Ok(result) = search_range(
+                            &puzzle.algorithm,
+                            puzzle.protocol_version,
+                            bytes,
+                            puzzle.difficulty_bits,
+                            start,
+                            batch,
+                        ) else {
+                            return;
+                        };
+                        hashes.fetch_add(result.hashes, Ordering::Relaxed);
+                        if let Some(nonce) = result.nonce {
+                            let _ = found_tx.send(nonce);
+                            return;
+                        }
+                    }
+                });
+            }
+            drop(found_tx);
+
+            let result = loop {
+                match found_rx.recv_timeout(self.progress_interval) {
+                    Ok(nonce) => break Ok(nonce),
+                    Err(RecvTimeoutError::Timeout) if cancel.is_cancelled() => {
+                        break Err(SolveError::Cancelled);
+                    }
+                    Err(RecvTimeoutError::Timeout) => on_tick(hashes.load(Ordering::Relaxed)),
+                    Err(RecvTimeoutError::Disconnected) if cancel.is_cancelled() => {
+                        break Err(SolveError::Cancelled);
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break Err(SolveError::Exhausted),
+                }
+            };
+            stop.store(true, Ordering::Relaxed);
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pow_core::algorithm::Argon2Params;
+
+    fn puzzle(algorithm: PowAlgorithm, difficulty_bits: u8, puzzle_count: u8) -> Puzzle {
+        Puzzle {
+            challenge_bytes: vec![7u8; 32],
+            algorithm,
+            protocol_version: ProtocolVersion::LATEST,
+            puzzle_count,
+            difficulty_bits,
+        }
+    }
+
+    /// Same check as the server (`Challenge::verify`)
+    fn verifies(puzzle: &Puzzle, nonces: &[u64]) -> bool {
+        nonces.len() == puzzle.puzzle_count as usize
+            && nonces.iter().enumerate().all(|(index, &nonce)| {
+                let bytes =
+                    sub_challenge(&puzzle.challenge_bytes, index as u32, puzzle.puzzle_count);
+                Nonce::new(puzzle.protocol_version, nonce).is_some_and(|nonce| {
+                    puzzle
+                        .algorithm
+                        .verify(&bytes, nonce, puzzle.difficulty_bits)
+                })
+            })
+    }
+
+    #[test]
+    fn test_search_range_stops_at_first_match() {
+        let bytes = [7u8; 32];
+        let sha256 = PowAlgorithm::Sha256;
+        let v1 = ProtocolVersion::V1;
+        let result = search_range(&sha256, v1, &bytes, 8, 0, u64::MAX).unwrap();
+        let nonce = result.nonce.unwrap();
+        assert_eq!(result.hashes, nonce + 1);
+        assert!(sha256.verify(&bytes, Nonce::new(v1, nonce).unwrap(), 8));
+
+        // A range ending before the first match finds nothing
+        let result = search_range(&sha256, v1, &bytes, 8, 0, nonce).unwrap();
+        assert_eq!(
+            result,
+            RangeResult {
+                nonce: None,
+                hashes: nonce
+            }
+        );
+
+        // The range is clamped at the end of the nonce space
+        let result = search_range(&sha256, v1, &bytes, 64, u32::MAX as u64, 10).unwrap();
+        assert_eq!(result.hashes, 1);
+        let result = search_range(&sha256, v1, &bytes, 8, u32::MAX as u64 + 1, 10).unwrap();
+        assert_eq!(result.hashes, 0);
+    }
+
+    #[test]
+    fn test_search_range_v2_beyond_32_bits() {
+        let bytes = [7u8; 32];
+        let start = u32::MAX as u64 - 2;
+        let result = search_range(
+            &PowAlgorithm::Sha256,
+            ProtocolVersion::V2,
+            &bytes,
+            4,
+            start,
+            1000,
+        )
+        .unwrap();
+        let nonce = result.nonce.unwrap();
+        assert!(PowAlgorithm::Sha256.verify(&bytes, Nonce::V2(nonce), 4));
+    }
+
+    #[test]
+    fn test_solution_verifies_on_the_server() {
+        let puzzle = puzzle(PowAlgorithm::Sha256, 12, 4);
+        let solution = Solver::new()
+            .with_threads(4)
+            .solve(&puzzle, &CancelToken::new(), |_| {})
+            .unwrap();
+
+        assert_eq!(solution.nonces.len(), 4);
+        assert!(verifies(&puzzle, &solution.nonces));
+        assert!(solution.total_hashes >= 4);
+    }
+
+    #[test]
+    fn test_memory_hard_solution_verifies() {
+        let params = Argon2Params::new(64, 1, 1).unwrap();
+        let puzzle = puzzle(PowAlgorithm::Argon2id(params), 3, 1);
+        let solution = Solver::new()
+            .with_threads(2)
+            .solve(&puzzle, &CancelToken::new(), |_| {})
+            .unwrap();
+
+        assert!(verifies(&puzzle, &solution.nonces));
+    }
+
+    #[test]
+    fn test_cancel_from_progress_callback() {
+        let puzzle = puzzle(PowAlgorithm::Sha256, 64, 1);
+        let cancel = CancelToken::new();
+        let mut ticks = 0;
+        let result = Solver::new()
+            .with_threads(2)
+            .with_progress_interval(Duration::from_millis(10))
+            .solve(&puzzle, &cancel, |progress| {
+                ticks += 1;
+                assert_eq!(progress.puzzle_count, 1);
+                cancel.cancel();
+            });
+
+        assert_eq!(result, Err(SolveError::Cancelled));
+        assert!(ticks >= 1);
+    }
+}
@@ -0,0 +1,168 @@
+//! WebAssembly Bindings
+//!
+//! Worker-friendly API for browsers: the worker drives the search in
+//! batches of nonces (`search(start, count)`), so it can post progress and
+//! stop between batches. Hashing is `pow-core`, the same code the server
+//! verifies with.
+//!
+//! The worker speaks protocol v1 (32-bit nonces), which is what the
+//! browser requests; 32-bit numbers also stay plain JS numbers.
+//!
+//! ## Build
+//! ```text
+//! wasm-pack build backend/crates/pow-solver --target web \
+//!     --out-dir ../../../frontend/public/pow-wasm --no-default-features --features wasm
+//! wasm-pack test backend/crates/pow-solver --headless --firefox \
+//!     --no-default-features --features wasm
+//! ```
+//!
+//! ## Usage (in a Web Worker)
+//! ```js
+//! const solver = algorithm === "argon2id"
+//!   ? PowWorker.argon2id(challengeBytes, difficultyBits, puzzleCount,
+//!       params.memoryKib, params.iterations, params.parallelism)
+//!   : new PowWorker(challengeBytes, difficultyBits, puzzleCount);
+//! let nonce = startNonce;
+//! while (!solver.solved) {
+//!   if (solver.search(nonce, 50_000) === undefined) nonce = (nonce + 50_000) >>> 0;
+//!   postMessage({ kind: "progress", totalHashes: solver.totalHashes });
+//! }
+//! postMessage({ kind: "found", nonces: Array.from(solver.nonces) });
+//! ```
+
+use pow_core::algorithm::{Argon2Params, PowAlgorithm};
+use pow_core::services::{self, Nonce, ProtocolVersion, sub_challenge};
+use wasm_bindgen::prelude::*;
+
+use crate::solver::{Puzzle, search_range};
+
+/// Solver state for one challenge
+#[wasm_bindgen]
+pub struct PowWorker {
+    puzzle: Puzzle,
+    /// Challenge bytes of the current sub-puzzle
+    current: Vec<u8>,
+    nonces: Vec<u32>,
+    total_hashes: u64,
+}
+
+#[wasm_bindgen]
+impl PowWorker {
+    /// SHA-256 challenge
+    #[wasm_bindgen(constructor)]
+    pub fn new(challenge: &[u8], difficulty_bits: u8, puzzle_count: u8) -> PowWorker {
+        Self::with_algorithm(
+            challenge,
+            PowAlgorithm::Sha256,
+            difficulty_bits,
+            puzzle_count,
+        )
+    }
+
+    /// Argon2id challenge (`powAlgorithmParams` of the challenge response)
+    pub fn argon2id(
+        challenge: &[u8],
+        difficulty_bits: u8,
+        puzzle_count: u8,
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+    ) -> Result<PowWorker, JsError> {
+        let params = Argon2Params::new(memory_kib, iterations, parallelism)
+            .ok_or_else(|| JsError::new("invalid Argon2id parameters"))?;
+        Ok(Self::with_algorithm(
+            challenge,
+            PowAlgorithm::Argon2id(params),
+            difficulty_bits,
+            puzzle_count,
+        ))
+    }
+
+    fn with_algorithm(
+        challenge: &[u8],
+        algorithm: PowAlgorithm,
+        difficulty_bits: u8,
+        puzzle_count: u8,
+    ) -> PowWorker {
+        let puzzle_count = puzzle_count.max(1);
+        PowWorker {
+            current: sub_challenge(challenge, 0, puzzle_count),
+            puzzle: Puzzle {
+                challenge_bytes: challenge.to_vec(),
+                algorithm,
+                protocol_version: ProtocolVersion::V1,
+                puzzle_count,
+                difficulty_bits,
+            },
+            nonces: Vec::with_capacity(puzzle_count as usize),
+            total_hashes: 0,
+        }
+    }
+
+    /// Try `count` nonces of the current sub-puzzle, starting at `start`
+    ///
+    /// Returns the nonce found (the worker then moves to the next
+    /// sub-puzzle) or `undefined`.
+    pub fn search(&mut self, start: u32, count: u32) -> Option<u32> {
+        if self.solved() {
+            return None;
+        }
+        let result = search_range(
+            &self.puzzle.algorithm,
+            self.puzzle.protocol_version,
+            &self.current,
+            self.puzzle.difficulty_bits,
+            start as u64,
+            count as u64,
+        )
+        .ok()?;
+        self.total_hashes += result.hashes;
+
+        let nonce = result.nonce? as u32;
+        self.nonces.push(nonce);
+        if !self.solved() {
+            self.current = sub_challenge(
+                &self.puzzle.challenge_bytes,
+                self.nonces.len() as u32,
+                self.puzzle.puzzle_count,
+            );
+        }
+        Some(nonce)
+    }
+
+    /// Whether every sub-puzzle has a nonce
+    #[wasm_bindgen(getter)]
+    pub fn solved(&self) -> bool {
+        self.nonces.len() == self.puzzle.puzzle_count as usize
+    }
+
+    /// Index of the sub-puzzle being searched
+    #[wasm_bindgen(getter, js_name = currentPuzzle)]
+    pub fn current_puzzle(&self) -> u8 {
+        self.nonces.len() as u8
+    }
+
+    /// Nonces found so far, in sub-puzzle order
+    #[wasm_bindgen(getter)]
+    pub fn nonces(&self) -> Vec<u32> {
+        self.nonces.clone()
+    }
+
+    /// Hashes evaluated so far
+    #[wasm_bindgen(getter, js_name = totalHashes)]
+    pub fn total_hashes(&self) -> f64 {
+        self.total_hashes as f64
+    }
+}
+
+/// SHA-256(challenge || nonce_be) of a v1 nonce, as the server computes it
+#[wasm_bindgen(js_name = computePowHash)]
+pub fn compute_pow_hash(challenge: &[u8], nonce: u32) -> Vec<u8> {
+    services::compute_pow_hash(challenge, Nonce::V1(nonce)).to_vec()
+}
+
+/// Whether a v1 SHA-256 nonce meets the difficulty, as the server checks it
+#[wasm_bindgen(js_name = verifyPow)]
+pub fn verify_pow(challenge: &[u8], nonce: u32, difficulty_bits: u8) -> bool {
+    services::verify_pow(challenge, Nonce::V1(nonce), difficulty_bits)
+}
@@ -0,0 +1,69 @@
+//! Headless browser tests of the WebAssembly build
+//!
+//! ```text
+//! wasm-pack test backend/crates/pow-solver --headless --firefox \
+//!     --no-default-features --features wasm
+//! ```
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use pow_core::services::{Nonce, sub_challenge};
+use pow_solver::wasm::{PowWorker, compute_pow_hash, verify_pow};
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+fn test_hash_uses_big_endian_nonce() {
+    let challenge = [0u8; 32];
+    let mut input = challenge.to_vec();
+    input.extend_from_slice(&[0x01, 0x02, 0x03, 0x04]);
+
+    assert_eq!(
+        compute_pow_hash(&challenge, 0x0102_0304),
+        pow_core::services::compute_pow_hash(&challenge, Nonce::V1(0x0102_0304)).to_vec()
+    );
+    assert_ne!(
+        compute_pow_hash(&challenge, 0x0102_0304),
+        compute_pow_hash(&challenge, 0x0403_0201)
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_batched_search_solves_every_sub_puzzle() {
+    let challenge = [9u8; 32];
+    let mut worker = PowWorker::new(&challenge, 8, 3);
+
+    let mut start = 0u32;
+    while !worker.solved() {
+        if worker.search(start, 64).is_none() {
+            start += 64;
+        } else {
+            start = 0;
+        }
+    }
+
+    let nonces = worker.nonces();
+    assert_eq!(nonces.len(), 3);
+    for (index, &nonce) in nonces.iter().enumerate() {
+        assert!(verify_pow(
+            &sub_challenge(&challenge, index as u32, 3),
+            nonce,
+            8
+        ));
+    }
+    assert!(worker.total_hashes() >= 3.0);
+    assert_eq!(worker.search(0, 64), None);
+}
+
+#[wasm_bindgen_test]
+fn test_argon2id_worker() {
+    let challenge = [3u8; 32];
+    let mut worker = PowWorker::argon2id(&challenge, 2, 1, 64, 1, 1).unwrap();
+
+    let mut start = 0u32;
+    while worker.search(start, 4).is_none() {
+        start += 4;
+    }
+    assert!(worker.solved());
+}
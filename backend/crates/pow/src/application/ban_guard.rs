@@ -0,0 +1,116 @@
+//! Ban Guard
+//!
+//! Rejects banned clients and turns recorded offenses into bans once the
+//! policy thresholds are crossed.
+
+use crate::application::config::PowConfig;
+use crate::domain::ban::{Ban, BanKeys, BanPolicy, BanSubject, OffenseKind};
+use crate::domain::repository::BanRepository;
+use crate::domain::value_objects::ClientFingerprint;
+use crate::error::{PowError, PowResult};
+use chrono::Utc;
+use std::sync::Arc;
+
+/// Ban checks shared by the challenge and submit use cases
+pub struct BanGuard<B>
+where
+    B: BanRepository,
+{
+    ban_repo: Arc<B>,
+    config: Arc<PowConfig>,
+}
+
+impl<B> BanGuard<B>
+where
+    B: BanRepository,
+{
+    pub fn new(ban_repo: Arc<B>, config: Arc<PowConfig>) -> Self {
+        Self { ban_repo, config }
+    }
+
+    /// Fail with `PowError::Banned` if the client or its network is banned
+    pub async fn check(&self, fingerprint: &ClientFingerprint) -> PowResult<()> {
+        if self.config.ban_policy.is_none() {
+            return Ok(());
+        }
+
+        let now_ms = Utc::now().timestamp_millis();
+        match self
+            .ban_repo
+            .active_ban(&self.keys(fingerprint), now_ms)
+            .await?
+        {
+            Some(ban) => Err(PowError::Banned {
+                subject_kind: ban.subject.kind(),
+                retry_after_secs: ban.retry_after_secs(now_ms),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Record an offense and ban the client if it crossed a threshold
+    ///
+    /// Failures are only logged; the request is rejected for the offense
+    /// itself either way.
+    pub async fn record_offense(&self, kind: OffenseKind, fingerprint: &ClientFingerprint) {
+        let Some(policy) = self.config.ban_policy else {
+            return;
+        };
+
+        if let Err(e) = self.try_record_offense(&policy, kind, fingerprint).await {
+            tracing::warn!(error = %e, kind = kind.as_str(), "Failed to record PoW offense");
+        }
+    }
+
+    async fn try_record_offense(
+        &self,
+        policy: &BanPolicy,
+        kind: OffenseKind,
+        fingerprint: &ClientFingerprint,
+    ) -> PowResult<()> {
+        let now_ms = Utc::now().timestamp_millis();
+        let keys = self.keys(fingerprint);
+        let counts = self
+            .ban_repo
+            .record_offense(kind, &keys, now_ms, now_ms - policy.offense_window_ms())
+            .await?;
+
+        // A client ban is enough; the network is only banned when many
+        // clients in it misbehave
+        let (subject, offense_count) = match policy.exceeded(&counts) {
+            (true, _) => (
+                BanSubject::Fingerprint(keys.client_hash),
+                counts.fingerprint,
+            ),
+            (false, true) => match keys.network {
+                Some(network) => (BanSubject::Network(network), counts.network),
+                None => return Ok(()),
+            },
+            (false, false) => return Ok(()),
+        };
+
+        let strike = self
+            .ban_repo
+            .count_bans(&subject, now_ms - policy.strike_memory_ms())
+            .await?
+            + 1;
+        let ban = Ban::new(
+            subject,
+            kind,
+            offense_count,
+            strike,
+            policy.duration(strike),
+            now_ms,
+        );
+        self.ban_repo.create_ban(&ban).await
+    }
+
+    /// Keys of the client: its fingerprint within its network, and the network
+    fn keys(&self, fingerprint: &ClientFingerprint) -> BanKeys {
+        let client_network = &self.config.client_network;
+        BanKeys {
+            client_hash: client_network.client_hash(fingerprint).to_vec(),
+            network: client_network.key(fingerprint),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! PoW Bypass
+//!
+//! Trusted clients (health checkers, internal services, partners) can skip
+//! the PoW session requirement. A request is let through when its client IP
+//! is in an allowlisted network, when it carries a valid signed bypass token,
+//! or when the authenticated user has an allowlisted role.
+
+use std::fmt;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use chrono::Utc;
+use platform::net::IpCidr;
+use platform::session_token::SessionKeyRing;
+use uuid::Uuid;
+
+/// Who may skip the PoW session requirement
+///
+/// Empty by default (no bypasses).
+#[derive(Debug, Clone, Default)]
+pub struct BypassPolicy {
+    /// Client networks that never need a PoW session
+    pub networks: Vec<IpCidr>,
+    /// Keys for bypass tokens sent in `X-PoW-Bypass` (None = tokens disabled)
+    ///
+    /// Must differ from the session keys, or any PoW session token would
+    /// double as a bypass token.
+    pub token_keys: Option<SessionKeyRing>,
+    /// User roles (e.g. `admin`) that never need a PoW session
+    pub roles: Vec<String>,
+}
+
+/// Why a request was let through without a PoW session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BypassReason {
+    /// Client IP in an allowlisted network
+    Network(IpCidr),
+    /// Valid bypass token (id of the issued credential)
+    Token(Uuid),
+    /// Authenticated user with an allowlisted role
+    Role(String),
+}
+
+impl BypassReason {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BypassReason::Network(_) => "network",
+            BypassReason::Token(_) => "token",
+            BypassReason::Role(_) => "role",
+        }
+    }
+}
+
+impl fmt::Display for BypassReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BypassReason::Network(network) => write!(f, "network {network}"),
+            BypassReason::Token(id) => write!(f, "token {id}"),
+            BypassReason::Role(role) => write!(f, "role {role}"),
+        }
+    }
+}
+
+impl BypassPolicy {
+    /// Whether any bypass is configured
+    pub fn is_enabled(&self) -> bool {
+        !self.networks.is_empty() || self.token_keys.is_some() || !self.roles.is_empty()
+    }
+
+    /// Reason to let the request through, if any
+    ///
+    /// Checked in order: network, token, role. An invalid or expired token
+    /// is ignored (the client still needs a PoW session).
+    pub fn check(
+        &self,
+        client_ip: Option<IpAddr>,
+        token: Option<&str>,
+        role: Option<&str>,
+    ) -> Option<BypassReason> {
+        if let Some(ip) = client_ip
+            && let Some(network) = self.networks.iter().find(|n| n.contains(&ip))
+        {
+            return Some(BypassReason::Network(*network));
+        }
+
+        if let (Some(keys), Some(token)) = (&self.token_keys, token) {
+            match keys.verify(token, Utc::now().timestamp_millis()) {
+                Ok(claims) => return Some(BypassReason::Token(claims.session_id)),
+                Err(e) => tracing::warn!(error = %e, "PoW bypass token rejected"),
+            }
+        }
+
+        let role = role?;
+        self.roles
+            .iter()
+            .any(|r| r == role)
+            .then(|| BypassReason::Role(role.to_string()))
+    }
+
+    /// Issue a bypass token for a credential, valid for `ttl`
+    ///
+    /// Returns `None` if bypass tokens are disabled.
+    pub fn issue_token(&self, credential_id: Uuid, ttl: Duration) -> Option<String> {
+        let keys = self.token_keys.as_ref()?;
+        let now_ms = Utc::now().timestamp_millis();
+        Some(keys.sign(credential_id, now_ms, now_ms + ttl.as_millis() as i64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use platform::session_token::SessionKey;
+
+    fn policy() -> BypassPolicy {
+        BypassPolicy {
+            networks: vec!["10.0.0.0/8".parse().unwrap()],
+            token_keys: Some(SessionKeyRing::new(SessionKey::new(1, [3u8; 32]))),
+            roles: vec!["admin".into()],
+        }
+    }
+
+    #[test]
+    fn test_default_has_no_bypass() {
+        let policy = BypassPolicy::default();
+        assert!(!policy.is_enabled());
+        assert_eq!(
+            policy.check(Some("10.0.0.1".parse().unwrap()), None, Some("admin")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_network_bypass() {
+        let reason = policy().check(Some("10.1.2.3".parse().unwrap()), None, None);
+        assert_eq!(
+            reason,
+            Some(BypassReason::Network("10.0.0.0/8".parse().unwrap()))
+        );
+        assert_eq!(
+            policy().check(Some("192.0.2.1".parse().unwrap()), None, None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_token_bypass() {
+        let policy = policy();
+        let id = Uuid::new_v4();
+        let token = policy.issue_token(id, Duration::from_secs(60)).unwrap();
+
+        assert_eq!(
+            policy.check(None, Some(&token), None),
+            Some(BypassReason::Token(id))
+        );
+        assert_eq!(policy.check(None, Some("garbage"), None), None);
+
+        // Tokens signed with other keys are rejected
+        let other = BypassPolicy {
+            token_keys: Some(SessionKeyRing::new(SessionKey::new(1, [4u8; 32]))),
+            ..BypassPolicy::default()
+        };
+        let forged = other.issue_token(id, Duration::from_secs(60)).unwrap();
+        assert_eq!(policy.check(None, Some(&forged), None), None);
+    }
+
+    #[test]
+    fn test_role_bypass() {
+        assert_eq!(
+            policy().check(None, None, Some("admin")),
+            Some(BypassReason::Role("admin".into()))
+        );
+        assert_eq!(policy().check(None, None, Some("user")), None);
+    }
+}
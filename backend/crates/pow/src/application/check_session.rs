@@ -1,9 +1,11 @@
 //! Check Session Use Case
 
 use crate::application::config::PowConfig;
+use crate::domain::entities::PowSession;
 use crate::domain::repository::PowSessionRepository;
-use crate::domain::value_objects::ClientFingerprint;
+use crate::domain::value_objects::{ClientFingerprint, PowAction};
 use crate::error::PowResult;
+use chrono::Utc;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -27,12 +29,27 @@ where
         }
     }
 
-    /// Check if a pow session token is valid
+    /// Check if a general pow session token is valid
+    ///
+    /// Action-scoped proofs and sessions whose request budget is used up
+    /// are not accepted.
     pub async fn check(&self, token: &str, fingerprint: &ClientFingerprint) -> PowResult<bool> {
+        Ok(self
+            .session(token, fingerprint)
+            .await?
+            .is_some_and(|s| !s.is_exhausted()))
+    }
+
+    /// Look up the general pow session of a token without spending budget
+    pub async fn session(
+        &self,
+        token: &str,
+        fingerprint: &ClientFingerprint,
+    ) -> PowResult<Option<PowSession>> {
         // Verify token signature and get pow session ID
-        let pow_session_id = match verify_pow_session_token(token, &self.config.session_secret) {
+        let pow_session_id = match verify_pow_session_token(token, &self.config) {
             Some(id) => id,
-            None => return Ok(false),
+            None => return Ok(None),
         };
 
         // Check pow session in database
@@ -40,12 +57,51 @@ where
             .pow_session_repo
             .get(pow_session_id, fingerprint)
             .await?
-            .is_some())
+            .filter(PowSession::is_general))
+    }
+
+    /// Authorize one request with a general pow session
+    ///
+    /// Spends one request of the session budget. Returns the session with
+    /// the budget left after this request, or None if it cannot be used.
+    pub async fn authorize(
+        &self,
+        token: &str,
+        fingerprint: &ClientFingerprint,
+    ) -> PowResult<Option<PowSession>> {
+        let pow_session_id = match verify_pow_session_token(token, &self.config) {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+
+        self.pow_session_repo
+            .spend_request(pow_session_id, fingerprint)
+            .await
+    }
+
+    /// Redeem a single-use proof for `action`
+    ///
+    /// The proof is deleted, so a second redemption fails. The caller must
+    /// still check the returned payload hash against the request body.
+    pub async fn redeem(
+        &self,
+        token: &str,
+        fingerprint: &ClientFingerprint,
+        action: &PowAction,
+    ) -> PowResult<Option<PowSession>> {
+        let pow_session_id = match verify_pow_session_token(token, &self.config) {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+
+        self.pow_session_repo
+            .consume(pow_session_id, fingerprint, action)
+            .await
     }
 
     /// Delete a pow session
     pub async fn logout(&self, token: &str) -> PowResult<()> {
-        if let Some(pow_session_id) = verify_pow_session_token(token, &self.config.session_secret) {
+        if let Some(pow_session_id) = verify_pow_session_token(token, &self.config) {
             self.pow_session_repo.delete(pow_session_id).await?;
         }
         Ok(())
@@ -53,22 +109,11 @@ where
 }
 
 /// Verify and extract pow session ID from signed token
-fn verify_pow_session_token(token: &str, secret: &[u8; 32]) -> Option<Uuid> {
-    let token_data = platform::crypto::from_base64(token).ok()?;
-    if token_data.len() != 48 {
-        // 16 (UUID) + 32 (HMAC)
-        return None;
-    }
-
-    let id_bytes: [u8; 16] = token_data[0..16].try_into().ok()?;
-    let provided_signature: &[u8] = &token_data[16..48];
-
-    let expected_signature = platform::crypto::hmac_sha256(secret, &id_bytes);
-
-    // Constant-time comparison
-    if !platform::crypto::constant_time_eq(provided_signature, &expected_signature) {
-        return None;
-    }
-
-    Some(Uuid::from_bytes(id_bytes))
+pub(crate) fn verify_pow_session_token(token: &str, config: &PowConfig) -> Option<Uuid> {
+    config
+        .session_keys
+        .verify(token, Utc::now().timestamp_millis())
+        .map(|claims| claims.session_id)
+        .inspect_err(|e| tracing::debug!(error = %e, "PoW session token rejected"))
+        .ok()
 }
@@ -2,30 +2,134 @@
 //!
 //! Configuration for the PoW application layer.
 
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 
+use crate::application::bypass::BypassPolicy;
+use crate::domain::algorithm::PowAlgorithm;
+use crate::domain::ban::BanPolicy;
+use crate::domain::difficulty::{DifficultyPolicy, FixedDifficulty};
+use crate::domain::services::ProtocolVersion;
+use crate::domain::value_objects::{ClientNetworkKey, PowAction};
+use platform::rate_limit::MemoryRateLimitStore;
+
 /// Re-export SameSite from platform
 pub use platform::cookie::SameSite;
+/// Re-export rate limit configuration from platform
+pub use platform::rate_limit::RateLimitConfig;
+/// Re-export session token keys from platform
+pub use platform::session_token::{SessionKey, SessionKeyRing};
+
+/// Where issued challenges are kept until they are solved
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChallengeMode {
+    /// Challenges are stored in the database and deleted on submit
+    #[default]
+    Stored,
+    /// Challenges are HMAC-signed into a token returned to the client
+    ///
+    /// Only the ids of solved challenges are stored (until they expire)
+    /// to prevent double-spending.
+    Stateless,
+}
+
+/// Settings for challenges scoped to an action
+///
+/// A solved action challenge yields a single-use proof instead of a
+/// general session, so costly endpoints can demand more work per call.
+#[derive(Debug, Clone)]
+pub struct ActionPolicy {
+    /// Baseline difficulty (replaces `PowConfig.difficulty_bits`)
+    pub difficulty_bits: u8,
+    /// Challenge TTL
+    pub challenge_ttl: Duration,
+    /// How long a proof stays redeemable
+    pub proof_ttl: Duration,
+    /// Whether challenges must be bound to a hash of the request body
+    pub require_payload_hash: bool,
+}
+
+impl ActionPolicy {
+    /// Policy with the given difficulty and default TTLs
+    pub fn new(difficulty_bits: u8) -> Self {
+        Self {
+            difficulty_bits,
+            challenge_ttl: Duration::from_secs(120),
+            proof_ttl: Duration::from_secs(300),
+            require_payload_hash: false,
+        }
+    }
+
+    pub fn challenge_ttl_ms(&self) -> i64 {
+        self.challenge_ttl.as_millis() as i64
+    }
+
+    pub fn proof_ttl_ms(&self) -> i64 {
+        self.proof_ttl.as_millis() as i64
+    }
+}
 
 /// PoW application configuration
 #[derive(Debug, Clone)]
 pub struct PowConfig {
+    /// Challenge storage mode
+    pub challenge_mode: ChallengeMode,
     /// Challenge bytes length
     pub challenge_bytes_len: usize,
-    /// Difficulty in leading zero bits
+    /// Hash algorithm clients must evaluate
+    ///
+    /// Memory-hard algorithms need a much lower `difficulty_bits`.
+    pub algorithm: PowAlgorithm,
+    /// Number of sub-puzzles per challenge
+    ///
+    /// The difficulty chosen by the policy is the total work; it is split
+    /// across the sub-puzzles to reduce solve-time variance.
+    pub puzzle_count: u8,
+    /// Oldest wire protocol version clients may request
+    ///
+    /// v1 (32-bit nonces) stays accepted during the transition; raise this
+    /// to v2 once all clients send 64-bit nonces.
+    pub min_protocol_version: ProtocolVersion,
+    /// Baseline difficulty in leading zero bits
     pub difficulty_bits: u8,
+    /// Policy adjusting the difficulty per challenge (fixed by default)
+    pub difficulty_policy: Arc<dyn DifficultyPolicy>,
+    /// Window over which the policy observes client activity
+    pub difficulty_window: Duration,
+    /// Challenges issued by this instance, for policies reading `global_issued`
+    ///
+    /// Kept in memory so issuance does not update one shared row on every
+    /// challenge; with several instances each counts its own share.
+    pub issue_counter: Arc<MemoryRateLimitStore>,
     /// Challenge TTL
     pub challenge_ttl: Duration,
     /// Session TTL
     pub session_ttl: Duration,
-    /// Rate limit: max requests per window
+    /// Requests a session may authorize (None = unlimited within its TTL)
+    pub session_request_budget: Option<u32>,
+    /// Where clients get a new challenge (sent as a hint when a session is rejected)
+    pub challenge_url: String,
+    /// Actions challenges can be scoped to
+    pub actions: HashMap<PowAction, ActionPolicy>,
+    /// Rate limit: max challenges per window (GCRA: a full burst, then window/max each)
     pub rate_limit_max_requests: u32,
     /// Rate limit window
     pub rate_limit_window: Duration,
+    /// Max challenges per window for a whole client network (None = no network limit)
+    ///
+    /// Catches clients rotating User-Agents or addresses within a network.
+    pub network_rate_limit_max_requests: Option<u32>,
+    /// How client IPs are grouped into networks for limits, activity and bans
+    pub client_network: ClientNetworkKey,
+    /// Trusted clients that skip `require_pow_session`
+    pub bypass: BypassPolicy,
+    /// Automatic bans of abusive clients (None = disabled)
+    pub ban_policy: Option<BanPolicy>,
     /// Cookie name for session
     pub session_cookie_name: String,
-    /// Session secret key for HMAC signing (32 bytes)
-    pub session_secret: [u8; 32],
+    /// Keys for signing and verifying session tokens
+    pub session_keys: SessionKeyRing,
     /// Whether to require Secure cookie
     pub cookie_secure: bool,
     /// SameSite policy
@@ -35,14 +139,28 @@ pub struct PowConfig {
 impl Default for PowConfig {
     fn default() -> Self {
         Self {
+            challenge_mode: ChallengeMode::Stored,
             challenge_bytes_len: 32,
+            algorithm: PowAlgorithm::Sha256,
+            puzzle_count: 1,
+            min_protocol_version: ProtocolVersion::V1,
             difficulty_bits: 23,
+            difficulty_policy: Arc::new(FixedDifficulty),
+            difficulty_window: Duration::from_secs(60),
+            issue_counter: Arc::new(MemoryRateLimitStore::with_capacity(1, 1)),
             challenge_ttl: Duration::from_secs(120),
             session_ttl: Duration::from_secs(3600),
+            session_request_budget: None,
+            challenge_url: "/api/pow/challenge".to_string(),
+            actions: HashMap::new(),
             rate_limit_max_requests: 10,
             rate_limit_window: Duration::from_secs(60),
+            network_rate_limit_max_requests: Some(60),
+            client_network: ClientNetworkKey::default(),
+            bypass: BypassPolicy::default(),
+            ban_policy: Some(BanPolicy::default()),
             session_cookie_name: "pow_session".to_string(),
-            session_secret: [0u8; 32],
+            session_keys: SessionKeyRing::new(SessionKey::new(1, [0u8; 32])),
             cookie_secure: true,
             cookie_same_site: SameSite::Lax,
         }
@@ -52,11 +170,8 @@ impl Default for PowConfig {
 impl PowConfig {
     /// Create config with a random session secret (for development)
     pub fn with_random_secret() -> Self {
-        use rand::RngCore;
-        let mut secret = [0u8; 32];
-        rand::rng().fill_bytes(&mut secret);
         Self {
-            session_secret: secret,
+            session_keys: SessionKeyRing::new(SessionKey::random(1)),
             ..Default::default()
         }
     }
@@ -69,6 +184,17 @@ impl PowConfig {
         }
     }
 
+    /// Allow challenges scoped to `action`
+    pub fn with_action(mut self, action: PowAction, policy: ActionPolicy) -> Self {
+        self.actions.insert(action, policy);
+        self
+    }
+
+    /// Policy of a configured action
+    pub fn action_policy(&self, action: &PowAction) -> Option<&ActionPolicy> {
+        self.actions.get(action)
+    }
+
     pub fn challenge_ttl_ms(&self) -> i64 {
         self.challenge_ttl.as_millis() as i64
     }
@@ -77,7 +203,38 @@ impl PowConfig {
         self.session_ttl.as_millis() as i64
     }
 
-    pub fn rate_limit_window_ms(&self) -> i64 {
-        self.rate_limit_window.as_millis() as i64
+    pub fn difficulty_window_ms(&self) -> i64 {
+        self.difficulty_window.as_millis() as i64
+    }
+
+    /// Limit on challenge issuance per client
+    pub fn rate_limit(&self) -> RateLimitConfig {
+        RateLimitConfig {
+            max_requests: self.rate_limit_max_requests,
+            window: self.rate_limit_window,
+        }
+    }
+
+    /// Limit sizing [`Self::issue_counter`] (None if the policy ignores `global_issued`)
+    ///
+    /// Never rejects in practice: the counter saturates at the policy's cap.
+    pub fn global_issue_counter(&self) -> Option<RateLimitConfig> {
+        let window_ms = u32::try_from(self.difficulty_window_ms()).unwrap_or(u32::MAX);
+        self.difficulty_policy
+            .global_issued_cap()
+            // GCRA needs at least 1ms per request
+            .map(|cap| RateLimitConfig {
+                max_requests: cap.clamp(1, window_ms.max(1)),
+                window: self.difficulty_window,
+            })
+    }
+
+    /// Limit on challenge issuance per client network
+    pub fn network_rate_limit(&self) -> Option<RateLimitConfig> {
+        self.network_rate_limit_max_requests
+            .map(|max_requests| RateLimitConfig {
+                max_requests,
+                window: self.rate_limit_window,
+            })
     }
 }
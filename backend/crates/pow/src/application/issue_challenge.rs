@@ -1,84 +1,269 @@
 //! Issue Challenge Use Case
 
-use crate::application::config::PowConfig;
+use crate::application::ban_guard::BanGuard;
+use crate::application::config::{ChallengeMode, PowConfig};
+use crate::domain::algorithm::PowAlgorithm;
+use crate::domain::ban::OffenseKind;
+use crate::domain::challenge_token::encode_challenge_token;
+use crate::domain::difficulty::DifficultyInputs;
 use crate::domain::entities::Challenge;
-use crate::domain::repository::{ChallengeRepository, RateLimitRepository};
-use crate::domain::value_objects::ClientFingerprint;
+use crate::domain::repository::{ActivityRepository, BanRepository, ChallengeRepository};
+use crate::domain::services::{MAX_PUZZLE_COUNT, ProtocolVersion, split_difficulty};
+use crate::domain::value_objects::{ClientFingerprint, PowAction};
 use crate::error::{PowError, PowResult};
-use platform::crypto::random_bytes;
+use chrono::Utc;
+use platform::crypto::{random_bytes, to_base64};
+use platform::rate_limit::{RateLimitConfig, RateLimitResult, RateLimitStore};
 use std::sync::Arc;
 
+/// Input DTO for issue challenge
+#[derive(Debug, Clone, Default)]
+pub struct IssueChallengeInput {
+    /// Action to scope the challenge to (None = general session)
+    pub action: Option<PowAction>,
+    /// SHA-256 of the request body the proof will be used for
+    pub payload_hash: Option<Vec<u8>>,
+    /// Wire protocol version the client speaks
+    pub protocol_version: ProtocolVersion,
+}
+
 /// Output DTO for issue challenge
 #[derive(Debug, Clone)]
 pub struct IssueChallengeOutput {
     pub challenge_id: uuid::Uuid,
     pub challenge_b64: String,
+    pub algorithm: PowAlgorithm,
+    pub protocol_version: ProtocolVersion,
+    pub puzzle_count: u8,
+    /// Difficulty of each sub-puzzle
     pub difficulty_bits: u8,
     pub expires_at_ms: i64,
+    pub action: Option<PowAction>,
+    /// Signed challenge (stateless mode only)
+    pub challenge_token: Option<String>,
 }
 
 /// Issue Challenge Use Case
-pub struct IssueChallengeUseCase<C, R>
+pub struct IssueChallengeUseCase<C, R, A, B>
 where
     C: ChallengeRepository,
-    R: RateLimitRepository,
+    R: RateLimitStore,
+    A: ActivityRepository,
+    B: BanRepository,
 {
     challenge_repo: Arc<C>,
     rate_limit_repo: Arc<R>,
+    activity_repo: Arc<A>,
+    ban_guard: BanGuard<B>,
     config: Arc<PowConfig>,
 }
 
-impl<C, R> IssueChallengeUseCase<C, R>
+impl<C, R, A, B> IssueChallengeUseCase<C, R, A, B>
 where
     C: ChallengeRepository,
-    R: RateLimitRepository,
+    R: RateLimitStore,
+    A: ActivityRepository,
+    B: BanRepository,
 {
-    pub fn new(challenge_repo: Arc<C>, rate_limit_repo: Arc<R>, config: Arc<PowConfig>) -> Self {
+    pub fn new(
+        challenge_repo: Arc<C>,
+        rate_limit_repo: Arc<R>,
+        activity_repo: Arc<A>,
+        ban_repo: Arc<B>,
+        config: Arc<PowConfig>,
+    ) -> Self {
         Self {
             challenge_repo,
             rate_limit_repo,
+            activity_repo,
+            ban_guard: BanGuard::new(ban_repo, config.clone()),
             config,
         }
     }
 
-    pub async fn execute(&self, fingerprint: ClientFingerprint) -> PowResult<IssueChallengeOutput> {
-        // Check rate limit
-        let allowed = self
-            .rate_limit_repo
-            .check(
-                &fingerprint,
-                self.config.rate_limit_max_requests,
-                self.config.rate_limit_window_ms(),
+    pub async fn execute(
+        &self,
+        input: IssueChallengeInput,
+        fingerprint: ClientFingerprint,
+    ) -> PowResult<IssueChallengeOutput> {
+        // Resolve baseline and TTL (per action if scoped)
+        let (baseline, ttl_ms) = match &input.action {
+            Some(action) => {
+                let policy = self.config.action_policy(action).ok_or_else(|| {
+                    PowError::InvalidRequest(format!("Unknown PoW action: {action}"))
+                })?;
+                if policy.require_payload_hash && input.payload_hash.is_none() {
+                    return Err(PowError::InvalidRequest(format!(
+                        "PoW action {action} requires a payload hash"
+                    )));
+                }
+                (policy.difficulty_bits, policy.challenge_ttl_ms())
+            }
+            None if input.payload_hash.is_some() => {
+                return Err(PowError::InvalidRequest(
+                    "A payload hash requires an action".into(),
+                ));
+            }
+            None => (self.config.difficulty_bits, self.config.challenge_ttl_ms()),
+        };
+        if input.payload_hash.as_ref().is_some_and(|h| h.len() != 32) {
+            return Err(PowError::InvalidRequest(
+                "Payload hash must be a SHA-256 digest".into(),
+            ));
+        }
+        if input.protocol_version < self.config.min_protocol_version {
+            return Err(PowError::InvalidRequest(format!(
+                "PoW protocol version {} is no longer supported",
+                input.protocol_version.as_u8()
+            )));
+        }
+
+        self.ban_guard.check(&fingerprint).await?;
+
+        // Check rate limits (per client, then per client network)
+        let limit = self
+            .check_rate_limit(
+                &format!(
+                    "pow:challenge:{}",
+                    to_base64(&self.config.client_network.client_hash(&fingerprint))
+                ),
+                &self.config.rate_limit(),
             )
             .await?;
 
-        if !allowed {
+        if !limit.allowed {
+            tracing::warn!(
+                retry_after_ms = limit.retry_after_ms,
+                max = self.config.rate_limit_max_requests,
+                "Rate limit exceeded"
+            );
+            self.ban_guard
+                .record_offense(OffenseKind::ChallengeFlood, &fingerprint)
+                .await;
             return Err(PowError::RateLimitExceeded);
         }
 
+        let client_issued = issued_before(&limit, self.config.rate_limit_max_requests);
+
+        let mut network_issued = 0;
+        if let (Some(network), Some(config)) = (
+            self.config.client_network.key(&fingerprint),
+            self.config.network_rate_limit(),
+        ) {
+            let limit = self
+                .check_rate_limit(&format!("pow:challenge:net:{network}"), &config)
+                .await?;
+            network_issued = issued_before(&limit, config.max_requests);
+            if !limit.allowed {
+                // Not an offense: the network may be shared by innocent clients
+                tracing::warn!(
+                    network = %network,
+                    retry_after_ms = limit.retry_after_ms,
+                    max = config.max_requests,
+                    "Network rate limit exceeded"
+                );
+                return Err(PowError::RateLimitExceeded);
+            }
+        }
+
+        // Decide difficulty from recent activity (issuance counts come from the
+        // rate-limit counters, so issuing writes nothing to the activity log)
+        let policy = &self.config.difficulty_policy;
+        let mut inputs = if policy.observes_activity() {
+            self.activity_repo
+                .summarize(&fingerprint, self.config.difficulty_window_ms())
+                .await?
+        } else {
+            DifficultyInputs::default()
+        };
+        inputs.client_issued = client_issued;
+        inputs.network_issued = network_issued;
+        if let Some(counter) = self.config.global_issue_counter() {
+            let count = self.config.issue_counter.check_at(
+                "pow:challenge:global",
+                &counter,
+                Utc::now().timestamp_millis(),
+            );
+            inputs.global_issued = issued_before(&count, counter.max_requests);
+        }
+        let total_bits = policy.difficulty_bits(baseline, &inputs);
+        let puzzle_count = self.config.puzzle_count.clamp(1, MAX_PUZZLE_COUNT);
+        let difficulty_bits = split_difficulty(total_bits, puzzle_count);
+
         // Generate challenge
         let challenge_bytes = random_bytes(self.config.challenge_bytes_len);
-        let challenge = Challenge::new(
+        let mut challenge = Challenge::new(
             challenge_bytes.clone(),
-            self.config.difficulty_bits,
-            self.config.challenge_ttl_ms(),
+            self.config.algorithm,
+            difficulty_bits,
+            ttl_ms,
             fingerprint.hash_vec(),
             fingerprint.ip,
-        );
+        )
+        .with_puzzle_count(puzzle_count)
+        .with_protocol_version(input.protocol_version);
+        if let Some(action) = input.action {
+            challenge = challenge.with_action(action, input.payload_hash);
+        }
 
-        self.challenge_repo.create(&challenge).await?;
+        let challenge_token = match self.config.challenge_mode {
+            ChallengeMode::Stored => {
+                self.challenge_repo.create(&challenge).await?;
+                None
+            }
+            ChallengeMode::Stateless => Some(encode_challenge_token(
+                &challenge,
+                &self.config.session_keys,
+            )),
+        };
 
         tracing::info!(
             challenge_id = %challenge.id,
-            difficulty = self.config.difficulty_bits,
+            algorithm = self.config.algorithm.id(),
+            protocol_version = challenge.protocol_version.as_u8(),
+            action = challenge.action.as_ref().map(PowAction::as_str),
+            baseline = baseline,
+            difficulty = total_bits,
+            puzzle_count = puzzle_count,
+            puzzle_difficulty = difficulty_bits,
+            global_issued = inputs.global_issued,
+            client_issued = inputs.client_issued,
+            network_issued = inputs.network_issued,
+            client_invalid_nonces = inputs.client_invalid_nonces,
+            client_sessions = inputs.client_sessions,
             "Issued challenge"
         );
 
         Ok(IssueChallengeOutput {
             challenge_id: challenge.id,
             challenge_b64: platform::crypto::to_base64(&challenge_bytes),
-            difficulty_bits: self.config.difficulty_bits,
+            algorithm: self.config.algorithm,
+            protocol_version: challenge.protocol_version,
+            puzzle_count,
+            difficulty_bits,
             expires_at_ms: challenge.expires_at_ms,
+            action: challenge.action.clone(),
+            challenge_token,
         })
     }
+
+    async fn check_rate_limit(
+        &self,
+        key: &str,
+        config: &RateLimitConfig,
+    ) -> PowResult<RateLimitResult> {
+        self.rate_limit_repo
+            .check_and_increment(key, config)
+            .await
+            .map_err(|e| PowError::Internal(format!("Rate limit store failed: {e}")))
+    }
+}
+
+/// Requests counted before this one by a rate-limit check (saturates at `max`)
+fn issued_before(result: &RateLimitResult, max: u32) -> u32 {
+    if result.allowed {
+        max.saturating_sub(result.remaining).saturating_sub(1)
+    } else {
+        max
+    }
 }
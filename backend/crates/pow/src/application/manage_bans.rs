@@ -0,0 +1,63 @@
+//! Manage Bans Use Cases
+//!
+//! Lists active bans and lifts them early (admin only).
+
+use crate::domain::ban::Ban;
+use crate::domain::repository::BanRepository;
+use crate::error::{PowError, PowResult};
+use chrono::Utc;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// List Bans Use Case
+pub struct ListBansUseCase<B>
+where
+    B: BanRepository,
+{
+    ban_repo: Arc<B>,
+}
+
+impl<B> ListBansUseCase<B>
+where
+    B: BanRepository,
+{
+    pub fn new(ban_repo: Arc<B>) -> Self {
+        Self { ban_repo }
+    }
+
+    /// Active bans, newest first
+    pub async fn execute(&self) -> PowResult<Vec<Ban>> {
+        self.ban_repo
+            .list_active_bans(Utc::now().timestamp_millis())
+            .await
+    }
+}
+
+/// Lift Ban Use Case
+pub struct LiftBanUseCase<B>
+where
+    B: BanRepository,
+{
+    ban_repo: Arc<B>,
+}
+
+impl<B> LiftBanUseCase<B>
+where
+    B: BanRepository,
+{
+    pub fn new(ban_repo: Arc<B>) -> Self {
+        Self { ban_repo }
+    }
+
+    pub async fn execute(&self, ban_id: Uuid) -> PowResult<()> {
+        let lifted = self
+            .ban_repo
+            .lift_ban(ban_id, Utc::now().timestamp_millis())
+            .await?;
+
+        if !lifted {
+            return Err(PowError::BanNotFound);
+        }
+        Ok(())
+    }
+}
@@ -3,7 +3,11 @@
 //! This layer orchestrates domain logic and infrastructure.
 //! Contains use case implementations.
 
+pub mod ban_guard;
+pub mod bypass;
 pub mod check_session;
 pub mod config;
 pub mod issue_challenge;
+pub mod manage_bans;
 pub mod submit_solution;
+pub mod telemetry_report;
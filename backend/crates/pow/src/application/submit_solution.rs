@@ -1,11 +1,21 @@
 //! Submit Solution Use Case
 
-use crate::application::config::PowConfig;
-use crate::domain::entities::PowSession;
-use crate::domain::repository::{ChallengeRepository, PowSessionRepository};
-use crate::domain::services::verify_pow;
-use crate::domain::value_objects::ClientFingerprint;
+use crate::application::ban_guard::BanGuard;
+use crate::application::check_session::verify_pow_session_token;
+use crate::application::config::{ChallengeMode, PowConfig};
+use crate::domain::ban::OffenseKind;
+use crate::domain::challenge_token::decode_challenge_token;
+use crate::domain::entities::{ActivityKind, Challenge, PowSession};
+use crate::domain::repository::{
+    ActivityRepository, BanRepository, ChallengeRepository, PowSessionRepository,
+    TelemetryRepository,
+};
+use crate::domain::services::ProtocolVersion;
+use crate::domain::telemetry::SolveSample;
+use crate::domain::value_objects::{ClientFingerprint, PowAction};
 use crate::error::{PowError, PowResult};
+use chrono::Utc;
+use platform::crypto::constant_time_eq;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -13,11 +23,21 @@ use uuid::Uuid;
 #[derive(Debug, Clone)]
 pub struct SubmitSolutionInput {
     pub challenge_id: Uuid,
-    pub nonce_u32: u32,
+    /// Signed challenge (required in stateless mode)
+    pub challenge_token: Option<String>,
+    /// Wire protocol version the nonces are encoded for
+    pub protocol_version: ProtocolVersion,
+    /// One nonce per sub-puzzle
+    pub nonces: Vec<u64>,
     /// Telemetry only - not trusted
     pub elapsed_ms: Option<i64>,
     /// Telemetry only - not trusted
     pub total_hashes: Option<i64>,
+    /// Session token the client already holds
+    ///
+    /// A still-valid general session is extended instead of creating a
+    /// second one.
+    pub current_session_token: Option<String>,
 }
 
 /// Output DTO for submit solution
@@ -26,28 +46,51 @@ pub struct SubmitSolutionOutput {
     pub session_id: Uuid,
     pub session_token: String,
     pub expires_at_ms: i64,
+    /// Action the token is a single-use proof for (None = general session)
+    pub action: Option<PowAction>,
+    /// Whether an existing session was extended
+    pub renewed: bool,
 }
 
 /// Submit Solution Use Case
-pub struct SubmitSolutionUseCase<C, S>
+pub struct SubmitSolutionUseCase<C, S, A, T, B>
 where
     C: ChallengeRepository,
     S: PowSessionRepository,
+    A: ActivityRepository,
+    T: TelemetryRepository,
+    B: BanRepository,
 {
     challenge_repo: Arc<C>,
     pow_session_repo: Arc<S>,
+    activity_repo: Arc<A>,
+    telemetry_repo: Arc<T>,
+    ban_guard: BanGuard<B>,
     config: Arc<PowConfig>,
 }
 
-impl<C, S> SubmitSolutionUseCase<C, S>
+impl<C, S, A, T, B> SubmitSolutionUseCase<C, S, A, T, B>
 where
     C: ChallengeRepository,
     S: PowSessionRepository,
+    A: ActivityRepository,
+    T: TelemetryRepository,
+    B: BanRepository,
 {
-    pub fn new(challenge_repo: Arc<C>, pow_session_repo: Arc<S>, config: Arc<PowConfig>) -> Self {
+    pub fn new(
+        challenge_repo: Arc<C>,
+        pow_session_repo: Arc<S>,
+        activity_repo: Arc<A>,
+        telemetry_repo: Arc<T>,
+        ban_repo: Arc<B>,
+        config: Arc<PowConfig>,
+    ) -> Self {
         Self {
             challenge_repo,
             pow_session_repo,
+            activity_repo,
+            telemetry_repo,
+            ban_guard: BanGuard::new(ban_repo, config.clone()),
             config,
         }
     }
@@ -67,37 +110,94 @@ where
             );
         }
 
-        // Atomically consume the challenge
-        let challenge = self
-            .challenge_repo
-            .consume(input.challenge_id, &fingerprint)
-            .await?
-            .ok_or(PowError::ChallengeNotFound)?;
-
-        // Verify the solution
-        if !verify_pow(
-            &challenge.challenge_bytes,
-            input.nonce_u32,
-            challenge.difficulty_bits,
-        ) {
+        self.ban_guard.check(&fingerprint).await?;
+
+        let challenge = match self.config.challenge_mode {
+            ChallengeMode::Stored => self.consume_stored(&input, &fingerprint).await?,
+            ChallengeMode::Stateless => self.consume_token(&input, &fingerprint).await?,
+        };
+
+        // The nonces must be encoded the way the challenge was issued
+        if input.protocol_version != challenge.protocol_version {
+            tracing::warn!(
+                challenge_id = %input.challenge_id,
+                challenge_version = challenge.protocol_version.as_u8(),
+                submit_version = input.protocol_version.as_u8(),
+                "Protocol version mismatch"
+            );
+            return Err(PowError::InvalidRequest(format!(
+                "Challenge was issued for PoW protocol version {}",
+                challenge.protocol_version.as_u8()
+            )));
+        }
+
+        // Verify the solution (one nonce per sub-puzzle, checked before hashing)
+        let count_matches = input.nonces.len() == challenge.puzzle_count as usize;
+        if !count_matches || !verify_solution(challenge.clone(), input.nonces.clone()).await? {
             tracing::warn!(
                 challenge_id = %input.challenge_id,
-                nonce = input.nonce_u32,
+                nonce_count = input.nonces.len(),
+                puzzle_count = challenge.puzzle_count,
                 "Invalid nonce"
             );
+            self.record_activity(ActivityKind::InvalidNonce, &fingerprint)
+                .await;
+            self.ban_guard
+                .record_offense(OffenseKind::InvalidNonce, &fingerprint)
+                .await;
             return Err(PowError::InvalidNonce);
         }
 
-        // Create pow session
-        let pow_session = PowSession::new(&challenge, self.config.session_ttl_ms());
-        self.pow_session_repo.create(&pow_session).await?;
+        // Create pow session (a short-lived proof for action-scoped challenges)
+        let ttl_ms = match &challenge.action {
+            Some(action) => self
+                .config
+                .action_policy(action)
+                .map_or(self.config.session_ttl_ms(), |p| p.proof_ttl_ms()),
+            None => self.config.session_ttl_ms(),
+        };
+        let mut pow_session = PowSession::new(&challenge, ttl_ms);
+        if pow_session.is_general() {
+            pow_session = pow_session.with_request_budget(self.config.session_request_budget);
+        }
+
+        // Extend the client's still-valid session instead of adding a parallel one
+        let current_session_id = input
+            .current_session_token
+            .as_deref()
+            .filter(|_| pow_session.is_general())
+            .and_then(|token| verify_pow_session_token(token, &self.config));
+        let renewed = match current_session_id {
+            Some(id) => {
+                self.pow_session_repo
+                    .renew(id, &fingerprint, &pow_session)
+                    .await?
+            }
+            None => None,
+        };
+        let is_renewal = renewed.is_some();
+        let pow_session = match renewed {
+            Some(session) => session,
+            None => {
+                self.pow_session_repo.create(&pow_session).await?;
+                pow_session
+            }
+        };
+        self.record_activity(ActivityKind::SessionCreated, &fingerprint)
+            .await;
+        self.record_solve(&challenge, &input, &fingerprint).await;
 
         // Create signed pow session token
-        let token = create_pow_session_token(&pow_session.id, &self.config.session_secret);
+        let token = self.config.session_keys.sign(
+            pow_session.id,
+            Utc::now().timestamp_millis(),
+            pow_session.expires_at_ms,
+        );
 
         tracing::info!(
             challenge_id = %input.challenge_id,
             pow_session_id = %pow_session.id,
+            renewed = is_renewal,
             "PoW verification successful"
         );
 
@@ -105,16 +205,114 @@ where
             session_id: pow_session.id,
             session_token: token,
             expires_at_ms: pow_session.expires_at_ms,
+            action: pow_session.action,
+            renewed: is_renewal,
         })
     }
+
+    /// Consume a challenge stored at issuance
+    async fn consume_stored(
+        &self,
+        input: &SubmitSolutionInput,
+        fingerprint: &ClientFingerprint,
+    ) -> PowResult<Challenge> {
+        match self
+            .challenge_repo
+            .consume(input.challenge_id, fingerprint)
+            .await
+        {
+            Ok(challenge) => challenge.ok_or(PowError::ChallengeNotFound),
+            Err(PowError::ChallengeFingerprintMismatch) => {
+                self.ban_guard
+                    .record_offense(OffenseKind::FingerprintMismatch, fingerprint)
+                    .await;
+                Err(PowError::ChallengeNotFound)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Verify a signed challenge and mark it as consumed
+    async fn consume_token(
+        &self,
+        input: &SubmitSolutionInput,
+        fingerprint: &ClientFingerprint,
+    ) -> PowResult<Challenge> {
+        let token = input
+            .challenge_token
+            .as_deref()
+            .ok_or_else(|| PowError::InvalidRequest("challengeToken is required".into()))?;
+
+        let challenge = decode_challenge_token(token, &self.config.session_keys)
+            .filter(|c| c.id == input.challenge_id)
+            .ok_or_else(|| {
+                tracing::warn!(challenge_id = %input.challenge_id, "Invalid challenge token");
+                PowError::ChallengeNotFound
+            })?;
+
+        if challenge.expires_at_ms <= Utc::now().timestamp_millis() {
+            tracing::warn!(challenge_id = %challenge.id, "Challenge expired");
+            return Err(PowError::ChallengeExpired);
+        }
+
+        if !constant_time_eq(&challenge.client_fingerprint_hash, &fingerprint.hash_vec()) {
+            tracing::warn!(challenge_id = %challenge.id, "Challenge fingerprint mismatch");
+            self.ban_guard
+                .record_offense(OffenseKind::FingerprintMismatch, fingerprint)
+                .await;
+            return Err(PowError::ChallengeNotFound);
+        }
+
+        if !self
+            .challenge_repo
+            .mark_consumed(challenge.id, challenge.expires_at_ms)
+            .await?
+        {
+            tracing::warn!(challenge_id = %challenge.id, "Challenge already consumed");
+            return Err(PowError::ChallengeNotFound);
+        }
+
+        tracing::info!(challenge_id = %challenge.id, "Challenge consumed");
+        Ok(challenge)
+    }
+
+    /// Record activity for the difficulty policy (failures are only logged)
+    async fn record_activity(&self, kind: ActivityKind, fingerprint: &ClientFingerprint) {
+        if let Err(e) = self.activity_repo.record(kind, fingerprint).await {
+            tracing::warn!(error = %e, kind = kind.as_str(), "Failed to record PoW activity");
+        }
+    }
+
+    /// Add the solve to the telemetry rollup (failures are only logged)
+    async fn record_solve(
+        &self,
+        challenge: &Challenge,
+        input: &SubmitSolutionInput,
+        fingerprint: &ClientFingerprint,
+    ) {
+        let now = Utc::now();
+        let sample = SolveSample::new(challenge, fingerprint.user_agent.as_deref(), now)
+            .with_client_report(input.elapsed_ms, input.total_hashes);
+        if let Err(e) = self
+            .telemetry_repo
+            .record_solve(&sample, now.timestamp_millis())
+            .await
+        {
+            tracing::warn!(error = %e, challenge_id = %challenge.id, "Failed to record PoW solve telemetry");
+        }
+    }
 }
 
-/// Create a signed pow session token
-fn create_pow_session_token(pow_session_id: &Uuid, pow_session_secret: &[u8; 32]) -> String {
-    let id_bytes = pow_session_id.as_bytes();
-    let signature = platform::crypto::hmac_sha256(pow_session_secret, id_bytes);
-    let mut token_data = Vec::with_capacity(16 + 32);
-    token_data.extend_from_slice(id_bytes);
-    token_data.extend_from_slice(&signature);
-    platform::crypto::to_base64(&token_data)
+/// Verify the nonces with the algorithm the challenge was issued with
+///
+/// Memory-hard algorithms run on the blocking pool so a single
+/// verification does not stall the async executor.
+async fn verify_solution(challenge: Challenge, nonces: Vec<u64>) -> PowResult<bool> {
+    if !challenge.algorithm.is_memory_hard() {
+        return Ok(challenge.verify(&nonces));
+    }
+
+    tokio::task::spawn_blocking(move || challenge.verify(&nonces))
+        .await
+        .map_err(|e| PowError::Internal(format!("PoW verification task failed: {e}")))
 }
@@ -0,0 +1,115 @@
+//! Telemetry Report Use Case
+//!
+//! Solve-time percentiles per work and device class, and the baseline
+//! difficulty that would hit a target median solve time.
+
+use crate::domain::repository::TelemetryRepository;
+use crate::domain::telemetry::{DeviceClass, SolveStats, suggest_difficulty, summarize_rollups};
+use crate::error::PowResult;
+use chrono::Utc;
+use std::sync::Arc;
+
+/// Input DTO for the telemetry report
+#[derive(Debug, Clone)]
+pub struct TelemetryReportInput {
+    /// How far back to look
+    pub window_ms: i64,
+    /// Median solve time the suggestion aims for
+    pub target_median_ms: i64,
+    /// Groups with fewer solves are left out of the suggestion
+    pub min_samples: u64,
+}
+
+impl Default for TelemetryReportInput {
+    fn default() -> Self {
+        Self {
+            window_ms: 24 * 3_600_000, // 24 hours
+            target_median_ms: 2_000,
+            min_samples: 20,
+        }
+    }
+}
+
+/// Suggested baseline difficulty for one algorithm
+#[derive(Debug, Clone, PartialEq)]
+pub struct DifficultySuggestion {
+    pub algorithm: String,
+    /// None = all device classes except bots
+    pub device_class: Option<DeviceClass>,
+    pub difficulty_bits: u8,
+    pub samples: u64,
+}
+
+/// Output DTO for the telemetry report
+#[derive(Debug, Clone)]
+pub struct TelemetryReport {
+    pub since_ms: i64,
+    pub target_median_ms: i64,
+    pub stats: Vec<SolveStats>,
+    pub suggestions: Vec<DifficultySuggestion>,
+}
+
+/// Telemetry Report Use Case
+pub struct TelemetryReportUseCase<T>
+where
+    T: TelemetryRepository,
+{
+    telemetry_repo: Arc<T>,
+}
+
+impl<T> TelemetryReportUseCase<T>
+where
+    T: TelemetryRepository,
+{
+    pub fn new(telemetry_repo: Arc<T>) -> Self {
+        Self { telemetry_repo }
+    }
+
+    pub async fn execute(&self, input: TelemetryReportInput) -> PowResult<TelemetryReport> {
+        let since_ms = Utc::now().timestamp_millis() - input.window_ms;
+        let rows = self.telemetry_repo.solve_rollups(since_ms).await?;
+        let stats = summarize_rollups(&rows);
+
+        Ok(TelemetryReport {
+            since_ms,
+            target_median_ms: input.target_median_ms,
+            suggestions: suggestions(&stats, &input),
+            stats,
+        })
+    }
+}
+
+/// One suggestion per algorithm overall and per device class
+///
+/// Bots are reported but never drive the suggestion.
+fn suggestions(stats: &[SolveStats], input: &TelemetryReportInput) -> Vec<DifficultySuggestion> {
+    let mut algorithms: Vec<&str> = stats.iter().map(|s| s.algorithm.as_str()).collect();
+    algorithms.dedup();
+
+    let classes = [None, Some(DeviceClass::Desktop), Some(DeviceClass::Mobile)];
+
+    algorithms
+        .into_iter()
+        .flat_map(|algorithm| {
+            classes.into_iter().filter_map(move |device_class| {
+                let group: Vec<&SolveStats> = stats
+                    .iter()
+                    .filter(|s| s.algorithm == algorithm && s.device_class != DeviceClass::Bot)
+                    .filter(|s| device_class.is_none_or(|c| s.device_class == c))
+                    .filter(|s| s.samples >= input.min_samples)
+                    .collect();
+                let difficulty_bits = suggest_difficulty(
+                    group.iter().copied(),
+                    input.target_median_ms,
+                    input.min_samples,
+                )?;
+                Some(DifficultySuggestion {
+                    algorithm: algorithm.to_string(),
+                    device_class,
+                    difficulty_bits,
+                    samples: group.iter().map(|s| s.samples).sum(),
+                })
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,6 @@
+//! PoW Algorithms
+//!
+//! The hash function a client must evaluate for `(challenge, nonce)`.
+//! Defined in `pow-core`, shared with the native and WebAssembly solvers.
+
+pub use pow_core::algorithm::*;
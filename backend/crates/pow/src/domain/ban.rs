@@ -0,0 +1,263 @@
+//! Ban Policy
+//!
+//! Clients that keep misbehaving (invalid nonces, challenge floods,
+//! fingerprint mismatches) are banned temporarily. Offenses are counted
+//! per client (fingerprint within its IP prefix) and per IP prefix within
+//! a window; crossing a threshold bans that subject. Every further ban of
+//! the same subject within the strike memory doubles the duration, up to
+//! a maximum.
+
+use std::time::Duration;
+
+use uuid::Uuid;
+
+/// Misbehaviour counted towards a ban
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffenseKind {
+    /// A submitted nonce did not meet the difficulty
+    InvalidNonce,
+    /// Challenges requested beyond the rate limit
+    ChallengeFlood,
+    /// A challenge was submitted from another fingerprint than it was issued to
+    FingerprintMismatch,
+}
+
+impl OffenseKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OffenseKind::InvalidNonce => "invalid_nonce",
+            OffenseKind::ChallengeFlood => "challenge_flood",
+            OffenseKind::FingerprintMismatch => "fingerprint_mismatch",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "invalid_nonce" => Some(OffenseKind::InvalidNonce),
+            "challenge_flood" => Some(OffenseKind::ChallengeFlood),
+            "fingerprint_mismatch" => Some(OffenseKind::FingerprintMismatch),
+            _ => None,
+        }
+    }
+}
+
+/// What a ban applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BanSubject {
+    /// One client (fingerprint hash within its network, see [`BanKeys`])
+    Fingerprint(Vec<u8>),
+    /// Every client in an IP prefix (CIDR notation)
+    Network(String),
+}
+
+impl BanSubject {
+    /// Subject kind as stored (`fingerprint` or `network`)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BanSubject::Fingerprint(_) => "fingerprint",
+            BanSubject::Network(_) => "network",
+        }
+    }
+
+    /// Printable subject (hex fingerprint hash or CIDR)
+    pub fn display(&self) -> String {
+        match self {
+            BanSubject::Fingerprint(hash) => hex::encode(hash),
+            BanSubject::Network(network) => network.clone(),
+        }
+    }
+}
+
+/// A temporary ban
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ban {
+    pub id: Uuid,
+    pub subject: BanSubject,
+    /// Offense that crossed the threshold
+    pub reason: OffenseKind,
+    /// Offenses counted in the window when the ban was issued
+    pub offense_count: u32,
+    /// 1 for the first ban of the subject within the strike memory, then 2, 3, ...
+    pub strike: u32,
+    pub created_at_ms: i64,
+    pub expires_at_ms: i64,
+}
+
+impl Ban {
+    pub fn new(
+        subject: BanSubject,
+        reason: OffenseKind,
+        offense_count: u32,
+        strike: u32,
+        duration: Duration,
+        now_ms: i64,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            subject,
+            reason,
+            offense_count,
+            strike,
+            created_at_ms: now_ms,
+            expires_at_ms: now_ms + duration.as_millis() as i64,
+        }
+    }
+
+    /// Whole seconds until the ban expires (at least 1, for `Retry-After`)
+    pub fn retry_after_secs(&self, now_ms: i64) -> u64 {
+        let remaining_ms = (self.expires_at_ms - now_ms).max(0) as u64;
+        remaining_ms.div_ceil(1000).max(1)
+    }
+}
+
+/// Keys a client's offenses and bans are tracked under
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BanKeys {
+    /// Fingerprint within its network (`ClientNetworkKey::client_hash`)
+    pub client_hash: Vec<u8>,
+    /// IP prefix in CIDR notation (None without a client IP)
+    pub network: Option<String>,
+}
+
+/// Offenses recorded within the policy window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OffenseCounts {
+    /// Offenses of this client
+    pub fingerprint: u32,
+    /// Offenses of this client's IP prefix (0 without a client IP)
+    pub network: u32,
+}
+
+/// Thresholds and durations of automatic bans
+///
+/// A threshold of 0 disables bans of that subject.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BanPolicy {
+    /// Window over which offenses are counted
+    pub offense_window: Duration,
+    /// Offenses of one fingerprint that trigger a fingerprint ban
+    pub fingerprint_threshold: u32,
+    /// Offenses of one IP prefix that trigger a network ban
+    pub network_threshold: u32,
+    /// Duration of the first ban
+    pub base_duration: Duration,
+    /// Longest ban after escalation
+    pub max_duration: Duration,
+    /// How long past bans count as strikes
+    pub strike_memory: Duration,
+}
+
+impl Default for BanPolicy {
+    fn default() -> Self {
+        Self {
+            offense_window: Duration::from_secs(600),
+            fingerprint_threshold: 20,
+            network_threshold: 100,
+            base_duration: Duration::from_secs(300),
+            max_duration: Duration::from_secs(24 * 3600),
+            strike_memory: Duration::from_secs(7 * 24 * 3600),
+        }
+    }
+}
+
+impl BanPolicy {
+    pub fn offense_window_ms(&self) -> i64 {
+        self.offense_window.as_millis() as i64
+    }
+
+    pub fn strike_memory_ms(&self) -> i64 {
+        self.strike_memory.as_millis() as i64
+    }
+
+    /// Whether the fingerprint, or failing that the network, must be banned
+    ///
+    /// Returns `(ban the fingerprint, ban the network)`.
+    pub fn exceeded(&self, counts: &OffenseCounts) -> (bool, bool) {
+        let over = |count: u32, threshold: u32| threshold > 0 && count >= threshold;
+        (
+            over(counts.fingerprint, self.fingerprint_threshold),
+            over(counts.network, self.network_threshold),
+        )
+    }
+
+    /// Duration of the ban with the given strike number (1-based)
+    pub fn duration(&self, strike: u32) -> Duration {
+        let factor = 1u32 << strike.saturating_sub(1).min(20);
+        self.base_duration
+            .saturating_mul(factor)
+            .min(self.max_duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offense_kind_roundtrip() {
+        for kind in [
+            OffenseKind::InvalidNonce,
+            OffenseKind::ChallengeFlood,
+            OffenseKind::FingerprintMismatch,
+        ] {
+            assert_eq!(OffenseKind::parse(kind.as_str()), Some(kind));
+        }
+        assert_eq!(OffenseKind::parse("spam"), None);
+    }
+
+    #[test]
+    fn test_thresholds() {
+        let policy = BanPolicy::default();
+        let counts = |fingerprint, network| OffenseCounts {
+            fingerprint,
+            network,
+        };
+
+        assert_eq!(policy.exceeded(&counts(19, 99)), (false, false));
+        assert_eq!(policy.exceeded(&counts(20, 0)), (true, false));
+        assert_eq!(policy.exceeded(&counts(0, 100)), (false, true));
+
+        let disabled = BanPolicy {
+            fingerprint_threshold: 0,
+            network_threshold: 0,
+            ..policy
+        };
+        assert_eq!(disabled.exceeded(&counts(1000, 1000)), (false, false));
+    }
+
+    #[test]
+    fn test_duration_escalates_up_to_max() {
+        let policy = BanPolicy {
+            base_duration: Duration::from_secs(60),
+            max_duration: Duration::from_secs(600),
+            ..BanPolicy::default()
+        };
+
+        assert_eq!(policy.duration(1), Duration::from_secs(60));
+        assert_eq!(policy.duration(2), Duration::from_secs(120));
+        assert_eq!(policy.duration(4), Duration::from_secs(480));
+        assert_eq!(policy.duration(5), Duration::from_secs(600));
+        assert_eq!(policy.duration(u32::MAX), Duration::from_secs(600));
+        // Strike 0 is treated as the first ban
+        assert_eq!(policy.duration(0), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let ban = Ban::new(
+            BanSubject::Network("192.0.2.0/24".into()),
+            OffenseKind::ChallengeFlood,
+            100,
+            1,
+            Duration::from_millis(1500),
+            1_000,
+        );
+
+        assert_eq!(ban.expires_at_ms, 2_500);
+        assert_eq!(ban.retry_after_secs(1_000), 2);
+        assert_eq!(ban.retry_after_secs(2_400), 1);
+        assert_eq!(ban.retry_after_secs(9_000), 1);
+        assert_eq!(ban.subject.kind(), "network");
+        assert_eq!(ban.subject.display(), "192.0.2.0/24");
+    }
+}
@@ -0,0 +1,241 @@
+//! Stateless Challenge Tokens
+//!
+//! In stateless mode a challenge is not stored. Everything needed to verify
+//! a solution is HMAC-signed into a token that the client sends back with
+//! its nonces.
+//!
+//! ## Layout (v3)
+//! ```text
+//! base64url(
+//!     version:u8 | key_id:u8 | challenge_id:16 | expires_at_ms:i64 |
+//!     issued_at_ms:i64 |
+//!     algorithm:u8 | argon2_m:u32 | argon2_t:u32 | argon2_p:u32 |
+//!     puzzle_count:u8 | difficulty_bits:u8 | fingerprint_hash:32 |
+//!     has_payload_hash:u8 | payload_hash:32 | protocol_version:u8 |
+//!     action_len:u8 | action:* |
+//!     challenge_bytes:* | hmac:32
+//! )
+//! ```
+//! The HMAC covers a context label followed by every preceding byte, so a
+//! challenge token can never be mistaken for a session token signed with
+//! the same key.
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::DateTime;
+use platform::crypto::{constant_time_eq, hmac_sha256};
+use platform::session_token::{SessionKey, SessionKeyRing};
+use uuid::Uuid;
+
+use crate::domain::algorithm::{Argon2Params, PowAlgorithm};
+use crate::domain::entities::Challenge;
+use crate::domain::services::ProtocolVersion;
+use crate::domain::value_objects::PowAction;
+
+const TOKEN_VERSION: u8 = 3;
+const CONTEXT: &[u8] = b"pow-challenge";
+const HEADER_LEN: usize = 1 + 1 + 16 + 8 + 8 + 1 + 4 + 4 + 4 + 1 + 1 + 32 + 1 + 32 + 1 + 1;
+const SIGNATURE_LEN: usize = 32;
+
+const ALGORITHM_SHA256: u8 = 0;
+const ALGORITHM_ARGON2ID: u8 = 1;
+
+fn sign(key: &SessionKey, data: &[u8]) -> [u8; 32] {
+    let mut input = Vec::with_capacity(CONTEXT.len() + data.len());
+    input.extend_from_slice(CONTEXT);
+    input.extend_from_slice(data);
+    hmac_sha256(&key.secret, &input)
+}
+
+/// Encode and sign a challenge with the current key
+pub fn encode_challenge_token(challenge: &Challenge, keys: &SessionKeyRing) -> String {
+    let key = keys.current();
+    let (algorithm, params) = match challenge.algorithm {
+        PowAlgorithm::Sha256 => (ALGORITHM_SHA256, None),
+        PowAlgorithm::Argon2id(params) => (ALGORITHM_ARGON2ID, Some(params)),
+    };
+
+    let mut data = Vec::with_capacity(
+        HEADER_LEN + PowAction::MAX_LEN + challenge.challenge_bytes.len() + SIGNATURE_LEN,
+    );
+    data.push(TOKEN_VERSION);
+    data.push(key.id);
+    data.extend_from_slice(challenge.id.as_bytes());
+    data.extend_from_slice(&challenge.expires_at_ms.to_be_bytes());
+    data.extend_from_slice(&challenge.created_at.timestamp_millis().to_be_bytes());
+    data.push(algorithm);
+    data.extend_from_slice(&params.map_or(0, |p| p.memory_kib).to_be_bytes());
+    data.extend_from_slice(&params.map_or(0, |p| p.iterations).to_be_bytes());
+    data.extend_from_slice(&params.map_or(0, |p| p.parallelism).to_be_bytes());
+    data.push(challenge.puzzle_count);
+    data.push(challenge.difficulty_bits);
+    let mut fingerprint = [0u8; 32];
+    let len = challenge.client_fingerprint_hash.len().min(32);
+    fingerprint[..len].copy_from_slice(&challenge.client_fingerprint_hash[..len]);
+    data.extend_from_slice(&fingerprint);
+    let mut payload_hash = [0u8; 32];
+    if let Some(hash) = &challenge.payload_hash {
+        let len = hash.len().min(32);
+        payload_hash[..len].copy_from_slice(&hash[..len]);
+    }
+    data.push(u8::from(challenge.payload_hash.is_some()));
+    data.extend_from_slice(&payload_hash);
+    data.push(challenge.protocol_version.as_u8());
+    let action = challenge.action.as_ref().map_or("", PowAction::as_str);
+    data.push(action.len() as u8);
+    data.extend_from_slice(action.as_bytes());
+    data.extend_from_slice(&challenge.challenge_bytes);
+
+    let signature = sign(key, &data);
+    data.extend_from_slice(&signature);
+    URL_SAFE_NO_PAD.encode(data)
+}
+
+/// Verify a token signature and restore the challenge
+///
+/// Expiry and fingerprint are not checked here; the caller decides how to
+/// report them.
+pub fn decode_challenge_token(token: &str, keys: &SessionKeyRing) -> Option<Challenge> {
+    let data = URL_SAFE_NO_PAD.decode(token).ok()?;
+    if data.len() <= HEADER_LEN + SIGNATURE_LEN || data[0] != TOKEN_VERSION {
+        return None;
+    }
+
+    let (payload, signature) = data.split_at(data.len() - SIGNATURE_LEN);
+    let key = keys.get(payload[1])?;
+    if !constant_time_eq(signature, &sign(key, payload)) {
+        return None;
+    }
+
+    let u32_at = |at: usize| u32::from_be_bytes(payload[at..at + 4].try_into().expect("4 bytes"));
+
+    let id = Uuid::from_bytes(payload[2..18].try_into().ok()?);
+    let expires_at_ms = i64::from_be_bytes(payload[18..26].try_into().ok()?);
+    let issued_at_ms = i64::from_be_bytes(payload[26..34].try_into().ok()?);
+    let algorithm = match payload[34] {
+        ALGORITHM_SHA256 => PowAlgorithm::Sha256,
+        ALGORITHM_ARGON2ID => {
+            PowAlgorithm::Argon2id(Argon2Params::new(u32_at(35), u32_at(39), u32_at(43))?)
+        }
+        _ => return None,
+    };
+    let puzzle_count = payload[47];
+    let difficulty_bits = payload[48];
+    let fingerprint_hash = payload[49..81].to_vec();
+    let payload_hash = (payload[81] == 1).then(|| payload[82..114].to_vec());
+    let protocol_version = ProtocolVersion::from_u8(payload[114])?;
+    let action_end = HEADER_LEN + payload[115] as usize;
+    let action = match payload.get(HEADER_LEN..action_end)? {
+        [] => None,
+        name => Some(PowAction::new(std::str::from_utf8(name).ok()?)?),
+    };
+    let challenge_bytes = payload
+        .get(action_end..)
+        .filter(|b| !b.is_empty())?
+        .to_vec();
+
+    Some(Challenge {
+        id,
+        challenge_bytes,
+        algorithm,
+        protocol_version,
+        puzzle_count,
+        difficulty_bits,
+        expires_at_ms,
+        created_at: DateTime::from_timestamp_millis(issued_at_ms)?,
+        client_fingerprint_hash: fingerprint_hash,
+        client_ip: None,
+        action,
+        payload_hash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys() -> SessionKeyRing {
+        SessionKeyRing::new(SessionKey::new(1, [9u8; 32]))
+    }
+
+    fn challenge(algorithm: PowAlgorithm) -> Challenge {
+        Challenge::new(vec![4u8; 32], algorithm, 12, 120_000, vec![2u8; 32], None)
+            .with_puzzle_count(4)
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for algorithm in [
+            PowAlgorithm::Sha256,
+            PowAlgorithm::Argon2id(Argon2Params::default()),
+        ] {
+            let original = challenge(algorithm);
+            let token = encode_challenge_token(&original, &keys());
+            let decoded = decode_challenge_token(&token, &keys()).unwrap();
+
+            assert_eq!(decoded.id, original.id);
+            assert_eq!(decoded.challenge_bytes, original.challenge_bytes);
+            assert_eq!(decoded.algorithm, original.algorithm);
+            assert_eq!(decoded.protocol_version, ProtocolVersion::V1);
+            assert_eq!(decoded.puzzle_count, 4);
+            assert_eq!(decoded.difficulty_bits, 12);
+            assert_eq!(decoded.expires_at_ms, original.expires_at_ms);
+            assert_eq!(
+                decoded.created_at.timestamp_millis(),
+                original.created_at.timestamp_millis()
+            );
+            assert_eq!(
+                decoded.client_fingerprint_hash,
+                original.client_fingerprint_hash
+            );
+            assert_eq!(decoded.action, None);
+            assert_eq!(decoded.payload_hash, None);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_with_action() {
+        let action = PowAction::new("comment:create").unwrap();
+        let original =
+            challenge(PowAlgorithm::Sha256).with_action(action.clone(), Some(vec![5u8; 32]));
+        let token = encode_challenge_token(&original, &keys());
+        let decoded = decode_challenge_token(&token, &keys()).unwrap();
+
+        assert_eq!(decoded.action, Some(action));
+        assert_eq!(decoded.payload_hash, Some(vec![5u8; 32]));
+        assert_eq!(decoded.challenge_bytes, original.challenge_bytes);
+    }
+
+    #[test]
+    fn test_roundtrip_protocol_version() {
+        let original = challenge(PowAlgorithm::Sha256).with_protocol_version(ProtocolVersion::V2);
+        let token = encode_challenge_token(&original, &keys());
+        let decoded = decode_challenge_token(&token, &keys()).unwrap();
+
+        assert_eq!(decoded.protocol_version, ProtocolVersion::V2);
+    }
+
+    #[test]
+    fn test_tampered_token_rejected() {
+        let token = encode_challenge_token(&challenge(PowAlgorithm::Sha256), &keys());
+        let mut data = URL_SAFE_NO_PAD.decode(&token).unwrap();
+        data[48] = 1; // lower the difficulty
+        let tampered = URL_SAFE_NO_PAD.encode(data);
+
+        assert!(decode_challenge_token(&tampered, &keys()).is_none());
+        assert!(decode_challenge_token("garbage", &keys()).is_none());
+    }
+
+    #[test]
+    fn test_unknown_key_rejected() {
+        let token = encode_challenge_token(&challenge(PowAlgorithm::Sha256), &keys());
+        let other = SessionKeyRing::new(SessionKey::new(2, [9u8; 32]));
+
+        assert!(decode_challenge_token(&token, &other).is_none());
+    }
+
+    #[test]
+    fn test_session_token_is_not_a_challenge_token() {
+        let session_token = keys().sign(Uuid::new_v4(), 0, i64::MAX);
+        assert!(decode_challenge_token(&session_token, &keys()).is_none());
+    }
+}
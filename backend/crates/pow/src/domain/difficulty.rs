@@ -0,0 +1,216 @@
+//! Difficulty Policy
+//!
+//! Decides the difficulty of each issued challenge from recent client
+//! activity. The baseline (`PowConfig.difficulty_bits`) applies when
+//! traffic is calm; every doubling of a signal above its threshold adds
+//! one bit (= doubles the expected work).
+//!
+//! Issuance counts come from the rate-limit counters; invalid nonces and
+//! session churn come from the activity log.
+
+use std::fmt::Debug;
+
+use crate::domain::value_objects::Difficulty;
+
+/// Observed activity within the policy window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DifficultyInputs {
+    /// Challenges issued to all clients by this server instance
+    pub global_issued: u32,
+    /// Challenges issued to this fingerprint (within the rate-limit window)
+    pub client_issued: u32,
+    /// Challenges issued to this client's IP prefix (0 without a network limit)
+    pub network_issued: u32,
+    /// Invalid nonces submitted by this fingerprint
+    pub client_invalid_nonces: u32,
+    /// Sessions created by this fingerprint (session churn)
+    pub client_sessions: u32,
+}
+
+/// Policy choosing the difficulty of a new challenge
+pub trait DifficultyPolicy: Debug + Send + Sync {
+    /// Return the difficulty in leading zero bits
+    fn difficulty_bits(&self, baseline: u8, inputs: &DifficultyInputs) -> u8;
+
+    /// Whether the per-client activity inputs are used
+    ///
+    /// When false, the activity log is not queried for new challenges.
+    fn observes_activity(&self) -> bool {
+        true
+    }
+
+    /// Global issuance count above which the policy no longer changes
+    ///
+    /// The global count is kept in an in-memory counter sized to this cap
+    /// and only maintained when this is Some, i.e. when the policy reads
+    /// `global_issued`.
+    fn global_issued_cap(&self) -> Option<u32> {
+        None
+    }
+}
+
+/// Always use the baseline difficulty
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FixedDifficulty;
+
+impl DifficultyPolicy for FixedDifficulty {
+    fn difficulty_bits(&self, baseline: u8, _inputs: &DifficultyInputs) -> u8 {
+        baseline
+    }
+
+    fn observes_activity(&self) -> bool {
+        false
+    }
+}
+
+/// Raise difficulty with load and bad client behaviour
+///
+/// A threshold of 0 disables the corresponding signal.
+#[derive(Debug, Clone, Copy)]
+pub struct AdaptiveDifficulty {
+    /// Issuance count (per server instance) at which difficulty starts to rise
+    pub global_threshold: u32,
+    /// Per-fingerprint issuance count at which difficulty starts to rise
+    pub client_threshold: u32,
+    /// Per-IP-prefix issuance count at which difficulty starts to rise
+    pub network_threshold: u32,
+    /// Invalid nonce count at which difficulty starts to rise
+    pub invalid_nonce_threshold: u32,
+    /// Session count at which difficulty starts to rise
+    pub session_churn_threshold: u32,
+    /// Maximum bits added on top of the baseline
+    pub max_extra_bits: u8,
+}
+
+impl Default for AdaptiveDifficulty {
+    fn default() -> Self {
+        Self {
+            global_threshold: 200,
+            client_threshold: 3,
+            network_threshold: 10,
+            invalid_nonce_threshold: 3,
+            session_churn_threshold: 2,
+            max_extra_bits: 6,
+        }
+    }
+}
+
+impl AdaptiveDifficulty {
+    /// Bits added for a signal: 1 at the threshold, +1 per doubling
+    fn extra_bits(value: u32, threshold: u32) -> u8 {
+        if threshold == 0 || value < threshold {
+            return 0;
+        }
+        ((value / threshold).ilog2() + 1) as u8
+    }
+}
+
+impl DifficultyPolicy for AdaptiveDifficulty {
+    fn difficulty_bits(&self, baseline: u8, inputs: &DifficultyInputs) -> u8 {
+        let extra = [
+            Self::extra_bits(inputs.global_issued, self.global_threshold),
+            Self::extra_bits(inputs.client_issued, self.client_threshold),
+            Self::extra_bits(inputs.network_issued, self.network_threshold),
+            Self::extra_bits(inputs.client_invalid_nonces, self.invalid_nonce_threshold),
+            Self::extra_bits(inputs.client_sessions, self.session_churn_threshold),
+        ]
+        .into_iter()
+        .fold(0u8, u8::saturating_add)
+        .min(self.max_extra_bits);
+
+        baseline.saturating_add(extra).min(Difficulty::MAX)
+    }
+
+    fn global_issued_cap(&self) -> Option<u32> {
+        // The global signal alone reaches max_extra_bits below threshold * 2^max_extra_bits
+        (self.global_threshold > 0).then(|| {
+            1u32.checked_shl(u32::from(self.max_extra_bits))
+                .map_or(u32::MAX, |factor| {
+                    self.global_threshold.saturating_mul(factor)
+                })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_difficulty() {
+        let inputs = DifficultyInputs {
+            global_issued: 10_000,
+            ..Default::default()
+        };
+        assert_eq!(FixedDifficulty.difficulty_bits(23, &inputs), 23);
+    }
+
+    #[test]
+    fn test_calm_traffic_uses_baseline() {
+        let policy = AdaptiveDifficulty::default();
+        let inputs = DifficultyInputs {
+            global_issued: 50,
+            client_issued: 1,
+            network_issued: 2,
+            ..Default::default()
+        };
+        assert_eq!(policy.difficulty_bits(23, &inputs), 23);
+    }
+
+    #[test]
+    fn test_extra_bits_per_doubling() {
+        assert_eq!(AdaptiveDifficulty::extra_bits(2, 3), 0);
+        assert_eq!(AdaptiveDifficulty::extra_bits(3, 3), 1);
+        assert_eq!(AdaptiveDifficulty::extra_bits(6, 3), 2);
+        assert_eq!(AdaptiveDifficulty::extra_bits(12, 3), 3);
+        assert_eq!(AdaptiveDifficulty::extra_bits(100, 0), 0);
+    }
+
+    #[test]
+    fn test_signals_add_up() {
+        let policy = AdaptiveDifficulty::default();
+        let inputs = DifficultyInputs {
+            client_issued: 3,
+            client_invalid_nonces: 3,
+            ..Default::default()
+        };
+        assert_eq!(policy.difficulty_bits(20, &inputs), 22);
+    }
+
+    #[test]
+    fn test_global_issued_cap() {
+        assert_eq!(FixedDifficulty.global_issued_cap(), None);
+        assert!(!FixedDifficulty.observes_activity());
+
+        let policy = AdaptiveDifficulty::default();
+        let cap = policy.global_issued_cap().unwrap();
+        let inputs = DifficultyInputs {
+            global_issued: cap,
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.difficulty_bits(20, &inputs),
+            20 + policy.max_extra_bits
+        );
+
+        let policy = AdaptiveDifficulty {
+            global_threshold: 0,
+            ..Default::default()
+        };
+        assert_eq!(policy.global_issued_cap(), None);
+    }
+
+    #[test]
+    fn test_extra_bits_capped() {
+        let policy = AdaptiveDifficulty::default();
+        let inputs = DifficultyInputs {
+            global_issued: u32::MAX,
+            client_issued: u32::MAX,
+            network_issued: u32::MAX,
+            client_invalid_nonces: u32::MAX,
+            client_sessions: u32::MAX,
+        };
+        assert_eq!(policy.difficulty_bits(20, &inputs), 26);
+        assert_eq!(policy.difficulty_bits(30, &inputs), Difficulty::MAX);
+    }
+}
@@ -3,6 +3,10 @@
 //! Core business entities for the PoW domain.
 
 use chrono::{DateTime, Utc};
+
+use crate::domain::algorithm::PowAlgorithm;
+use crate::domain::services::{Nonce, ProtocolVersion, sub_challenge};
+use crate::domain::value_objects::PowAction;
 use std::net::IpAddr;
 use uuid::Uuid;
 
@@ -11,17 +15,28 @@ use uuid::Uuid;
 pub struct Challenge {
     pub id: Uuid,
     pub challenge_bytes: Vec<u8>,
+    pub algorithm: PowAlgorithm,
+    /// Wire protocol version (nonce encoding) the client asked for
+    pub protocol_version: ProtocolVersion,
+    /// Number of independent sub-puzzles (1 = single nonce)
+    pub puzzle_count: u8,
+    /// Difficulty of each sub-puzzle
     pub difficulty_bits: u8,
     pub expires_at_ms: i64,
     pub created_at: DateTime<Utc>,
     pub client_fingerprint_hash: Vec<u8>,
     pub client_ip: Option<IpAddr>,
+    /// Action the resulting proof is scoped to (None = general session)
+    pub action: Option<PowAction>,
+    /// SHA-256 of the request body the proof may be used for
+    pub payload_hash: Option<Vec<u8>>,
 }
 
 impl Challenge {
     /// Create a new challenge
     pub fn new(
         challenge_bytes: Vec<u8>,
+        algorithm: PowAlgorithm,
         difficulty_bits: u8,
         ttl_ms: i64,
         fingerprint_hash: Vec<u8>,
@@ -31,18 +46,58 @@ impl Challenge {
         Self {
             id: Uuid::new_v4(),
             challenge_bytes,
+            algorithm,
+            protocol_version: ProtocolVersion::V1,
+            puzzle_count: 1,
             difficulty_bits,
             expires_at_ms: now.timestamp_millis() + ttl_ms,
             created_at: now,
             client_fingerprint_hash: fingerprint_hash,
             client_ip,
+            action: None,
+            payload_hash: None,
         }
     }
 
+    /// Scope the challenge to an action (and optionally a request payload)
+    pub fn with_action(mut self, action: PowAction, payload_hash: Option<Vec<u8>>) -> Self {
+        self.action = Some(action);
+        self.payload_hash = payload_hash;
+        self
+    }
+
+    /// Split the challenge into independent sub-puzzles
+    pub fn with_puzzle_count(mut self, puzzle_count: u8) -> Self {
+        self.puzzle_count = puzzle_count.max(1);
+        self
+    }
+
+    /// Use a wire protocol version other than v1
+    pub fn with_protocol_version(mut self, version: ProtocolVersion) -> Self {
+        self.protocol_version = version;
+        self
+    }
+
     /// Check if the challenge has expired
     pub fn is_expired(&self) -> bool {
         Utc::now().timestamp_millis() > self.expires_at_ms
     }
+
+    /// Verify one nonce per sub-puzzle, in order
+    ///
+    /// Nonces outside the range of the challenge's protocol version fail.
+    pub fn verify(&self, nonces: &[u64]) -> bool {
+        if nonces.len() != self.puzzle_count as usize {
+            return false;
+        }
+        nonces.iter().enumerate().all(|(index, &nonce)| {
+            let Some(nonce) = Nonce::new(self.protocol_version, nonce) else {
+                return false;
+            };
+            let bytes = sub_challenge(&self.challenge_bytes, index as u32, self.puzzle_count);
+            self.algorithm.verify(&bytes, nonce, self.difficulty_bits)
+        })
+    }
 }
 
 /// PowSession entity - represents a valid PoW session
@@ -53,6 +108,16 @@ pub struct PowSession {
     pub created_at: DateTime<Utc>,
     pub client_fingerprint_hash: Vec<u8>,
     pub challenge_id: Uuid,
+    /// Action this session is a single-use proof for (None = general session)
+    pub action: Option<PowAction>,
+    /// SHA-256 of the request body the proof is bound to
+    pub payload_hash: Option<Vec<u8>>,
+    /// Requests this session may still authorize (None = unlimited)
+    pub remaining_requests: Option<u32>,
+    /// Difficulty of each sub-puzzle of the challenge solved last (0 = unknown)
+    pub difficulty_bits: u8,
+    /// Sub-puzzles of the challenge solved last
+    pub puzzle_count: u8,
 }
 
 impl PowSession {
@@ -65,11 +130,55 @@ impl PowSession {
             created_at: now,
             client_fingerprint_hash: challenge.client_fingerprint_hash.clone(),
             challenge_id: challenge.id,
+            action: challenge.action.clone(),
+            payload_hash: challenge.payload_hash.clone(),
+            remaining_requests: None,
+            difficulty_bits: challenge.difficulty_bits,
+            puzzle_count: challenge.puzzle_count,
         }
     }
 
+    /// Limit the number of requests the session may authorize
+    pub fn with_request_budget(mut self, budget: Option<u32>) -> Self {
+        self.remaining_requests = budget;
+        self
+    }
+
+    /// Whether the request budget is used up
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining_requests == Some(0)
+    }
+
     /// Check if the session has expired
     pub fn is_expired(&self) -> bool {
         Utc::now().timestamp_millis() > self.expires_at_ms
     }
+
+    /// Milliseconds until the session expires (0 once expired)
+    pub fn remaining_ttl_ms(&self, now_ms: i64) -> i64 {
+        (self.expires_at_ms - now_ms).max(0)
+    }
+
+    /// Whether this is a general session rather than an action-scoped proof
+    pub fn is_general(&self) -> bool {
+        self.action.is_none()
+    }
+}
+
+/// Kind of client activity recorded for the difficulty policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityKind {
+    /// A submitted nonce did not meet the difficulty
+    InvalidNonce,
+    /// A pow session was created
+    SessionCreated,
+}
+
+impl ActivityKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivityKind::InvalidNonce => "invalid_nonce",
+            ActivityKind::SessionCreated => "session_created",
+        }
+    }
 }
@@ -4,9 +4,19 @@
 //! - Domain entities (Challenge, PowSession)
 //! - Domain value objects (ClientFingerprint, Difficulty)
 //! - Domain services (PoW verification logic)
+//! - PoW algorithms (SHA-256, Argon2id)
+//! - Signed challenge tokens (stateless mode)
+//! - Difficulty policies (adaptive difficulty)
+//! - Solve telemetry (difficulty tuning)
+//! - Ban policy (temporary bans of abusive clients)
 //! - Repository traits (interfaces)
 
+pub mod algorithm;
+pub mod ban;
+pub mod challenge_token;
+pub mod difficulty;
 pub mod entities;
-pub mod services;
 pub mod repository;
+pub mod services;
+pub mod telemetry;
 pub mod value_objects;
@@ -2,8 +2,11 @@
 //!
 //! Interfaces for data persistence. Implementation is in infrastructure layer.
 
-use crate::domain::entities::{Challenge, PowSession};
-use crate::domain::value_objects::ClientFingerprint;
+use crate::domain::ban::{Ban, BanKeys, BanSubject, OffenseCounts, OffenseKind};
+use crate::domain::difficulty::DifficultyInputs;
+use crate::domain::entities::{ActivityKind, Challenge, PowSession};
+use crate::domain::telemetry::{SolveRollup, SolveSample};
+use crate::domain::value_objects::{ClientFingerprint, PowAction};
 use crate::error::PowResult;
 use uuid::Uuid;
 
@@ -16,11 +19,19 @@ pub trait LocalChallengeRepository {
     /// Consume a challenge atomically (delete and return if valid)
     ///
     /// This must be bound to the request fingerprint to prevent replay/hijack.
+    /// Fails with `PowError::ChallengeFingerprintMismatch` if the challenge
+    /// was issued to another client; it is left in place.
     async fn consume(
         &self,
         challenge_id: Uuid,
         fingerprint: &ClientFingerprint,
     ) -> PowResult<Option<Challenge>>;
+
+    /// Mark a stateless challenge as solved
+    ///
+    /// Returns false if it was already consumed. The record only needs to
+    /// be kept until `expires_at_ms`.
+    async fn mark_consumed(&self, challenge_id: Uuid, expires_at_ms: i64) -> PowResult<bool>;
 }
 
 /// PowSession repository trait
@@ -30,25 +41,105 @@ pub trait LocalPowSessionRepository {
     async fn create(&self, pow_session: &PowSession) -> PowResult<()>;
 
     /// Get pow session by ID and verify fingerprint
+    ///
+    /// Fails with `PowError::SessionFingerprintMismatch` if the session is
+    /// bound to another client.
     async fn get(
         &self,
         pow_session_id: Uuid,
         fingerprint: &ClientFingerprint,
     ) -> PowResult<Option<PowSession>>;
 
+    /// Spend one request of a general session atomically
+    ///
+    /// Returns the session with its remaining budget after this request, or
+    /// None if it is missing, expired, bound to another fingerprint or
+    /// exhausted. Sessions without a budget are returned unchanged.
+    async fn spend_request(
+        &self,
+        pow_session_id: Uuid,
+        fingerprint: &ClientFingerprint,
+    ) -> PowResult<Option<PowSession>>;
+
+    /// Extend a still-valid general session with a newly solved challenge
+    ///
+    /// Takes the expiry, budget, challenge and difficulty from `renewed`.
+    /// Returns the updated session, or None if the session is missing,
+    /// expired, bound to another fingerprint or an action-scoped proof.
+    async fn renew(
+        &self,
+        pow_session_id: Uuid,
+        fingerprint: &ClientFingerprint,
+        renewed: &PowSession,
+    ) -> PowResult<Option<PowSession>>;
+
+    /// Consume an action-scoped proof atomically (delete and return if valid)
+    ///
+    /// Sessions bound to another fingerprint or action are left untouched.
+    async fn consume(
+        &self,
+        pow_session_id: Uuid,
+        fingerprint: &ClientFingerprint,
+        action: &PowAction,
+    ) -> PowResult<Option<PowSession>>;
+
     /// Delete a pow session
     async fn delete(&self, pow_session_id: Uuid) -> PowResult<()>;
 }
 
-/// Rate limit repository trait
-#[trait_variant::make(RateLimitRepository: Send)]
-pub trait LocalRateLimitRepository {
-    /// Check rate limit for a fingerprint
-    /// Returns true if request is allowed
-    async fn check(
+/// Client activity repository trait (inputs for the difficulty policy)
+#[trait_variant::make(ActivityRepository: Send)]
+pub trait LocalActivityRepository {
+    /// Record an activity of a client
+    ///
+    /// Clients are told apart by `ClientNetworkKey::client_hash`, so clients
+    /// sharing a User-Agent do not share a reputation.
+    async fn record(&self, kind: ActivityKind, fingerprint: &ClientFingerprint) -> PowResult<()>;
+
+    /// Summarize recent activity of a client
+    ///
+    /// Issuance counts are left at 0; they come from the rate-limit counters.
+    async fn summarize(
         &self,
         fingerprint: &ClientFingerprint,
-        max_requests: u32,
         window_ms: i64,
-    ) -> PowResult<bool>;
+    ) -> PowResult<DifficultyInputs>;
+}
+
+/// Solve telemetry repository trait
+#[trait_variant::make(TelemetryRepository: Send)]
+pub trait LocalTelemetryRepository {
+    /// Add a solve to the rollup period containing `solved_at_ms`
+    async fn record_solve(&self, sample: &SolveSample, solved_at_ms: i64) -> PowResult<()>;
+
+    /// Rollup rows since `since_ms`, merged across periods and UA families
+    async fn solve_rollups(&self, since_ms: i64) -> PowResult<Vec<SolveRollup>>;
+}
+
+/// Ban list repository trait
+#[trait_variant::make(BanRepository: Send)]
+pub trait LocalBanRepository {
+    /// Active ban of the client or its IP prefix (the longest one)
+    async fn active_ban(&self, keys: &BanKeys, now_ms: i64) -> PowResult<Option<Ban>>;
+
+    /// Record an offense and count the client's offenses since `since_ms`
+    async fn record_offense(
+        &self,
+        kind: OffenseKind,
+        keys: &BanKeys,
+        now_ms: i64,
+        since_ms: i64,
+    ) -> PowResult<OffenseCounts>;
+
+    /// Number of bans of a subject created since `since_ms`
+    async fn count_bans(&self, subject: &BanSubject, since_ms: i64) -> PowResult<u32>;
+
+    /// Store a ban and clear the subject's offense log
+    async fn create_ban(&self, ban: &Ban) -> PowResult<()>;
+
+    /// Bans in effect at `now_ms`, newest first
+    async fn list_active_bans(&self, now_ms: i64) -> PowResult<Vec<Ban>>;
+
+    /// End a ban early; returns false if it is unknown or no longer active
+    async fn lift_ban(&self, ban_id: Uuid, now_ms: i64) -> PowResult<bool>;
 }
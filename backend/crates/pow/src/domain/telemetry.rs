@@ -0,0 +1,442 @@
+//! Solve Telemetry
+//!
+//! How long clients take to solve challenges, per amount of work and
+//! device class. Used to tune `PowConfig.difficulty_bits`.
+//!
+//! Samples are rolled up into log-scale buckets of the solve time
+//! (`BUCKETS_PER_DOUBLING` per power of two, ~19% wide), so storage stays
+//! bounded while percentiles can still be estimated.
+
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+use crate::domain::entities::Challenge;
+use crate::domain::value_objects::Difficulty;
+
+/// Histogram resolution: buckets per doubling of the solve time
+pub const BUCKETS_PER_DOUBLING: u32 = 4;
+
+/// Time resolution of the rollup (1 hour)
+pub const ROLLUP_PERIOD_MS: i64 = 3_600_000;
+
+/// Device class derived from the User-Agent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DeviceClass {
+    Desktop,
+    Mobile,
+    Bot,
+    Unknown,
+}
+
+impl DeviceClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeviceClass::Desktop => "desktop",
+            DeviceClass::Mobile => "mobile",
+            DeviceClass::Bot => "bot",
+            DeviceClass::Unknown => "unknown",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "desktop" => DeviceClass::Desktop,
+            "mobile" => DeviceClass::Mobile,
+            "bot" => DeviceClass::Bot,
+            _ => DeviceClass::Unknown,
+        }
+    }
+}
+
+/// Classify a User-Agent into (browser family, device class)
+///
+/// Deliberately coarse: only used to group telemetry.
+pub fn classify_user_agent(user_agent: Option<&str>) -> (&'static str, DeviceClass) {
+    let Some(ua) = user_agent.map(str::to_ascii_lowercase) else {
+        return ("unknown", DeviceClass::Unknown);
+    };
+
+    let has = |needle: &str| ua.contains(needle);
+
+    if has("bot") || has("crawler") || has("spider") || has("headless") || has("curl") {
+        return ("bot", DeviceClass::Bot);
+    }
+
+    let family = if has("edg/") {
+        "edge"
+    } else if has("firefox/") || has("fxios/") {
+        "firefox"
+    } else if has("chrome/") || has("crios/") || has("chromium/") {
+        "chrome"
+    } else if has("safari/") {
+        "safari"
+    } else {
+        "other"
+    };
+
+    let device = if has("mobile") || has("android") || has("iphone") || has("ipad") {
+        DeviceClass::Mobile
+    } else if has("windows") || has("macintosh") || has("linux") || has("cros") {
+        DeviceClass::Desktop
+    } else {
+        DeviceClass::Unknown
+    };
+
+    (family, device)
+}
+
+/// One solved challenge
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolveSample {
+    /// Algorithm identifier
+    pub algorithm: &'static str,
+    /// Difficulty of each sub-puzzle
+    pub difficulty_bits: u8,
+    pub puzzle_count: u8,
+    pub ua_family: &'static str,
+    pub device_class: DeviceClass,
+    /// Time from issue to submit, measured by the server
+    pub server_elapsed_ms: i64,
+    /// Client-reported solve time (not trusted)
+    pub client_elapsed_ms: Option<i64>,
+    /// Client-reported hash count (not trusted)
+    pub client_total_hashes: Option<i64>,
+}
+
+impl SolveSample {
+    /// Sample for `challenge`, solved by a client with `user_agent` at `submitted_at`
+    pub fn new(
+        challenge: &Challenge,
+        user_agent: Option<&str>,
+        submitted_at: DateTime<Utc>,
+    ) -> Self {
+        let (ua_family, device_class) = classify_user_agent(user_agent);
+        Self {
+            algorithm: challenge.algorithm.id(),
+            difficulty_bits: challenge.difficulty_bits,
+            puzzle_count: challenge.puzzle_count,
+            ua_family,
+            device_class,
+            server_elapsed_ms: (submitted_at - challenge.created_at)
+                .num_milliseconds()
+                .max(0),
+            client_elapsed_ms: None,
+            client_total_hashes: None,
+        }
+    }
+
+    /// Attach the client's own report (negative values are dropped)
+    pub fn with_client_report(
+        mut self,
+        elapsed_ms: Option<i64>,
+        total_hashes: Option<i64>,
+    ) -> Self {
+        self.client_elapsed_ms = elapsed_ms.filter(|v| *v >= 0);
+        self.client_total_hashes = total_hashes.filter(|v| *v >= 0);
+        self
+    }
+
+    /// Total work in bits (k puzzles of d bits ≈ d + log2(k) bits)
+    pub fn work_bits(&self) -> u8 {
+        self.difficulty_bits
+            .saturating_add(self.puzzle_count.max(1).ilog2() as u8)
+    }
+}
+
+/// Start of the rollup period containing `at_ms`
+pub fn rollup_period_start_ms(at_ms: i64) -> i64 {
+    at_ms - at_ms.rem_euclid(ROLLUP_PERIOD_MS)
+}
+
+/// Histogram bucket of a solve time
+pub fn elapsed_bucket(elapsed_ms: i64) -> i16 {
+    if elapsed_ms <= 1 {
+        return 0;
+    }
+    ((elapsed_ms as f64).log2() * BUCKETS_PER_DOUBLING as f64).floor() as i16
+}
+
+/// Representative solve time of a bucket (geometric midpoint)
+pub fn bucket_midpoint_ms(bucket: i16) -> i64 {
+    2f64.powf((bucket as f64 + 0.5) / BUCKETS_PER_DOUBLING as f64)
+        .round() as i64
+}
+
+/// Solve-time histogram of one group
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolveHistogram {
+    counts: BTreeMap<i16, u64>,
+}
+
+impl SolveHistogram {
+    pub fn add(&mut self, bucket: i16, count: u64) {
+        *self.counts.entry(bucket).or_default() += count;
+    }
+
+    pub fn samples(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    /// Estimated solve time at quantile `q` (0.0..=1.0)
+    pub fn percentile_ms(&self, q: f64) -> Option<i64> {
+        let total = self.samples();
+        if total == 0 {
+            return None;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (&bucket, &count) in &self.counts {
+            seen += count;
+            if seen >= rank {
+                return Some(bucket_midpoint_ms(bucket));
+            }
+        }
+        None
+    }
+}
+
+/// Solve-time statistics of one (algorithm, work, device class) group
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolveStats {
+    pub algorithm: String,
+    pub work_bits: u8,
+    pub device_class: DeviceClass,
+    pub samples: u64,
+    pub p50_ms: i64,
+    pub p90_ms: i64,
+    pub p99_ms: i64,
+    /// Client-reported hash rate (hashes per second), if reported
+    pub client_hash_rate: Option<f64>,
+}
+
+/// One rollup row: solves of a group that fell into one histogram bucket
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolveRollup {
+    pub algorithm: String,
+    pub work_bits: u8,
+    pub device_class: DeviceClass,
+    pub elapsed_bucket: i16,
+    pub samples: u64,
+    /// Solves that came with both client-reported values
+    pub client_samples: u64,
+    pub client_elapsed_ms: i64,
+    pub client_hashes: i64,
+}
+
+/// Build per-group statistics from rollup rows
+///
+/// Groups are ordered by algorithm, work and device class.
+pub fn summarize_rollups(rows: &[SolveRollup]) -> Vec<SolveStats> {
+    let mut groups: BTreeMap<(&str, u8, DeviceClass), (SolveHistogram, i64, i64)> = BTreeMap::new();
+    for row in rows {
+        let (histogram, elapsed, hashes) = groups
+            .entry((row.algorithm.as_str(), row.work_bits, row.device_class))
+            .or_default();
+        histogram.add(row.elapsed_bucket, row.samples);
+        if row.client_samples > 0 {
+            *elapsed += row.client_elapsed_ms;
+            *hashes += row.client_hashes;
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter_map(
+            |((algorithm, work_bits, device_class), (histogram, elapsed, hashes))| {
+                Some(SolveStats {
+                    algorithm: algorithm.to_string(),
+                    work_bits,
+                    device_class,
+                    samples: histogram.samples(),
+                    p50_ms: histogram.percentile_ms(0.5)?,
+                    p90_ms: histogram.percentile_ms(0.9)?,
+                    p99_ms: histogram.percentile_ms(0.99)?,
+                    client_hash_rate: (elapsed > 0)
+                        .then(|| hashes as f64 * 1000.0 / elapsed as f64),
+                })
+            },
+        )
+        .collect()
+}
+
+/// Difficulty whose median solve time is closest to `target_median_ms`
+///
+/// Each group predicts `work_bits + log2(target / p50)` (solve time doubles
+/// with every bit); predictions are averaged weighted by sample count.
+/// Groups with fewer than `min_samples` samples are ignored.
+pub fn suggest_difficulty<'a>(
+    stats: impl IntoIterator<Item = &'a SolveStats>,
+    target_median_ms: i64,
+    min_samples: u64,
+) -> Option<u8> {
+    let (weighted, samples) = stats
+        .into_iter()
+        .filter(|s| s.samples >= min_samples.max(1) && s.p50_ms > 0)
+        .fold((0.0, 0u64), |(weighted, samples), s| {
+            let bits = s.work_bits as f64 + (target_median_ms as f64 / s.p50_ms as f64).log2();
+            (weighted + bits * s.samples as f64, samples + s.samples)
+        });
+
+    if samples == 0 || target_median_ms <= 0 {
+        return None;
+    }
+
+    let bits = (weighted / samples as f64).round();
+    Some(bits.clamp(Difficulty::MIN as f64, Difficulty::MAX as f64) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(work_bits: u8, p50_ms: i64, samples: u64) -> SolveStats {
+        SolveStats {
+            algorithm: "sha256".to_string(),
+            work_bits,
+            device_class: DeviceClass::Desktop,
+            samples,
+            p50_ms,
+            p90_ms: p50_ms * 2,
+            p99_ms: p50_ms * 4,
+            client_hash_rate: None,
+        }
+    }
+
+    #[test]
+    fn test_classify_user_agent() {
+        let chrome_desktop = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0 Safari/537.36";
+        let safari_iphone = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1";
+
+        assert_eq!(
+            classify_user_agent(Some(chrome_desktop)),
+            ("chrome", DeviceClass::Desktop)
+        );
+        assert_eq!(
+            classify_user_agent(Some(safari_iphone)),
+            ("safari", DeviceClass::Mobile)
+        );
+        assert_eq!(
+            classify_user_agent(Some("curl/8.0")),
+            ("bot", DeviceClass::Bot)
+        );
+        assert_eq!(classify_user_agent(None), ("unknown", DeviceClass::Unknown));
+    }
+
+    #[test]
+    fn test_work_bits() {
+        let sample = SolveSample {
+            algorithm: "sha256",
+            difficulty_bits: 21,
+            puzzle_count: 4,
+            ua_family: "chrome",
+            device_class: DeviceClass::Desktop,
+            server_elapsed_ms: 1000,
+            client_elapsed_ms: None,
+            client_total_hashes: None,
+        };
+        assert_eq!(sample.work_bits(), 23);
+    }
+
+    #[test]
+    fn test_sample_from_challenge() {
+        let challenge = Challenge::new(
+            vec![0u8; 32],
+            crate::domain::algorithm::PowAlgorithm::Sha256,
+            18,
+            60_000,
+            vec![1u8; 32],
+            None,
+        );
+        let submitted_at = challenge.created_at + chrono::Duration::milliseconds(2_500);
+        let sample = SolveSample::new(&challenge, Some("curl/8.0"), submitted_at)
+            .with_client_report(Some(2_000), Some(-1));
+
+        assert_eq!(sample.algorithm, "sha256");
+        assert_eq!(sample.server_elapsed_ms, 2_500);
+        assert_eq!(sample.client_elapsed_ms, Some(2_000));
+        assert_eq!(sample.client_total_hashes, None);
+        assert_eq!(sample.device_class, DeviceClass::Bot);
+        assert_eq!(rollup_period_start_ms(7_200_123), 7_200_000);
+    }
+
+    #[test]
+    fn test_bucket_roundtrip_within_resolution() {
+        for ms in [5, 120, 1_000, 3_500, 60_000] {
+            let mid = bucket_midpoint_ms(elapsed_bucket(ms));
+            let ratio = mid as f64 / ms as f64;
+            assert!((0.8..1.25).contains(&ratio), "{ms} -> {mid}");
+        }
+        assert_eq!(elapsed_bucket(0), 0);
+        assert_eq!(elapsed_bucket(-5), 0);
+    }
+
+    #[test]
+    fn test_histogram_percentiles() {
+        let mut histogram = SolveHistogram::default();
+        histogram.add(elapsed_bucket(1_000), 50);
+        histogram.add(elapsed_bucket(4_000), 40);
+        histogram.add(elapsed_bucket(30_000), 10);
+
+        assert_eq!(histogram.samples(), 100);
+        assert_eq!(
+            histogram.percentile_ms(0.5),
+            Some(bucket_midpoint_ms(elapsed_bucket(1_000)))
+        );
+        assert_eq!(
+            histogram.percentile_ms(0.9),
+            Some(bucket_midpoint_ms(elapsed_bucket(4_000)))
+        );
+        assert_eq!(
+            histogram.percentile_ms(0.99),
+            Some(bucket_midpoint_ms(elapsed_bucket(30_000)))
+        );
+        assert_eq!(SolveHistogram::default().percentile_ms(0.5), None);
+    }
+
+    #[test]
+    fn test_summarize_rollups() {
+        let row = |device_class, elapsed_ms, samples, client_samples| SolveRollup {
+            algorithm: "sha256".to_string(),
+            work_bits: 20,
+            device_class,
+            elapsed_bucket: elapsed_bucket(elapsed_ms),
+            samples,
+            client_samples,
+            client_elapsed_ms: if client_samples > 0 { 1_000 } else { 0 },
+            client_hashes: if client_samples > 0 { 500_000 } else { 0 },
+        };
+        let stats = summarize_rollups(&[
+            row(DeviceClass::Mobile, 8_000, 10, 0),
+            row(DeviceClass::Desktop, 2_000, 30, 1),
+            row(DeviceClass::Desktop, 16_000, 1, 0),
+        ]);
+
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].device_class, DeviceClass::Desktop);
+        assert_eq!(stats[0].samples, 31);
+        assert_eq!(stats[0].p50_ms, bucket_midpoint_ms(elapsed_bucket(2_000)));
+        assert_eq!(stats[0].p99_ms, bucket_midpoint_ms(elapsed_bucket(16_000)));
+        assert_eq!(stats[0].client_hash_rate, Some(500_000.0));
+        assert_eq!(stats[1].device_class, DeviceClass::Mobile);
+        assert_eq!(stats[1].client_hash_rate, None);
+    }
+
+    #[test]
+    fn test_suggest_difficulty() {
+        // 23 bits take ~8s; a 2s target needs two bits less
+        assert_eq!(
+            suggest_difficulty(&[stats(23, 8_000, 100)], 2_000, 10),
+            Some(21)
+        );
+        // Already on target
+        assert_eq!(
+            suggest_difficulty(&[stats(20, 2_000, 100)], 2_000, 10),
+            Some(20)
+        );
+        // Weighted by samples
+        let mixed = [stats(20, 1_000, 300), stats(20, 4_000, 100)];
+        assert_eq!(suggest_difficulty(&mixed, 2_000, 10), Some(21));
+        // Not enough data
+        assert_eq!(suggest_difficulty(&[stats(20, 1_000, 5)], 2_000, 10), None);
+    }
+}
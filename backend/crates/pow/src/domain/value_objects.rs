@@ -2,8 +2,39 @@
 //!
 //! Immutable value types for the PoW domain.
 
-/// Re-export ClientFingerprint from platform
-pub use platform::client::ClientFingerprint;
+/// Re-export client identification types from platform
+pub use platform::client::{ClientFingerprint, ClientNetworkKey};
+
+/// Action a PoW proof is scoped to (e.g. `signup`, `comment:create`)
+///
+/// Lowercase ASCII letters, digits and `:`, `_`, `-`, `.`; at most 64 bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PowAction(String);
+
+impl PowAction {
+    pub const MAX_LEN: usize = 64;
+
+    pub fn new(name: &str) -> Option<Self> {
+        let valid = !name.is_empty()
+            && name.len() <= Self::MAX_LEN
+            && name.bytes().all(|b| {
+                b.is_ascii_lowercase()
+                    || b.is_ascii_digit()
+                    || matches!(b, b':' | b'_' | b'-' | b'.')
+            });
+        valid.then(|| Self(name.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for PowAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
 
 /// Difficulty level for PoW
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
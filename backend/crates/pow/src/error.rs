@@ -3,7 +3,7 @@
 //! This module provides PoW-specific error variants that integrate
 //! with the unified `kernel::error::AppError` system.
 
-use axum::http::StatusCode;
+use axum::http::{HeaderValue, StatusCode, header};
 use axum::response::{IntoResponse, Response};
 use kernel::error::{app_error::AppError, kind::ErrorKind};
 use thiserror::Error;
@@ -21,6 +21,13 @@ pub enum PowError {
     #[error("Challenge not found or expired")]
     ChallengeNotFound,
 
+    /// Challenge bound to another client
+    ///
+    /// Answered like [`PowError::ChallengeNotFound`] so the binding is not
+    /// revealed.
+    #[error("Challenge fingerprint mismatch")]
+    ChallengeFingerprintMismatch,
+
     /// Challenge has expired (TTL exceeded)
     #[error("Challenge expired")]
     ChallengeExpired,
@@ -33,6 +40,20 @@ pub enum PowError {
     #[error("Rate limit exceeded")]
     RateLimitExceeded,
 
+    /// Client is temporarily banned
+    ///
+    /// Fingerprint bans answer 403, network bans 429 (clients sharing the
+    /// prefix are not necessarily abusive themselves).
+    #[error("Client banned ({subject_kind}) for {retry_after_secs}s")]
+    Banned {
+        subject_kind: &'static str,
+        retry_after_secs: u64,
+    },
+
+    /// Ban not found, expired or already lifted
+    #[error("Ban not found")]
+    BanNotFound,
+
     /// Session not found or invalid
     #[error("Session not found or invalid")]
     SessionInvalid,
@@ -45,6 +66,10 @@ pub enum PowError {
     #[error("Missing required header: {0}")]
     MissingHeader(String),
 
+    /// Malformed request body
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
     /// Database error
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
@@ -58,13 +83,20 @@ impl PowError {
     /// Get the HTTP status code for this error
     pub fn status_code(&self) -> StatusCode {
         match self {
-            PowError::ChallengeNotFound | PowError::ChallengeExpired => StatusCode::GONE,
+            PowError::ChallengeNotFound
+            | PowError::ChallengeFingerprintMismatch
+            | PowError::ChallengeExpired => StatusCode::GONE,
             PowError::InvalidNonce => StatusCode::CONFLICT,
             PowError::RateLimitExceeded => StatusCode::TOO_MANY_REQUESTS,
+            PowError::Banned { subject_kind, .. } if *subject_kind == "network" => {
+                StatusCode::TOO_MANY_REQUESTS
+            }
+            PowError::Banned { .. } => StatusCode::FORBIDDEN,
+            PowError::BanNotFound => StatusCode::NOT_FOUND,
             PowError::SessionInvalid | PowError::SessionFingerprintMismatch => {
                 StatusCode::UNAUTHORIZED
             }
-            PowError::MissingHeader(_) => StatusCode::BAD_REQUEST,
+            PowError::MissingHeader(_) | PowError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
             PowError::Database(_) | PowError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -72,13 +104,20 @@ impl PowError {
     /// Get the ErrorKind for this error
     pub fn kind(&self) -> ErrorKind {
         match self {
-            PowError::ChallengeNotFound | PowError::ChallengeExpired => ErrorKind::Gone,
+            PowError::ChallengeNotFound
+            | PowError::ChallengeFingerprintMismatch
+            | PowError::ChallengeExpired => ErrorKind::Gone,
             PowError::InvalidNonce => ErrorKind::Conflict,
             PowError::RateLimitExceeded => ErrorKind::TooManyRequests,
+            PowError::Banned { subject_kind, .. } if *subject_kind == "network" => {
+                ErrorKind::TooManyRequests
+            }
+            PowError::Banned { .. } => ErrorKind::Forbidden,
+            PowError::BanNotFound => ErrorKind::NotFound,
             PowError::SessionInvalid | PowError::SessionFingerprintMismatch => {
                 ErrorKind::Unauthorized
             }
-            PowError::MissingHeader(_) => ErrorKind::BadRequest,
+            PowError::MissingHeader(_) | PowError::InvalidRequest(_) => ErrorKind::BadRequest,
             PowError::Database(_) | PowError::Internal(_) => ErrorKind::InternalServerError,
         }
     }
@@ -98,6 +137,16 @@ impl PowError {
             PowError::RateLimitExceeded => {
                 tracing::warn!("PoW rate limit exceeded");
             }
+            PowError::Banned {
+                subject_kind,
+                retry_after_secs,
+            } => {
+                tracing::info!(
+                    subject_kind,
+                    retry_after_secs,
+                    "PoW request from banned client"
+                );
+            }
             _ => {
                 tracing::debug!(error = %self, "PoW error");
             }
@@ -118,7 +167,16 @@ impl IntoResponse for PowError {
         self.log();
         let status = self.status_code();
         // Return empty body for security (don't leak details)
-        (status, ()).into_response()
+        let mut response = (status, ()).into_response();
+        if let PowError::Banned {
+            retry_after_secs, ..
+        } = self
+        {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+        }
+        response
     }
 }
 
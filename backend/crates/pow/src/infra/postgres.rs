@@ -1,86 +1,225 @@
 //! PostgreSQL Repository Implementations
 
-use crate::domain::entities::{Challenge, PowSession};
-use crate::domain::repository::{ChallengeRepository, PowSessionRepository, RateLimitRepository};
-use crate::domain::value_objects::ClientFingerprint;
+use crate::domain::algorithm::{Argon2Params, PowAlgorithm};
+use crate::domain::ban::{Ban, BanKeys, BanSubject, OffenseCounts, OffenseKind};
+use crate::domain::difficulty::DifficultyInputs;
+use crate::domain::entities::{ActivityKind, Challenge, PowSession};
+use crate::domain::repository::{
+    ActivityRepository, BanRepository, ChallengeRepository, PowSessionRepository,
+    TelemetryRepository,
+};
+use crate::domain::services::ProtocolVersion;
+use crate::domain::telemetry::{
+    DeviceClass, SolveRollup, SolveSample, elapsed_bucket, rollup_period_start_ms,
+};
+use crate::domain::value_objects::{ClientFingerprint, ClientNetworkKey, PowAction};
 use crate::error::{PowError, PowResult};
 use chrono::Utc;
+use platform::rate_limit::{
+    PgRateLimitStore, RateLimitBackend, RateLimitConfig, RateLimitError, RateLimitResult,
+    RateLimitStore,
+};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-const OLD_WINDOW_MS: i64 = 3600_000; // 1 hour
+const OLD_WINDOW_MS: i64 = 3_600_000; // 1 hour
+const TELEMETRY_RETENTION_MS: i64 = 30 * 24 * 3_600_000; // 30 days
+const OFFENSE_RETENTION_MS: i64 = 24 * 3_600_000; // 1 day
+const BAN_RETENTION_MS: i64 = 30 * 24 * 3_600_000; // 30 days (longer than the strike memory)
+
+/// Rows deleted per statement by [`PgPowRepository::cleanup_expired`]
+pub const DEFAULT_CLEANUP_BATCH_SIZE: i64 = 1_000;
 
 /// PostgreSQL-backed repository
 #[derive(Clone)]
 pub struct PgPowRepository {
     pool: PgPool,
+    rate_limits: RateLimitBackend,
+    client_network: ClientNetworkKey,
 }
 
 impl PgPowRepository {
+    /// Repository keeping rate limits in Postgres as well
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        let rate_limits = RateLimitBackend::Postgres(PgRateLimitStore::new(pool.clone()));
+        Self {
+            pool,
+            rate_limits,
+            client_network: ClientNetworkKey::default(),
+        }
+    }
+
+    /// Use another rate limit store (e.g. in-memory for a single instance)
+    pub fn with_rate_limits(mut self, rate_limits: RateLimitBackend) -> Self {
+        self.rate_limits = rate_limits;
+        self
+    }
+
+    /// Group client IPs into other networks for activity and bans
+    ///
+    /// Must match `PowConfig.client_network`, which names the networks that
+    /// get banned.
+    pub fn with_client_network(mut self, client_network: ClientNetworkKey) -> Self {
+        self.client_network = client_network;
+        self
     }
 
     /// Clean up expired data
     pub async fn cleanup_expired(&self) -> PowResult<(u64, u64, u64)> {
+        self.cleanup_expired_batched(DEFAULT_CLEANUP_BATCH_SIZE)
+            .await
+    }
+
+    /// Clean up expired data, deleting at most `batch_size` rows per statement
+    ///
+    /// Returns the challenges, sessions and rate limit buckets deleted. The
+    /// buckets table is shared, so refilled buckets of other limits go too.
+    pub async fn cleanup_expired_batched(&self, batch_size: i64) -> PowResult<(u64, u64, u64)> {
         let now_ms = Utc::now().timestamp_millis();
         let old_window_ms = now_ms - OLD_WINDOW_MS;
+        let batch_size = batch_size.max(1);
 
-        let challenges_deleted = sqlx::query("DELETE FROM pow_challenges WHERE expires_at_ms < $1")
-            .bind(now_ms)
-            .execute(&self.pool)
-            .await?
-            .rows_affected();
-
-        let sessions_deleted = sqlx::query("DELETE FROM pow_sessions WHERE expires_at_ms < $1")
-            .bind(now_ms)
-            .execute(&self.pool)
-            .await?
-            .rows_affected();
-
-        let rate_limits_deleted =
-            sqlx::query("DELETE FROM pow_rate_limits WHERE window_start_ms < $1")
-                .bind(old_window_ms)
-                .execute(&self.pool)
-                .await?
-                .rows_affected();
+        let challenges_deleted = self
+            .delete_before("pow_challenges", "expires_at_ms", now_ms, batch_size)
+            .await?;
+        let consumed_deleted = self
+            .delete_before(
+                "pow_consumed_challenges",
+                "expires_at_ms",
+                now_ms,
+                batch_size,
+            )
+            .await?;
+        let sessions_deleted = self
+            .delete_before("pow_sessions", "expires_at_ms", now_ms, batch_size)
+            .await?;
+        let rate_limits_deleted = PgRateLimitStore::new(self.pool.clone())
+            .cleanup_expired_batched(batch_size)
+            .await?;
+        let events_deleted = self
+            .delete_before(
+                "pow_client_events",
+                "created_at_ms",
+                old_window_ms,
+                batch_size,
+            )
+            .await?;
+        let rollups_deleted = self
+            .delete_before(
+                "pow_solve_rollups",
+                "period_start_ms",
+                now_ms - TELEMETRY_RETENTION_MS,
+                batch_size,
+            )
+            .await?;
+        let offenses_deleted = self
+            .delete_before(
+                "pow_offenses",
+                "created_at_ms",
+                now_ms - OFFENSE_RETENTION_MS,
+                batch_size,
+            )
+            .await?;
+        let bans_deleted = self
+            .delete_before(
+                "pow_bans",
+                "expires_at_ms",
+                now_ms - BAN_RETENTION_MS,
+                batch_size,
+            )
+            .await?;
 
         tracing::info!(
             challenges = challenges_deleted,
+            consumed_challenges = consumed_deleted,
             sessions = sessions_deleted,
             rate_limits = rate_limits_deleted,
+            events = events_deleted,
+            solve_rollups = rollups_deleted,
+            offenses = offenses_deleted,
+            bans = bans_deleted,
             "Cleaned up expired PoW data"
         );
 
         Ok((challenges_deleted, sessions_deleted, rate_limits_deleted))
     }
+
+    /// Delete the rows of `table` with `column < before_ms`, `batch_size` at a time
+    ///
+    /// Short statements keep lock times and WAL bursts small when a large
+    /// backlog has built up. `table` and `column` are always literals.
+    async fn delete_before(
+        &self,
+        table: &'static str,
+        column: &'static str,
+        before_ms: i64,
+        batch_size: i64,
+    ) -> PowResult<u64> {
+        let sql = format!(
+            "DELETE FROM {table} WHERE ctid = ANY(ARRAY(\
+             SELECT ctid FROM {table} WHERE {column} < $1 LIMIT $2))"
+        );
+
+        let mut deleted = 0;
+        loop {
+            let batch = sqlx::query(&sql)
+                .bind(before_ms)
+                .bind(batch_size)
+                .execute(&self.pool)
+                .await?
+                .rows_affected();
+            deleted += batch;
+            if batch < batch_size as u64 {
+                return Ok(deleted);
+            }
+        }
+    }
 }
 
 impl ChallengeRepository for PgPowRepository {
     async fn create(&self, challenge: &Challenge) -> PowResult<()> {
+        let argon2_params = challenge.algorithm.argon2_params();
+
         sqlx::query(
             r#"
             INSERT INTO pow_challenges (
                 pow_challenge_id,
                 pow_challenge_bytes,
+                pow_algorithm,
+                argon2_memory_kib,
+                argon2_iterations,
+                argon2_parallelism,
+                pow_puzzle_count,
                 pow_difficulty_bits,
                 expires_at_ms,
                 client_fingerprint_hash,
-                client_ip
-            ) VALUES ($1, $2, $3, $4, $5, $6::inet)
+                client_ip,
+                pow_action,
+                pow_payload_hash,
+                pow_protocol_version
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11::inet, $12, $13, $14)
             "#,
         )
         .bind(challenge.id)
         .bind(&challenge.challenge_bytes)
+        .bind(challenge.algorithm.id())
+        .bind(argon2_params.map(|p| p.memory_kib as i32))
+        .bind(argon2_params.map(|p| p.iterations as i32))
+        .bind(argon2_params.map(|p| p.parallelism as i32))
+        .bind(challenge.puzzle_count as i16)
         .bind(challenge.difficulty_bits as i16)
         .bind(challenge.expires_at_ms)
         .bind(&challenge.client_fingerprint_hash)
         .bind(challenge.client_ip.as_ref().map(|ip| ip.to_string()))
+        .bind(challenge.action.as_ref().map(PowAction::as_str))
+        .bind(challenge.payload_hash.as_deref())
+        .bind(challenge.protocol_version.as_u8() as i16)
         .execute(&self.pool)
         .await?;
 
         tracing::info!(
             challenge_id = %challenge.id,
+            algorithm = challenge.algorithm.id(),
             difficulty = challenge.difficulty_bits,
             "Challenge created"
         );
@@ -104,11 +243,19 @@ impl ChallengeRepository for PgPowRepository {
                 RETURNING
                     pow_challenge_id,
                     pow_challenge_bytes,
+                    pow_algorithm,
+                    argon2_memory_kib,
+                    argon2_iterations,
+                    argon2_parallelism,
+                    pow_puzzle_count,
                     pow_difficulty_bits,
                     expires_at_ms,
                     created_at,
                     client_fingerprint_hash,
-                    client_ip::TEXT
+                    client_ip::TEXT,
+                    pow_action,
+                    pow_payload_hash,
+                    pow_protocol_version
             "#,
         )
         .bind(challenge_id)
@@ -137,9 +284,12 @@ impl ChallengeRepository for PgPowRepository {
                         Err(PowError::ChallengeExpired)
                     }
                     Some(_) => {
-                        // Exists and not expired, but fingerprint mismatch (or other constraint)
-                        tracing::warn!(challenge_id = %challenge_id, "Challenge not consumable");
-                        Ok(None)
+                        // Exists and not expired: issued to another client
+                        tracing::warn!(
+                            challenge_id = %challenge_id,
+                            "Challenge fingerprint mismatch"
+                        );
+                        Err(PowError::ChallengeFingerprintMismatch)
                     }
                     None => {
                         tracing::warn!(challenge_id = %challenge_id, "Challenge not found");
@@ -149,6 +299,23 @@ impl ChallengeRepository for PgPowRepository {
             }
         }
     }
+
+    async fn mark_consumed(&self, challenge_id: Uuid, expires_at_ms: i64) -> PowResult<bool> {
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO pow_consumed_challenges (pow_challenge_id, expires_at_ms)
+            VALUES ($1, $2)
+            ON CONFLICT (pow_challenge_id) DO NOTHING
+            "#,
+        )
+        .bind(challenge_id)
+        .bind(expires_at_ms)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(inserted == 1)
+    }
 }
 
 impl PowSessionRepository for PgPowRepository {
@@ -159,20 +326,35 @@ impl PowSessionRepository for PgPowRepository {
                 pow_session_id,
                 expires_at_ms,
                 client_fingerprint_hash,
-                pow_challenge_id
-            ) VALUES ($1, $2, $3, $4)
+                pow_challenge_id,
+                pow_action,
+                pow_payload_hash,
+                remaining_requests,
+                pow_difficulty_bits,
+                pow_puzzle_count
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
             "#,
         )
         .bind(pow_session.id)
         .bind(pow_session.expires_at_ms)
         .bind(&pow_session.client_fingerprint_hash)
         .bind(pow_session.challenge_id)
+        .bind(pow_session.action.as_ref().map(PowAction::as_str))
+        .bind(pow_session.payload_hash.as_deref())
+        .bind(
+            pow_session
+                .remaining_requests
+                .map(|n| n.min(i32::MAX as u32) as i32),
+        )
+        .bind(i16::from(pow_session.difficulty_bits))
+        .bind(i16::from(pow_session.puzzle_count))
         .execute(&self.pool)
         .await?;
 
         tracing::info!(
             pow_session_id = %pow_session.id,
             challenge_id = %pow_session.challenge_id,
+            action = pow_session.action.as_ref().map(PowAction::as_str),
             "PoW session created"
         );
 
@@ -193,7 +375,12 @@ impl PowSessionRepository for PgPowRepository {
                 expires_at_ms,
                 created_at,
                 client_fingerprint_hash,
-                pow_challenge_id
+                pow_challenge_id,
+                pow_action,
+                pow_payload_hash,
+                remaining_requests,
+                pow_difficulty_bits,
+                pow_puzzle_count
             FROM pow_sessions
             WHERE pow_session_id = $1 AND expires_at_ms > $2
             "#,
@@ -213,12 +400,169 @@ impl PowSessionRepository for PgPowRepository {
                     );
                     return Err(PowError::SessionFingerprintMismatch);
                 }
-                Ok(Some(r.into_pow_session()))
+                Ok(Some(r.into_pow_session()?))
             }
             None => Ok(None),
         }
     }
 
+    async fn spend_request(
+        &self,
+        pow_session_id: Uuid,
+        fingerprint: &ClientFingerprint,
+    ) -> PowResult<Option<PowSession>> {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+
+        let row = sqlx::query_as::<_, PowSessionRow>(
+            r#"
+            UPDATE pow_sessions
+            SET remaining_requests = remaining_requests - 1
+            WHERE pow_session_id = $1
+                AND expires_at_ms > $2
+                AND client_fingerprint_hash = $3
+                AND pow_action IS NULL
+                AND (remaining_requests IS NULL OR remaining_requests > 0)
+            RETURNING
+                pow_session_id,
+                expires_at_ms,
+                created_at,
+                client_fingerprint_hash,
+                pow_challenge_id,
+                pow_action,
+                pow_payload_hash,
+                remaining_requests,
+                pow_difficulty_bits,
+                pow_puzzle_count
+            "#,
+        )
+        .bind(pow_session_id)
+        .bind(now_ms)
+        .bind(fingerprint.hash_vec())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(r) => Ok(Some(r.into_pow_session()?)),
+            None => {
+                tracing::debug!(pow_session_id = %pow_session_id, "PoW session not usable");
+                Ok(None)
+            }
+        }
+    }
+
+    async fn renew(
+        &self,
+        pow_session_id: Uuid,
+        fingerprint: &ClientFingerprint,
+        renewed: &PowSession,
+    ) -> PowResult<Option<PowSession>> {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+
+        let row = sqlx::query_as::<_, PowSessionRow>(
+            r#"
+            UPDATE pow_sessions
+            SET
+                expires_at_ms = $4,
+                pow_challenge_id = $5,
+                remaining_requests = $6,
+                pow_difficulty_bits = $7,
+                pow_puzzle_count = $8
+            WHERE pow_session_id = $1
+                AND expires_at_ms > $2
+                AND client_fingerprint_hash = $3
+                AND pow_action IS NULL
+            RETURNING
+                pow_session_id,
+                expires_at_ms,
+                created_at,
+                client_fingerprint_hash,
+                pow_challenge_id,
+                pow_action,
+                pow_payload_hash,
+                remaining_requests,
+                pow_difficulty_bits,
+                pow_puzzle_count
+            "#,
+        )
+        .bind(pow_session_id)
+        .bind(now_ms)
+        .bind(fingerprint.hash_vec())
+        .bind(renewed.expires_at_ms)
+        .bind(renewed.challenge_id)
+        .bind(
+            renewed
+                .remaining_requests
+                .map(|n| n.min(i32::MAX as u32) as i32),
+        )
+        .bind(i16::from(renewed.difficulty_bits))
+        .bind(i16::from(renewed.puzzle_count))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(r) => {
+                tracing::info!(
+                    pow_session_id = %pow_session_id,
+                    challenge_id = %renewed.challenge_id,
+                    expires_at_ms = renewed.expires_at_ms,
+                    "PoW session renewed"
+                );
+                Ok(Some(r.into_pow_session()?))
+            }
+            None => {
+                tracing::debug!(pow_session_id = %pow_session_id, "PoW session not renewable");
+                Ok(None)
+            }
+        }
+    }
+
+    async fn consume(
+        &self,
+        pow_session_id: Uuid,
+        fingerprint: &ClientFingerprint,
+        action: &PowAction,
+    ) -> PowResult<Option<PowSession>> {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+
+        let row = sqlx::query_as::<_, PowSessionRow>(
+            r#"
+            DELETE FROM pow_sessions
+            WHERE pow_session_id = $1
+                AND expires_at_ms > $2
+                AND client_fingerprint_hash = $3
+                AND pow_action = $4
+            RETURNING
+                pow_session_id,
+                expires_at_ms,
+                created_at,
+                client_fingerprint_hash,
+                pow_challenge_id,
+                pow_action,
+                pow_payload_hash,
+                remaining_requests,
+                pow_difficulty_bits,
+                pow_puzzle_count
+            "#,
+        )
+        .bind(pow_session_id)
+        .bind(now_ms)
+        .bind(fingerprint.hash_vec())
+        .bind(action.as_str())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(r) => {
+                tracing::info!(pow_session_id = %pow_session_id, action = %action, "PoW proof redeemed");
+                Ok(Some(r.into_pow_session()?))
+            }
+            None => {
+                tracing::warn!(pow_session_id = %pow_session_id, action = %action, "PoW proof not redeemable");
+                Ok(None)
+            }
+        }
+    }
+
     async fn delete(&self, pow_session_id: Uuid) -> PowResult<()> {
         sqlx::query("DELETE FROM pow_sessions WHERE pow_session_id = $1")
             .bind(pow_session_id)
@@ -230,38 +574,349 @@ impl PowSessionRepository for PgPowRepository {
     }
 }
 
-impl RateLimitRepository for PgPowRepository {
-    async fn check(
+impl RateLimitStore for PgPowRepository {
+    async fn check_and_increment(
+        &self,
+        key: &str,
+        config: &RateLimitConfig,
+    ) -> Result<RateLimitResult, RateLimitError> {
+        self.rate_limits.check_and_increment(key, config).await
+    }
+}
+
+impl ActivityRepository for PgPowRepository {
+    async fn record(&self, kind: ActivityKind, fingerprint: &ClientFingerprint) -> PowResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO pow_client_events (
+                event_kind,
+                client_fingerprint_hash,
+                client_network,
+                created_at_ms
+            ) VALUES ($1, $2, $3::cidr, $4)
+            "#,
+        )
+        .bind(kind.as_str())
+        .bind(self.client_network.client_hash(fingerprint).to_vec())
+        .bind(self.client_network.key(fingerprint))
+        .bind(Utc::now().timestamp_millis())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn summarize(
         &self,
         fingerprint: &ClientFingerprint,
-        max_requests: u32,
         window_ms: i64,
-    ) -> PowResult<bool> {
-        let now_ms = chrono::Utc::now().timestamp_millis();
-        let window_start = (now_ms / window_ms) * window_ms;
+    ) -> PowResult<DifficultyInputs> {
+        let since_ms = Utc::now().timestamp_millis() - window_ms;
 
-        let row = sqlx::query_as::<_, (i32,)>(
+        let row = sqlx::query_as::<_, ActivitySummaryRow>(
             r#"
-            INSERT INTO pow_rate_limits (client_fingerprint_hash, window_start_ms, request_count)
-            VALUES ($1, $2, 1)
-            ON CONFLICT (client_fingerprint_hash, window_start_ms)
-            DO UPDATE SET request_count = pow_rate_limits.request_count + 1
-            RETURNING request_count
+            SELECT
+                COUNT(*) FILTER (
+                    WHERE event_kind = 'invalid_nonce'
+                ) AS client_invalid_nonces,
+                COUNT(*) FILTER (
+                    WHERE event_kind = 'session_created'
+                ) AS client_sessions
+            FROM pow_client_events
+            WHERE created_at_ms >= $1
+              AND client_fingerprint_hash = $2
             "#,
         )
-        .bind(fingerprint.hash.as_slice())
-        .bind(window_start)
+        .bind(since_ms)
+        .bind(self.client_network.client_hash(fingerprint).to_vec())
         .fetch_one(&self.pool)
         .await?;
 
-        let count = row.0 as u32;
-        let allowed = count <= max_requests;
+        Ok(row.into_inputs())
+    }
+}
+
+impl TelemetryRepository for PgPowRepository {
+    async fn record_solve(&self, sample: &SolveSample, solved_at_ms: i64) -> PowResult<()> {
+        let client_report = sample.client_elapsed_ms.zip(sample.client_total_hashes);
+
+        sqlx::query(
+            r#"
+            INSERT INTO pow_solve_rollups (
+                period_start_ms,
+                pow_algorithm,
+                pow_work_bits,
+                device_class,
+                ua_family,
+                elapsed_bucket,
+                samples,
+                server_elapsed_ms_sum,
+                client_samples,
+                client_elapsed_ms_sum,
+                client_hashes_sum
+            ) VALUES ($1, $2, $3, $4, $5, $6, 1, $7, $8, $9, $10)
+            ON CONFLICT (
+                period_start_ms, pow_algorithm, pow_work_bits, device_class, ua_family, elapsed_bucket
+            ) DO UPDATE SET
+                samples = pow_solve_rollups.samples + 1,
+                server_elapsed_ms_sum = pow_solve_rollups.server_elapsed_ms_sum + EXCLUDED.server_elapsed_ms_sum,
+                client_samples = pow_solve_rollups.client_samples + EXCLUDED.client_samples,
+                client_elapsed_ms_sum = pow_solve_rollups.client_elapsed_ms_sum + EXCLUDED.client_elapsed_ms_sum,
+                client_hashes_sum = pow_solve_rollups.client_hashes_sum + EXCLUDED.client_hashes_sum
+            "#,
+        )
+        .bind(rollup_period_start_ms(solved_at_ms))
+        .bind(sample.algorithm)
+        .bind(sample.work_bits() as i16)
+        .bind(sample.device_class.as_str())
+        .bind(sample.ua_family)
+        .bind(elapsed_bucket(sample.server_elapsed_ms))
+        .bind(sample.server_elapsed_ms)
+        .bind(i64::from(client_report.is_some()))
+        .bind(client_report.map_or(0, |(elapsed, _)| elapsed))
+        .bind(client_report.map_or(0, |(_, hashes)| hashes))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn solve_rollups(&self, since_ms: i64) -> PowResult<Vec<SolveRollup>> {
+        let rows = sqlx::query_as::<_, SolveRollupRow>(
+            r#"
+            SELECT
+                pow_algorithm,
+                pow_work_bits,
+                device_class,
+                elapsed_bucket,
+                SUM(samples)::BIGINT AS samples,
+                SUM(client_samples)::BIGINT AS client_samples,
+                SUM(client_elapsed_ms_sum)::BIGINT AS client_elapsed_ms,
+                SUM(client_hashes_sum)::BIGINT AS client_hashes
+            FROM pow_solve_rollups
+            WHERE period_start_ms >= $1
+            GROUP BY pow_algorithm, pow_work_bits, device_class, elapsed_bucket
+            "#,
+        )
+        .bind(rollup_period_start_ms(since_ms))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(SolveRollupRow::into_rollup).collect())
+    }
+}
 
-        if !allowed {
-            tracing::warn!(count = count, max = max_requests, "Rate limit exceeded");
+impl BanRepository for PgPowRepository {
+    async fn active_ban(&self, keys: &BanKeys, now_ms: i64) -> PowResult<Option<Ban>> {
+        let row = sqlx::query_as::<_, BanRow>(
+            r#"
+            SELECT
+                pow_ban_id,
+                subject_kind,
+                client_fingerprint_hash,
+                client_network::TEXT,
+                reason,
+                offense_count,
+                strike,
+                created_at_ms,
+                expires_at_ms
+            FROM pow_bans
+            WHERE expires_at_ms > $1
+                AND lifted_at_ms IS NULL
+                AND (client_fingerprint_hash = $2 OR client_network = $3::cidr)
+            ORDER BY expires_at_ms DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(now_ms)
+        .bind(keys.client_hash.as_slice())
+        .bind(keys.network.as_deref())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(BanRow::into_ban).transpose()
+    }
+
+    async fn record_offense(
+        &self,
+        kind: OffenseKind,
+        keys: &BanKeys,
+        now_ms: i64,
+        since_ms: i64,
+    ) -> PowResult<OffenseCounts> {
+        sqlx::query(
+            r#"
+            INSERT INTO pow_offenses (
+                offense_kind,
+                client_fingerprint_hash,
+                client_network,
+                created_at_ms
+            ) VALUES ($1, $2, $3::cidr, $4)
+            "#,
+        )
+        .bind(kind.as_str())
+        .bind(keys.client_hash.as_slice())
+        .bind(keys.network.as_deref())
+        .bind(now_ms)
+        .execute(&self.pool)
+        .await?;
+
+        let (fingerprint_count, network_count) = sqlx::query_as::<_, (i64, i64)>(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE client_fingerprint_hash = $2),
+                COUNT(*) FILTER (WHERE client_network = $3::cidr)
+            FROM pow_offenses
+            WHERE created_at_ms >= $1
+                AND (client_fingerprint_hash = $2 OR client_network = $3::cidr)
+            "#,
+        )
+        .bind(since_ms)
+        .bind(keys.client_hash.as_slice())
+        .bind(keys.network.as_deref())
+        .fetch_one(&self.pool)
+        .await?;
+
+        let count = |n: i64| u32::try_from(n).unwrap_or(u32::MAX);
+        Ok(OffenseCounts {
+            fingerprint: count(fingerprint_count),
+            network: count(network_count),
+        })
+    }
+
+    async fn count_bans(&self, subject: &BanSubject, since_ms: i64) -> PowResult<u32> {
+        let (fingerprint, network) = subject_columns(subject);
+
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+            FROM pow_bans
+            WHERE created_at_ms >= $1
+                AND subject_kind = $2
+                AND (client_fingerprint_hash = $3 OR client_network = $4::cidr)
+            "#,
+        )
+        .bind(since_ms)
+        .bind(subject.kind())
+        .bind(fingerprint)
+        .bind(network)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(u32::try_from(count).unwrap_or(u32::MAX))
+    }
+
+    async fn create_ban(&self, ban: &Ban) -> PowResult<()> {
+        let (fingerprint, network) = subject_columns(&ban.subject);
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO pow_bans (
+                pow_ban_id,
+                subject_kind,
+                client_fingerprint_hash,
+                client_network,
+                reason,
+                offense_count,
+                strike,
+                created_at_ms,
+                expires_at_ms
+            ) VALUES ($1, $2, $3, $4::cidr, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(ban.id)
+        .bind(ban.subject.kind())
+        .bind(fingerprint)
+        .bind(network)
+        .bind(ban.reason.as_str())
+        .bind(ban.offense_count as i32)
+        .bind(ban.strike as i32)
+        .bind(ban.created_at_ms)
+        .bind(ban.expires_at_ms)
+        .execute(&mut *tx)
+        .await?;
+
+        // Offenses that led to this ban must not count towards the next one
+        let clear = match &ban.subject {
+            BanSubject::Fingerprint(_) => {
+                "DELETE FROM pow_offenses WHERE client_fingerprint_hash = $1"
+            }
+            BanSubject::Network(_) => "DELETE FROM pow_offenses WHERE client_network = $2::cidr",
+        };
+        sqlx::query(clear)
+            .bind(fingerprint)
+            .bind(network)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        tracing::warn!(
+            ban_id = %ban.id,
+            subject_kind = ban.subject.kind(),
+            subject = %ban.subject.display(),
+            reason = ban.reason.as_str(),
+            strike = ban.strike,
+            expires_at_ms = ban.expires_at_ms,
+            "PoW client banned"
+        );
+
+        Ok(())
+    }
+
+    async fn list_active_bans(&self, now_ms: i64) -> PowResult<Vec<Ban>> {
+        let rows = sqlx::query_as::<_, BanRow>(
+            r#"
+            SELECT
+                pow_ban_id,
+                subject_kind,
+                client_fingerprint_hash,
+                client_network::TEXT,
+                reason,
+                offense_count,
+                strike,
+                created_at_ms,
+                expires_at_ms
+            FROM pow_bans
+            WHERE expires_at_ms > $1 AND lifted_at_ms IS NULL
+            ORDER BY created_at_ms DESC
+            "#,
+        )
+        .bind(now_ms)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(BanRow::into_ban).collect()
+    }
+
+    async fn lift_ban(&self, ban_id: Uuid, now_ms: i64) -> PowResult<bool> {
+        let lifted = sqlx::query(
+            r#"
+            UPDATE pow_bans
+            SET lifted_at_ms = $2
+            WHERE pow_ban_id = $1 AND expires_at_ms > $2 AND lifted_at_ms IS NULL
+            "#,
+        )
+        .bind(ban_id)
+        .bind(now_ms)
+        .execute(&self.pool)
+        .await?
+        .rows_affected()
+            > 0;
+
+        if lifted {
+            tracing::info!(ban_id = %ban_id, "PoW ban lifted");
         }
+        Ok(lifted)
+    }
+}
 
-        Ok(allowed)
+/// Fingerprint hash and network columns of a ban subject
+fn subject_columns(subject: &BanSubject) -> (Option<&[u8]>, Option<&str>) {
+    match subject {
+        BanSubject::Fingerprint(hash) => (Some(hash.as_slice()), None),
+        BanSubject::Network(network) => (None, Some(network.as_str())),
     }
 }
 
@@ -270,23 +925,58 @@ impl RateLimitRepository for PgPowRepository {
 struct ChallengeRow {
     pow_challenge_id: Uuid,
     pow_challenge_bytes: Vec<u8>,
+    pow_algorithm: String,
+    argon2_memory_kib: Option<i32>,
+    argon2_iterations: Option<i32>,
+    argon2_parallelism: Option<i32>,
+    pow_puzzle_count: i16,
     pow_difficulty_bits: i16,
     expires_at_ms: i64,
     created_at: chrono::DateTime<chrono::Utc>,
     client_fingerprint_hash: Vec<u8>,
     client_ip: Option<String>,
+    pow_action: Option<String>,
+    pow_payload_hash: Option<Vec<u8>>,
+    pow_protocol_version: i16,
 }
 
 impl ChallengeRow {
     fn into_challenge(self) -> PowResult<Challenge> {
+        let argon2_params = match (
+            self.argon2_memory_kib,
+            self.argon2_iterations,
+            self.argon2_parallelism,
+        ) {
+            (Some(m), Some(t), Some(p)) => Argon2Params::new(m as u32, t as u32, p as u32),
+            _ => None,
+        };
+        let algorithm =
+            PowAlgorithm::from_parts(&self.pow_algorithm, argon2_params).ok_or_else(|| {
+                PowError::Internal(format!("Unknown PoW algorithm: {}", self.pow_algorithm))
+            })?;
+        let protocol_version = u8::try_from(self.pow_protocol_version)
+            .ok()
+            .and_then(ProtocolVersion::from_u8)
+            .ok_or_else(|| {
+                PowError::Internal(format!(
+                    "Unknown PoW protocol version: {}",
+                    self.pow_protocol_version
+                ))
+            })?;
+
         Ok(Challenge {
             id: self.pow_challenge_id,
             challenge_bytes: self.pow_challenge_bytes,
+            algorithm,
+            protocol_version,
+            puzzle_count: self.pow_puzzle_count as u8,
             difficulty_bits: self.pow_difficulty_bits as u8,
             expires_at_ms: self.expires_at_ms,
             created_at: self.created_at,
             client_fingerprint_hash: self.client_fingerprint_hash,
             client_ip: self.client_ip.and_then(|s| s.parse().ok()),
+            action: parse_action(self.pow_action)?,
+            payload_hash: self.pow_payload_hash,
         })
     }
 }
@@ -298,16 +988,122 @@ struct PowSessionRow {
     created_at: chrono::DateTime<chrono::Utc>,
     client_fingerprint_hash: Vec<u8>,
     pow_challenge_id: Uuid,
+    pow_action: Option<String>,
+    pow_payload_hash: Option<Vec<u8>>,
+    remaining_requests: Option<i32>,
+    pow_difficulty_bits: i16,
+    pow_puzzle_count: i16,
 }
 
 impl PowSessionRow {
-    fn into_pow_session(self) -> PowSession {
-        PowSession {
+    fn into_pow_session(self) -> PowResult<PowSession> {
+        Ok(PowSession {
             id: self.pow_session_id,
             expires_at_ms: self.expires_at_ms,
             created_at: self.created_at,
             client_fingerprint_hash: self.client_fingerprint_hash,
             challenge_id: self.pow_challenge_id,
+            action: parse_action(self.pow_action)?,
+            payload_hash: self.pow_payload_hash,
+            remaining_requests: self.remaining_requests.map(|n| n.max(0) as u32),
+            difficulty_bits: self.pow_difficulty_bits.clamp(0, 255) as u8,
+            puzzle_count: self.pow_puzzle_count.clamp(1, 255) as u8,
+        })
+    }
+}
+
+fn parse_action(action: Option<String>) -> PowResult<Option<PowAction>> {
+    action
+        .map(|a| {
+            PowAction::new(&a).ok_or_else(|| PowError::Internal(format!("Invalid PoW action: {a}")))
+        })
+        .transpose()
+}
+
+#[derive(sqlx::FromRow)]
+struct ActivitySummaryRow {
+    client_invalid_nonces: i64,
+    client_sessions: i64,
+}
+
+impl ActivitySummaryRow {
+    fn into_inputs(self) -> DifficultyInputs {
+        let count = |v: i64| u32::try_from(v).unwrap_or(u32::MAX);
+        DifficultyInputs {
+            client_invalid_nonces: count(self.client_invalid_nonces),
+            client_sessions: count(self.client_sessions),
+            ..Default::default()
         }
     }
 }
+
+#[derive(sqlx::FromRow)]
+struct SolveRollupRow {
+    pow_algorithm: String,
+    pow_work_bits: i16,
+    device_class: String,
+    elapsed_bucket: i16,
+    samples: i64,
+    client_samples: i64,
+    client_elapsed_ms: i64,
+    client_hashes: i64,
+}
+
+impl SolveRollupRow {
+    fn into_rollup(self) -> SolveRollup {
+        SolveRollup {
+            algorithm: self.pow_algorithm,
+            work_bits: self.pow_work_bits.clamp(0, u8::MAX as i16) as u8,
+            device_class: DeviceClass::parse(&self.device_class),
+            elapsed_bucket: self.elapsed_bucket,
+            samples: self.samples.max(0) as u64,
+            client_samples: self.client_samples.max(0) as u64,
+            client_elapsed_ms: self.client_elapsed_ms,
+            client_hashes: self.client_hashes,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct BanRow {
+    pow_ban_id: Uuid,
+    subject_kind: String,
+    client_fingerprint_hash: Option<Vec<u8>>,
+    client_network: Option<String>,
+    reason: String,
+    offense_count: i32,
+    strike: i32,
+    created_at_ms: i64,
+    expires_at_ms: i64,
+}
+
+impl BanRow {
+    fn into_ban(self) -> PowResult<Ban> {
+        let subject = match (
+            self.subject_kind.as_str(),
+            self.client_fingerprint_hash,
+            self.client_network,
+        ) {
+            ("fingerprint", Some(hash), _) => BanSubject::Fingerprint(hash),
+            ("network", _, Some(network)) => BanSubject::Network(network),
+            (kind, _, _) => {
+                return Err(PowError::Internal(format!(
+                    "Invalid PoW ban subject: {kind}"
+                )));
+            }
+        };
+        let reason = OffenseKind::parse(&self.reason).ok_or_else(|| {
+            PowError::Internal(format!("Unknown PoW offense kind: {}", self.reason))
+        })?;
+
+        Ok(Ban {
+            id: self.pow_ban_id,
+            subject,
+            reason,
+            offense_count: self.offense_count.max(0) as u32,
+            strike: self.strike.max(1) as u32,
+            created_at_ms: self.created_at_ms,
+            expires_at_ms: self.expires_at_ms,
+        })
+    }
+}
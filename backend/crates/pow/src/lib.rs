@@ -22,7 +22,8 @@ pub mod presentation;
 pub use application::config::PowConfig;
 pub use error::{PowError, PowResult};
 pub use infra::postgres::PgPowRepository;
-pub use presentation::router::pow_router;
+pub use presentation::guard_layer::PowGuardLayer;
+pub use presentation::router::{pow_admin_router, pow_router};
 
 // Re-export kernel error types for unified error handling
 pub use kernel::error::{
@@ -3,30 +3,205 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::domain::ban::Ban;
+use crate::domain::services::ProtocolVersion;
+
+/// Query for GET /api/pow/challenge
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChallengeQuery {
+    /// Action to scope the proof to (e.g. `signup`)
+    #[serde(default)]
+    pub action: Option<String>,
+    /// base64url (no padding) SHA-256 of the request body the proof is for
+    #[serde(default)]
+    pub payload_hash: Option<String>,
+    /// Wire protocol version the client speaks (absent = 1)
+    #[serde(default)]
+    pub protocol_version: Option<u8>,
+}
+
 /// Response for GET /api/pow/challenge
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ChallengeResponse {
     pub pow_challenge_id: Uuid,
     pub pow_challenge_b64: String,
+    /// Wire protocol version; decides the nonce encoding and submit fields
+    #[serde(default = "protocol_v1")]
+    pub pow_protocol_version: u8,
+    /// Algorithm identifier ("sha256" or "argon2id")
+    pub pow_algorithm: String,
+    /// Algorithm parameters (absent for sha256)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pow_algorithm_params: Option<PowAlgorithmParams>,
+    /// Number of sub-puzzles; one nonce must be submitted per puzzle
+    pub pow_puzzle_count: u8,
+    /// Difficulty of each sub-puzzle
     pub pow_difficulty_bits: u8,
     pub pow_expires_at_ms: i64,
+    /// Action the proof will be scoped to (absent for general sessions)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pow_action: Option<String>,
+    /// Signed challenge to send back on submit (stateless mode only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pow_challenge_token: Option<String>,
+}
+
+fn protocol_v1() -> u8 {
+    ProtocolVersion::V1.as_u8()
+}
+
+/// Parameters of a memory-hard algorithm
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowAlgorithmParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
 }
 
 /// Request for POST /api/pow/submit
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SubmitRequest {
     pub challenge_id: Uuid,
-    pub nonce_u32: u32,
-    #[serde(default)]
+    /// `powChallengeToken` from the challenge response (stateless mode)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub challenge_token: Option<String>,
+    /// `powProtocolVersion` of the challenge (absent = 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol_version: Option<u8>,
+    /// Single-puzzle solution (v1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce_u32: Option<u32>,
+    /// Multi-puzzle solution (v1, one nonce per sub-puzzle, in order)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonces_u32: Option<Vec<u32>>,
+    /// Solution (v2, one nonce per sub-puzzle, in order)
+    ///
+    /// Sent as decimal strings: JSON numbers lose precision above 2^53 in
+    /// JavaScript.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "decimal_nonces"
+    )]
+    pub nonces_u64: Option<Vec<u64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub elapsed_ms: Option<i64>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub total_hashes: Option<i64>,
 }
 
+impl SubmitRequest {
+    /// Protocol version of the submission (None if unknown)
+    pub fn protocol_version(&self) -> Option<ProtocolVersion> {
+        match self.protocol_version {
+            Some(version) => ProtocolVersion::from_u8(version),
+            None => Some(ProtocolVersion::V1),
+        }
+    }
+
+    /// Submitted nonces in the field of the submission's protocol version
+    ///
+    /// v1 reads `noncesU32` (taking precedence) or `nonceU32`; v2 reads `noncesU64`.
+    pub fn nonces(&self) -> Option<Vec<u64>> {
+        match self.protocol_version()? {
+            ProtocolVersion::V1 => match (&self.nonces_u32, self.nonce_u32) {
+                (Some(nonces), _) => Some(nonces.iter().map(|&n| n as u64).collect()),
+                (None, Some(nonce)) => Some(vec![nonce as u64]),
+                (None, None) => None,
+            },
+            ProtocolVersion::V2 => self.nonces_u64.clone(),
+        }
+    }
+}
+
+/// `Option<Vec<u64>>` as an array of decimal strings
+mod decimal_nonces {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(nonces: &Option<Vec<u64>>, s: S) -> Result<S::Ok, S::Error> {
+        match nonces {
+            Some(nonces) => s.collect_seq(nonces.iter().map(u64::to_string)),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Vec<u64>>, D::Error> {
+        Option::<Vec<String>>::deserialize(d)?
+            .map(|nonces| {
+                nonces
+                    .iter()
+                    .map(|n| n.parse().map_err(D::Error::custom))
+                    .collect()
+            })
+            .transpose()
+    }
+}
+
+/// Response for POST /api/pow/submit of an action-scoped challenge
+///
+/// The proof is sent back in the `X-PoW-Proof` header of the protected request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofResponse {
+    pub pow_proof: String,
+    pub pow_action: String,
+    pub pow_expires_at_ms: i64,
+}
+
 /// Response for GET /api/pow/status
-#[derive(Debug, Clone, Serialize)]
+///
+/// Everything but `passed` is absent without a session.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct StatusResponse {
     pub passed: bool,
+    /// Requests the session may still authorize (absent if unlimited or no session)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining_requests: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at_ms: Option<i64>,
+    /// Time left until the session expires
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining_ttl_ms: Option<i64>,
+    /// Difficulty of each sub-puzzle the session was earned with (absent if unknown)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub difficulty_bits: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub puzzle_count: Option<u8>,
+}
+
+/// Entry of GET /api/admin/pow/bans
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BanResponse {
+    pub ban_id: Uuid,
+    /// `fingerprint` or `network`
+    pub subject_kind: String,
+    /// Hex fingerprint hash or CIDR
+    pub subject: String,
+    /// Offense that triggered the ban
+    pub reason: String,
+    pub offense_count: u32,
+    pub strike: u32,
+    pub created_at_ms: i64,
+    pub expires_at_ms: i64,
+}
+
+impl From<Ban> for BanResponse {
+    fn from(ban: Ban) -> Self {
+        Self {
+            ban_id: ban.id,
+            subject_kind: ban.subject.kind().to_string(),
+            subject: ban.subject.display(),
+            reason: ban.reason.as_str().to_string(),
+            offense_count: ban.offense_count,
+            strike: ban.strike,
+            created_at_ms: ban.created_at_ms,
+            expires_at_ms: ban.expires_at_ms,
+        }
+    }
 }
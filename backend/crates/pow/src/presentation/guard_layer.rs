@@ -0,0 +1,161 @@
+//! PoW Guard Layer
+//!
+//! `tower::Layer` form of the PoW guards, for protecting arbitrary routes
+//! with `.route_layer(...)`.
+//!
+//! ## Usage
+//! ```rust,ignore
+//! let router = Router::new()
+//!     .route("/api/export", post(export))
+//!     .route_layer(PowGuardLayer::new(repo, config).json_errors());
+//! ```
+
+use crate::application::config::PowConfig;
+use crate::domain::repository::PowSessionRepository;
+use crate::domain::value_objects::PowAction;
+use crate::presentation::middleware::{
+    Rejection, check_request, forward, pow_required, pow_required_json,
+};
+use axum::body::Body;
+use axum::http::{Method, Request};
+use axum::response::Response;
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Layer requiring a PoW session (or an action proof) on the wrapped routes
+///
+/// Defaults: general PoW session required, header-only `401` rejections and
+/// `OPTIONS` exempt (so CORS preflights pass).
+#[derive(Clone)]
+pub struct PowGuardLayer<R>
+where
+    R: PowSessionRepository + Clone + Send + Sync + 'static,
+{
+    repo: Arc<R>,
+    config: Arc<PowConfig>,
+    action: Option<PowAction>,
+    accept_session: bool,
+    json_errors: bool,
+    exempt_methods: Vec<Method>,
+}
+
+impl<R> PowGuardLayer<R>
+where
+    R: PowSessionRepository + Clone + Send + Sync + 'static,
+{
+    /// Guard that requires a general PoW session
+    pub fn new(repo: Arc<R>, config: Arc<PowConfig>) -> Self {
+        Self {
+            repo,
+            config,
+            action: None,
+            accept_session: false,
+            json_errors: false,
+            exempt_methods: vec![Method::OPTIONS],
+        }
+    }
+
+    /// Require a single-use proof for `action` instead of a session
+    pub fn action(mut self, action: PowAction) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    /// With an action, also accept a general PoW session
+    pub fn or_session(mut self) -> Self {
+        self.accept_session = true;
+        self
+    }
+
+    /// Reject with an RFC 7807 problem details body instead of an empty one
+    pub fn json_errors(mut self) -> Self {
+        self.json_errors = true;
+        self
+    }
+
+    /// Methods let through without PoW (replaces the default `OPTIONS`)
+    pub fn exempt_methods(mut self, methods: impl IntoIterator<Item = Method>) -> Self {
+        self.exempt_methods = methods.into_iter().collect();
+        self
+    }
+
+    fn rejection(&self, rejection: Rejection) -> Response {
+        match rejection {
+            Rejection::PowRequired if self.json_errors => {
+                pow_required_json(&self.config, self.action.as_ref())
+            }
+            Rejection::PowRequired => pow_required(&self.config, self.action.as_ref()),
+            Rejection::Response(response) => response,
+        }
+    }
+}
+
+impl<S, R> Layer<S> for PowGuardLayer<R>
+where
+    R: PowSessionRepository + Clone + Send + Sync + 'static,
+{
+    type Service = PowGuard<S, R>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        PowGuard {
+            inner,
+            guard: Arc::new(self.clone()),
+        }
+    }
+}
+
+/// Service created by [`PowGuardLayer`]
+#[derive(Clone)]
+pub struct PowGuard<S, R>
+where
+    R: PowSessionRepository + Clone + Send + Sync + 'static,
+{
+    inner: S,
+    guard: Arc<PowGuardLayer<R>>,
+}
+
+impl<S, R> Service<Request<Body>> for PowGuard<S, R>
+where
+    S: Service<Request<Body>, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    R: PowSessionRepository + Clone + Send + Sync + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // The ready service must handle this request; leave a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let guard = self.guard.clone();
+
+        Box::pin(async move {
+            if guard.exempt_methods.contains(req.method()) {
+                return inner.call(req).await;
+            }
+
+            let checked = check_request(
+                &guard.repo,
+                &guard.config,
+                guard.action.as_ref(),
+                guard.accept_session,
+                req,
+            )
+            .await;
+
+            match checked {
+                Ok((req, session)) => Ok(forward(inner.call(req).await?, session.as_ref())),
+                Err(rejection) => Ok(guard.rejection(rejection)),
+            }
+        })
+    }
+}
@@ -1,18 +1,33 @@
 //! HTTP Handlers
 
+use crate::application::ban_guard::BanGuard;
 use crate::application::check_session::CheckPowSessionUseCase;
 use crate::application::config::{PowConfig, SameSite};
-use crate::application::issue_challenge::IssueChallengeUseCase;
+use crate::application::issue_challenge::{IssueChallengeInput, IssueChallengeUseCase};
+use crate::application::manage_bans::{LiftBanUseCase, ListBansUseCase};
 use crate::application::submit_solution::{SubmitSolutionInput, SubmitSolutionUseCase};
-use crate::domain::repository::{ChallengeRepository, PowSessionRepository, RateLimitRepository};
-use crate::error::PowResult;
-use crate::presentation::dto::{ChallengeResponse, StatusResponse, SubmitRequest};
+use crate::domain::ban::OffenseKind;
+use crate::domain::repository::{
+    ActivityRepository, BanRepository, ChallengeRepository, PowSessionRepository,
+    TelemetryRepository,
+};
+use crate::domain::services::{MAX_PUZZLE_COUNT, ProtocolVersion};
+use crate::domain::value_objects::PowAction;
+use crate::error::{PowError, PowResult};
+use crate::presentation::dto::{
+    BanResponse, ChallengeQuery, ChallengeResponse, PowAlgorithmParams, ProofResponse,
+    StatusResponse, SubmitRequest,
+};
 use axum::Json;
-use axum::extract::State;
+use axum::extract::{Path, Query, State};
 use axum::http::{HeaderMap, StatusCode, header};
-use axum::response::IntoResponse;
-use platform::client::{extract_client_ip, extract_fingerprint};
+use axum::response::{IntoResponse, Response};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::Utc;
+use platform::client::{ClientIp, extract_fingerprint};
+use platform::rate_limit::RateLimitStore;
 use std::sync::Arc;
+use uuid::Uuid;
 
 /// Shared state for PoW handlers
 #[derive(Clone)]
@@ -20,7 +35,10 @@ pub struct PowAppState<R>
 where
     R: ChallengeRepository
         + PowSessionRepository
-        + RateLimitRepository
+        + RateLimitStore
+        + ActivityRepository
+        + TelemetryRepository
+        + BanRepository
         + Clone
         + Send
         + Sync
@@ -34,98 +52,203 @@ where
 pub async fn issue_challenge<R>(
     State(state): State<PowAppState<R>>,
     headers: HeaderMap,
-    axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
+    ClientIp(client_ip): ClientIp,
+    Query(query): Query<ChallengeQuery>,
 ) -> PowResult<Json<ChallengeResponse>>
 where
     R: ChallengeRepository
         + PowSessionRepository
-        + RateLimitRepository
+        + RateLimitStore
+        + ActivityRepository
+        + TelemetryRepository
+        + BanRepository
         + Clone
         + Send
         + Sync
         + 'static,
 {
-    let client_ip = extract_client_ip(&headers, Some(addr.ip()));
     let fingerprint = extract_fingerprint(&headers, client_ip)?;
 
-    let use_case =
-        IssueChallengeUseCase::new(state.repo.clone(), state.repo.clone(), state.config.clone());
+    let use_case = IssueChallengeUseCase::new(
+        state.repo.clone(),
+        state.repo.clone(),
+        state.repo.clone(),
+        state.repo.clone(),
+        state.config.clone(),
+    );
+
+    let input = IssueChallengeInput {
+        action: query
+            .action
+            .map(|a| {
+                PowAction::new(&a)
+                    .ok_or_else(|| PowError::InvalidRequest(format!("Invalid PoW action: {a}")))
+            })
+            .transpose()?,
+        payload_hash: query
+            .payload_hash
+            .map(|h| {
+                URL_SAFE_NO_PAD
+                    .decode(h)
+                    .map_err(|_| PowError::InvalidRequest("Invalid payload hash".into()))
+            })
+            .transpose()?,
+        protocol_version: match query.protocol_version {
+            Some(version) => ProtocolVersion::from_u8(version).ok_or_else(|| {
+                PowError::InvalidRequest(format!("Unsupported PoW protocol version: {version}"))
+            })?,
+            None => ProtocolVersion::V1,
+        },
+    };
 
-    let output = use_case.execute(fingerprint).await?;
+    let output = use_case.execute(input, fingerprint).await?;
 
     Ok(Json(ChallengeResponse {
         pow_challenge_id: output.challenge_id,
         pow_challenge_b64: output.challenge_b64,
+        pow_protocol_version: output.protocol_version.as_u8(),
+        pow_algorithm: output.algorithm.id().to_string(),
+        pow_algorithm_params: output
+            .algorithm
+            .argon2_params()
+            .map(|p| PowAlgorithmParams {
+                memory_kib: p.memory_kib,
+                iterations: p.iterations,
+                parallelism: p.parallelism,
+            }),
+        pow_puzzle_count: output.puzzle_count,
         pow_difficulty_bits: output.difficulty_bits,
         pow_expires_at_ms: output.expires_at_ms,
+        pow_action: output.action.map(|a| a.to_string()),
+        pow_challenge_token: output.challenge_token,
     }))
 }
 
 /// POST /api/pow/submit
+///
+/// A client still holding a valid session cookie gets that session extended
+/// rather than a new one.
 pub async fn submit_solution<R>(
     State(state): State<PowAppState<R>>,
     headers: HeaderMap,
-    axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
+    ClientIp(client_ip): ClientIp,
     Json(req): Json<SubmitRequest>,
-) -> PowResult<impl IntoResponse>
+) -> PowResult<Response>
 where
     R: ChallengeRepository
         + PowSessionRepository
-        + RateLimitRepository
+        + RateLimitStore
+        + ActivityRepository
+        + TelemetryRepository
+        + BanRepository
         + Clone
         + Send
         + Sync
         + 'static,
 {
-    let client_ip = extract_client_ip(&headers, Some(addr.ip()));
     let fingerprint = extract_fingerprint(&headers, client_ip)?;
 
-    let use_case =
-        SubmitSolutionUseCase::new(state.repo.clone(), state.repo.clone(), state.config.clone());
+    let use_case = SubmitSolutionUseCase::new(
+        state.repo.clone(),
+        state.repo.clone(),
+        state.repo.clone(),
+        state.repo.clone(),
+        state.repo.clone(),
+        state.config.clone(),
+    );
+
+    let protocol_version = req
+        .protocol_version()
+        .ok_or_else(|| PowError::InvalidRequest("Unsupported PoW protocol version".into()))?;
+    let nonces = req.nonces().ok_or_else(|| match protocol_version {
+        ProtocolVersion::V1 => PowError::InvalidRequest("nonceU32 or noncesU32 is required".into()),
+        ProtocolVersion::V2 => PowError::InvalidRequest("noncesU64 is required".into()),
+    })?;
+    if nonces.is_empty() || nonces.len() > MAX_PUZZLE_COUNT as usize {
+        return Err(PowError::InvalidRequest(format!(
+            "Between 1 and {MAX_PUZZLE_COUNT} nonces are required"
+        )));
+    }
 
     let input = SubmitSolutionInput {
         challenge_id: req.challenge_id,
-        nonce_u32: req.nonce_u32,
+        challenge_token: req.challenge_token,
+        protocol_version,
+        nonces,
         elapsed_ms: req.elapsed_ms,
         total_hashes: req.total_hashes,
+        current_session_token: extract_session_cookie(&headers, &state.config.session_cookie_name),
     };
 
     let output = use_case.execute(input, fingerprint).await?;
 
+    // Action-scoped proofs are returned to the caller instead of replacing the session cookie
+    if let Some(action) = output.action {
+        return Ok(Json(ProofResponse {
+            pow_proof: output.session_token,
+            pow_action: action.to_string(),
+            pow_expires_at_ms: output.expires_at_ms,
+        })
+        .into_response());
+    }
+
     let cookie = build_session_cookie(&state.config, &output.session_token);
 
-    Ok((StatusCode::NO_CONTENT, [(header::SET_COOKIE, cookie)]))
+    Ok((StatusCode::NO_CONTENT, [(header::SET_COOKIE, cookie)]).into_response())
 }
 
 /// GET /api/pow/status
+///
+/// Reports the session's expiry, budget and difficulty so clients can renew
+/// it (by solving a new challenge) before it runs out.
 pub async fn check_status<R>(
     State(state): State<PowAppState<R>>,
     headers: HeaderMap,
-    axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
+    ClientIp(client_ip): ClientIp,
 ) -> PowResult<Json<StatusResponse>>
 where
     R: ChallengeRepository
         + PowSessionRepository
-        + RateLimitRepository
+        + RateLimitStore
+        + ActivityRepository
+        + TelemetryRepository
+        + BanRepository
         + Clone
         + Send
         + Sync
         + 'static,
 {
-    let client_ip = extract_client_ip(&headers, Some(addr.ip()));
     let fingerprint = extract_fingerprint(&headers, client_ip)?;
 
     let token = extract_session_cookie(&headers, &state.config.session_cookie_name);
 
     let use_case = CheckPowSessionUseCase::new(state.repo.clone(), state.config.clone());
 
-    let passed = if let Some(token) = token {
-        use_case.check(&token, &fingerprint).await?
-    } else {
-        false
+    let session = match token {
+        Some(token) => match use_case.session(&token, &fingerprint).await {
+            Err(PowError::SessionFingerprintMismatch) => {
+                BanGuard::new(state.repo.clone(), state.config.clone())
+                    .record_offense(OffenseKind::FingerprintMismatch, &fingerprint)
+                    .await;
+                return Err(PowError::SessionFingerprintMismatch);
+            }
+            result => result?,
+        },
+        None => None,
+    };
+
+    let Some(session) = session else {
+        return Ok(Json(StatusResponse::default()));
     };
 
-    Ok(Json(StatusResponse { passed }))
+    Ok(Json(StatusResponse {
+        passed: !session.is_exhausted(),
+        remaining_requests: session.remaining_requests,
+        expires_at_ms: Some(session.expires_at_ms),
+        remaining_ttl_ms: Some(session.remaining_ttl_ms(Utc::now().timestamp_millis())),
+        difficulty_bits: (session.difficulty_bits > 0).then_some(session.difficulty_bits),
+        puzzle_count: (session.difficulty_bits > 0).then_some(session.puzzle_count),
+    }))
 }
 
 /// POST /api/pow/logout
@@ -136,7 +259,10 @@ pub async fn logout<R>(
 where
     R: ChallengeRepository
         + PowSessionRepository
-        + RateLimitRepository
+        + RateLimitStore
+        + ActivityRepository
+        + TelemetryRepository
+        + BanRepository
         + Clone
         + Send
         + Sync
@@ -157,6 +283,51 @@ where
     Ok((StatusCode::NO_CONTENT, [(header::SET_COOKIE, cookie)]))
 }
 
+/// GET /api/admin/pow/bans
+pub async fn list_bans<R>(State(state): State<PowAppState<R>>) -> PowResult<Json<Vec<BanResponse>>>
+where
+    R: ChallengeRepository
+        + PowSessionRepository
+        + RateLimitStore
+        + ActivityRepository
+        + TelemetryRepository
+        + BanRepository
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    let use_case = ListBansUseCase::new(state.repo.clone());
+
+    let bans = use_case.execute().await?;
+
+    Ok(Json(bans.into_iter().map(BanResponse::from).collect()))
+}
+
+/// POST /api/admin/pow/bans/{ban_id}/lift
+pub async fn lift_ban<R>(
+    State(state): State<PowAppState<R>>,
+    Path(ban_id): Path<Uuid>,
+) -> PowResult<StatusCode>
+where
+    R: ChallengeRepository
+        + PowSessionRepository
+        + RateLimitStore
+        + ActivityRepository
+        + TelemetryRepository
+        + BanRepository
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    let use_case = LiftBanUseCase::new(state.repo.clone());
+
+    use_case.execute(ban_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 fn build_session_cookie(config: &PowConfig, token: &str) -> String {
     let mut parts = vec![
         format!("{}={}", config.session_cookie_name, token),
@@ -1,16 +1,38 @@
 //! PoW Middleware
 
+use crate::application::bypass::BypassReason;
 use crate::application::check_session::CheckPowSessionUseCase;
 use crate::application::config::PowConfig;
-use crate::domain::repository::{ChallengeRepository, RateLimitRepository, PowSessionRepository};
+use crate::domain::entities::PowSession;
+use crate::domain::repository::PowSessionRepository;
+use crate::domain::value_objects::PowAction;
 use crate::error::PowError;
+use kernel::error::app_error::AppError;
 use axum::body::Body;
-use axum::http::{Request, StatusCode};
+use axum::extract::State;
+use axum::http::{HeaderValue, Request, StatusCode};
 use axum::middleware::Next;
 use axum::response::{IntoResponse, Response};
-use platform::client::{extract_client_ip, extract_fingerprint};
+use platform::client::{ClientIp, ClientRole, extract_fingerprint};
+use platform::crypto::{constant_time_eq, sha256};
+use std::net::IpAddr;
 use std::sync::Arc;
 
+/// Request header carrying an action-scoped proof
+pub const POW_PROOF_HEADER: &str = "x-pow-proof";
+
+/// Request header carrying a PoW bypass token
+pub const POW_BYPASS_HEADER: &str = "x-pow-bypass";
+
+/// Response header with the requests left in a budgeted session
+pub const POW_REMAINING_HEADER: &str = "x-pow-remaining";
+
+/// Response header pointing to where a new challenge can be fetched
+pub const POW_CHALLENGE_HEADER: &str = "x-pow-challenge";
+
+/// Largest request body hashed for payload-bound proofs
+const MAX_PAYLOAD_BYTES: usize = 1024 * 1024;
+
 /// Middleware state
 #[derive(Clone)]
 pub struct PowMiddlewareState<R>
@@ -22,52 +44,266 @@ where
 }
 
 /// Middleware that requires a valid PoW session
+///
+/// Each passing request spends one request of the session budget (if the
+/// session has one). The budget left is returned in `X-PoW-Remaining`.
+///
+/// Clients matching `PowConfig.bypass` are let through without a session.
+/// Role bypasses need an auth middleware (which sets `ClientRole`) to run
+/// before this one.
+///
+/// See [`PowGuardLayer`](crate::presentation::guard_layer::PowGuardLayer)
+/// for a layer that does not need a hand-built state.
 pub async fn require_pow_session<R>(
     state: PowMiddlewareState<R>,
     req: Request<Body>,
     next: Next,
 ) -> Result<Response, Response>
 where
-    R: ChallengeRepository + PowSessionRepository + RateLimitRepository + Clone + Send + Sync + 'static,
+    R: PowSessionRepository + Clone + Send + Sync + 'static,
 {
-    let headers = req.headers();
+    match check_request(&state.repo, &state.config, None, true, req).await {
+        Ok((req, session)) => Ok(forward(next.run(req).await, session.as_ref())),
+        Err(Rejection::PowRequired) => Err(pow_required(&state.config, None)),
+        Err(Rejection::Response(response)) => Err(response),
+    }
+}
 
-    let client_ip = req
-        .extensions()
-        .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
-        .map(|info| info.0.ip());
+/// Action guard state
+#[derive(Clone)]
+pub struct PowActionGuardState<R>
+where
+    R: PowSessionRepository + Clone + Send + Sync + 'static,
+{
+    pub repo: Arc<R>,
+    pub config: Arc<PowConfig>,
+    /// Action the guarded routes require a proof for
+    pub action: PowAction,
+    /// Also let requests with a valid general PoW session through
+    pub accept_session: bool,
+}
 
-    let client_ip = extract_client_ip(headers, client_ip);
+impl<R> PowActionGuardState<R>
+where
+    R: PowSessionRepository + Clone + Send + Sync + 'static,
+{
+    /// Guard that only accepts a proof for `action`
+    pub fn new(repo: Arc<R>, config: Arc<PowConfig>, action: PowAction) -> Self {
+        Self {
+            repo,
+            config,
+            action,
+            accept_session: false,
+        }
+    }
 
-    let fingerprint = match extract_fingerprint(headers, client_ip) {
-        Ok(fp) => fp,
-        Err(e) => return Err(PowError::from(e).into_response()),
-    };
+    /// Also accept a general PoW session (spending one request of its budget)
+    pub fn or_session(mut self) -> Self {
+        self.accept_session = true;
+        self
+    }
+}
+
+/// Middleware that requires a single-use proof for the route's action
+///
+/// The proof is read from the `X-PoW-Proof` header and redeemed (deleted)
+/// before the handler runs. If it was bound to a payload hash, the request
+/// body must hash to the same value. Without a proof, a general PoW session
+/// cookie is accepted when the guard allows it.
+///
+/// ## Usage
+/// ```rust,ignore
+/// let guard = PowActionGuardState::new(repo, config, PowAction::new("signup").unwrap());
+/// let router = router.route_layer(from_fn_with_state(guard, require_pow_action::<PgPowRepository>));
+/// ```
+pub async fn require_pow_action<R>(
+    State(state): State<PowActionGuardState<R>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response
+where
+    R: PowSessionRepository + Clone + Send + Sync + 'static,
+{
+    let action = Some(&state.action);
+    match check_request(&state.repo, &state.config, action, state.accept_session, req).await {
+        Ok((req, session)) => forward(next.run(req).await, session.as_ref()),
+        Err(Rejection::PowRequired) => pow_required(&state.config, action),
+        Err(Rejection::Response(response)) => response,
+    }
+}
+
+/// Why a PoW guard rejected a request
+pub(crate) enum Rejection {
+    /// No usable session or proof (rendered by the caller)
+    PowRequired,
+    /// Any other failure
+    Response(Response),
+}
 
-    let token = platform::cookie::extract_cookie(headers, &state.config.session_cookie_name);
+impl Rejection {
+    /// Empty-bodied response with `status`
+    fn status(status: StatusCode) -> Self {
+        Rejection::Response((status, ()).into_response())
+    }
+}
+
+/// Check a request against the PoW requirement shared by all guards
+///
+/// Without an `action`, a general PoW session is required (or a bypass).
+/// With one, a proof for that action is required, or a general session if
+/// `accept_session` is set. On success, returns the request to forward
+/// (with its body buffered for payload-bound proofs) and the session whose
+/// budget was spent.
+pub(crate) async fn check_request<R>(
+    repo: &Arc<R>,
+    config: &Arc<PowConfig>,
+    action: Option<&PowAction>,
+    accept_session: bool,
+    req: Request<Body>,
+) -> Result<(Request<Body>, Option<PowSession>), Rejection>
+where
+    R: PowSessionRepository + Clone + Send + Sync + 'static,
+{
+    let ClientIp(client_ip) = ClientIp::from_extensions(req.extensions());
+
+    if action.is_none()
+        && let Some(reason) = bypass_reason(config, &req, client_ip)
+    {
+        tracing::info!(
+            reason = reason.kind(),
+            detail = %reason,
+            path = %req.uri().path(),
+            "PoW session requirement bypassed"
+        );
+        return Ok((req, None));
+    }
 
-    let use_case = CheckPowSessionUseCase::new(state.repo.clone(), state.config.clone());
+    let fingerprint = extract_fingerprint(req.headers(), client_ip)
+        .map_err(|e| Rejection::Response(PowError::from(e).into_response()))?;
 
-    let session_valid = if let Some(token) = token {
-        match use_case.check(&token, &fingerprint).await {
-            Ok(valid) => valid,
+    let use_case = CheckPowSessionUseCase::new(repo.clone(), config.clone());
+
+    let proof_token = action.and_then(|_| {
+        req.headers()
+            .get(POW_PROOF_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_owned)
+    });
+
+    let (Some(action), Some(token)) = (action, proof_token) else {
+        let session_token = (action.is_none() || accept_session)
+            .then(|| platform::cookie::extract_cookie(req.headers(), &config.session_cookie_name))
+            .flatten();
+        let Some(session_token) = session_token else {
+            tracing::debug!(action = action.map(PowAction::as_str), "No PoW session or proof");
+            return Err(Rejection::PowRequired);
+        };
+
+        return match use_case.authorize(&session_token, &fingerprint).await {
+            Ok(Some(session)) => Ok((req, Some(session))),
+            Ok(None) => Err(Rejection::PowRequired),
             Err(e) => {
                 tracing::error!(error = %e, "Error checking PoW session");
-                return Err((StatusCode::INTERNAL_SERVER_ERROR, ()).into_response());
+                Err(Rejection::status(StatusCode::INTERNAL_SERVER_ERROR))
             }
+        };
+    };
+
+    let proof = match use_case.redeem(&token, &fingerprint, action).await {
+        Ok(Some(proof)) => proof,
+        Ok(None) => return Err(Rejection::PowRequired),
+        Err(e) => {
+            tracing::error!(error = %e, "Error redeeming PoW proof");
+            return Err(Rejection::status(StatusCode::INTERNAL_SERVER_ERROR));
         }
-    } else {
-        tracing::debug!("No PoW session cookie");
-        false
     };
 
-    if !session_valid {
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            [("X-PoW-Required", "true")],
-        )
-            .into_response());
+    let Some(expected) = proof.payload_hash else {
+        return Ok((req, None));
+    };
+
+    // Payload-bound proof: the body must match the hash the challenge was issued for
+    let (parts, body) = req.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_PAYLOAD_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Err(Rejection::status(StatusCode::PAYLOAD_TOO_LARGE)),
+    };
+    if !constant_time_eq(&sha256(&bytes), &expected) {
+        tracing::warn!(
+            action = %action,
+            pow_session_id = %proof.id,
+            "PoW proof payload mismatch"
+        );
+        return Err(Rejection::PowRequired);
     }
 
-    Ok(next.run(req).await)
+    Ok((Request::from_parts(parts, Body::from(bytes)), None))
+}
+
+/// The "PoW required" response shared by all PoW guards
+///
+/// 401 with `X-PoW-Required: true`, the challenge endpoint in
+/// `X-PoW-Challenge` and, for action guards, the action in `X-PoW-Action`.
+pub fn pow_required(config: &PowConfig, action: Option<&PowAction>) -> Response {
+    let mut response = (StatusCode::UNAUTHORIZED, ()).into_response();
+    set_pow_required_headers(&mut response, config, action);
+    response
+}
+
+/// [`pow_required`] with an RFC 7807 problem details body
+///
+/// For API clients that do not inspect headers.
+pub fn pow_required_json(config: &PowConfig, action: Option<&PowAction>) -> Response {
+    let detail = match action {
+        Some(action) => format!("Proof of work required for {action}"),
+        None => "Proof of work required".to_string(),
+    };
+    let mut response = AppError::unauthorized(detail)
+        .with_action(format!("Solve a challenge from {}", config.challenge_url))
+        .into_response();
+    set_pow_required_headers(&mut response, config, action);
+    response
+}
+
+fn set_pow_required_headers(
+    response: &mut Response,
+    config: &PowConfig,
+    action: Option<&PowAction>,
+) {
+    let headers = response.headers_mut();
+    headers.insert("X-PoW-Required", HeaderValue::from_static("true"));
+    if let Ok(value) = HeaderValue::from_str(&config.challenge_url) {
+        headers.insert(POW_CHALLENGE_HEADER, value);
+    }
+    if let Some(value) = action.and_then(|a| HeaderValue::from_str(a.as_str()).ok()) {
+        headers.insert("X-PoW-Action", value);
+    }
+}
+
+/// Bypass that applies to the request, if any
+fn bypass_reason(
+    config: &PowConfig,
+    req: &Request<Body>,
+    client_ip: Option<IpAddr>,
+) -> Option<BypassReason> {
+    if !config.bypass.is_enabled() {
+        return None;
+    }
+
+    let token = req
+        .headers()
+        .get(POW_BYPASS_HEADER)
+        .and_then(|v| v.to_str().ok());
+    let role = req.extensions().get::<ClientRole>().map(|r| r.0.as_str());
+    config.bypass.check(client_ip, token, role)
+}
+
+/// Add `X-PoW-Remaining` for budgeted sessions to the handler's response
+pub(crate) fn forward(mut response: Response, session: Option<&PowSession>) -> Response {
+    if let Some(remaining) = session.and_then(|s| s.remaining_requests) {
+        response
+            .headers_mut()
+            .insert(POW_REMAINING_HEADER, HeaderValue::from(remaining));
+    }
+    response
 }
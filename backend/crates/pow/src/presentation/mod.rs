@@ -4,5 +4,6 @@
 
 pub mod handlers;
 pub mod dto;
+pub mod guard_layer;
 pub mod middleware;
 pub mod router;
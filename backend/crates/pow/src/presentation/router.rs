@@ -1,13 +1,17 @@
 //! PoW Router
 
 use crate::application::config::PowConfig;
-use crate::domain::repository::{ChallengeRepository, PowSessionRepository, RateLimitRepository};
+use crate::domain::repository::{
+    ActivityRepository, BanRepository, ChallengeRepository, PowSessionRepository,
+    TelemetryRepository,
+};
 use crate::infra::postgres::PgPowRepository;
 use crate::presentation::handlers::{self, PowAppState};
 use axum::{
     Router,
     routing::{get, post},
 };
+use platform::rate_limit::RateLimitStore;
 use std::sync::Arc;
 
 /// Create the PoW router with PostgreSQL repository
@@ -31,12 +35,33 @@ pub fn pow_router(repo: PgPowRepository, config: PowConfig) -> Router {
         .with_state(state)
 }
 
+/// Create the PoW admin router (ban list and lifting)
+///
+/// Must be nested behind an admin-only guard.
+pub fn pow_admin_router(repo: PgPowRepository, config: PowConfig) -> Router {
+    let state = PowAppState {
+        repo: Arc::new(repo),
+        config: Arc::new(config),
+    };
+
+    Router::new()
+        .route("/bans", get(handlers::list_bans::<PgPowRepository>))
+        .route(
+            "/bans/{ban_id}/lift",
+            post(handlers::lift_ban::<PgPowRepository>),
+        )
+        .with_state(state)
+}
+
 /// Create a generic PoW router for any repository implementation
 pub fn pow_router_generic<R>(repo: R, config: PowConfig) -> Router
 where
     R: ChallengeRepository
         + PowSessionRepository
-        + RateLimitRepository
+        + RateLimitStore
+        + ActivityRepository
+        + TelemetryRepository
+        + BanRepository
         + Clone
         + Send
         + Sync
@@ -47,7 +47,7 @@ mod crypto_tests {
         let challenge = vec![0u8; 32];
         let nonce: u32 = 0x01020304;
 
-        let hash = compute_pow_hash(&challenge, nonce);
+        let hash = compute_pow_hash(&challenge, Nonce::V1(nonce));
 
         let mut data = vec![0u8; 32];
         data.extend_from_slice(&[0x01, 0x02, 0x03, 0x04]);
@@ -107,7 +107,7 @@ mod crypto_tests {
 
         let mut nonce = 0u32;
         loop {
-            if verify_pow(&challenge, nonce, difficulty) {
+            if verify_pow(&challenge, Nonce::V1(nonce), difficulty) {
                 break;
             }
             nonce += 1;
@@ -116,7 +116,7 @@ mod crypto_tests {
             }
         }
 
-        assert!(verify_pow(&challenge, nonce, difficulty));
+        assert!(verify_pow(&challenge, Nonce::V1(nonce), difficulty));
     }
 
     #[test]
@@ -148,30 +148,76 @@ mod crypto_tests {
 #[cfg(test)]
 mod config_tests {
     use crate::application::config::*;
+    use crate::domain::difficulty::AdaptiveDifficulty;
+    use crate::domain::value_objects::ClientNetworkKey;
     use std::time::Duration;
 
     #[test]
     fn test_default_config() {
         let config = PowConfig::default();
 
+        assert_eq!(config.challenge_mode, ChallengeMode::Stored);
         assert_eq!(config.challenge_bytes_len, 32);
         assert_eq!(config.difficulty_bits, 23);
         assert_eq!(config.challenge_ttl, Duration::from_secs(120));
         assert_eq!(config.session_ttl, Duration::from_secs(3600));
+        assert_eq!(config.session_request_budget, None);
         assert_eq!(config.rate_limit_max_requests, 10);
         assert_eq!(config.rate_limit_window, Duration::from_secs(60));
+        assert_eq!(config.rate_limit().max_requests, 10);
+        assert_eq!(config.rate_limit().window_ms(), 60_000);
+        assert_eq!(config.network_rate_limit().unwrap().max_requests, 60);
+        assert_eq!(config.client_network, ClientNetworkKey::default());
+        assert!(config.ban_policy.is_some());
+        assert!(!config.difficulty_policy.observes_activity());
+        assert!(config.global_issue_counter().is_none());
         assert_eq!(config.session_cookie_name, "pow_session");
         assert!(config.cookie_secure);
         assert_eq!(config.cookie_same_site, SameSite::Lax);
     }
 
+    #[test]
+    fn test_global_issue_counter() {
+        let config = PowConfig {
+            difficulty_policy: std::sync::Arc::new(AdaptiveDifficulty::default()),
+            ..PowConfig::default()
+        };
+        let counter = config.global_issue_counter().unwrap();
+        assert_eq!(counter.max_requests, 200 * 64);
+        assert_eq!(counter.window, config.difficulty_window);
+
+        // Clones of the config (one per request) share the instance's counter
+        let shared = config.clone();
+        config
+            .issue_counter
+            .check_at("pow:challenge:global", &counter, 0);
+        assert_eq!(shared.issue_counter.len(), 1);
+
+        // GCRA needs at least 1ms per request
+        let config = PowConfig {
+            difficulty_window: Duration::from_millis(100),
+            ..config
+        };
+        assert_eq!(config.global_issue_counter().unwrap().max_requests, 100);
+    }
+
     #[test]
     fn test_with_random_secret() {
         let config1 = PowConfig::with_random_secret();
         let config2 = PowConfig::with_random_secret();
 
-        assert_ne!(config1.session_secret, config2.session_secret);
-        assert!(config1.session_secret.iter().any(|&b| b != 0));
+        assert_ne!(
+            config1.session_keys.current().secret,
+            config2.session_keys.current().secret
+        );
+        assert!(
+            config1
+                .session_keys
+                .current()
+                .secret
+                .iter()
+                .any(|&b| b != 0)
+        );
     }
 
     #[test]
@@ -179,7 +225,7 @@ mod config_tests {
         let config = PowConfig::development();
 
         assert!(!config.cookie_secure);
-        assert!(config.session_secret.iter().any(|&b| b != 0));
+        assert!(config.session_keys.current().secret.iter().any(|&b| b != 0));
     }
 
     #[test]
@@ -192,10 +238,24 @@ mod config_tests {
         assert_ne!(lax, none);
         assert_ne!(strict, none);
     }
+
+    #[test]
+    fn test_action_policy() {
+        let signup = crate::domain::value_objects::PowAction::new("signup").unwrap();
+        let signin = crate::domain::value_objects::PowAction::new("signin").unwrap();
+        let config = PowConfig::default().with_action(signup.clone(), ActionPolicy::new(25));
+
+        let policy = config.action_policy(&signup).unwrap();
+        assert_eq!(policy.difficulty_bits, 25);
+        assert_eq!(policy.proof_ttl, Duration::from_secs(300));
+        assert!(!policy.require_payload_hash);
+        assert!(config.action_policy(&signin).is_none());
+    }
 }
 
 #[cfg(test)]
 mod models_tests {
+    use crate::domain::services::ProtocolVersion;
     use crate::presentation::dto::*;
 
     #[test]
@@ -203,8 +263,14 @@ mod models_tests {
         let response = ChallengeResponse {
             pow_challenge_id: uuid::Uuid::nil(),
             pow_challenge_b64: "YWJjZA==".to_string(),
+            pow_protocol_version: 1,
+            pow_algorithm: "sha256".to_string(),
+            pow_algorithm_params: None,
+            pow_puzzle_count: 1,
             pow_difficulty_bits: 18,
             pow_expires_at_ms: 1234567890000,
+            pow_action: None,
+            pow_challenge_token: None,
         };
 
         let json = serde_json::to_string(&response).unwrap();
@@ -212,6 +278,40 @@ mod models_tests {
         assert!(json.contains("powChallengeB64"));
         assert!(json.contains("powDifficultyBits"));
         assert!(json.contains("powExpiresAtMs"));
+        assert!(json.contains(r#""powPuzzleCount":1"#));
+        assert!(json.contains(r#""powProtocolVersion":1"#));
+        assert!(json.contains(r#""powAlgorithm":"sha256""#));
+        assert!(!json.contains("powAlgorithmParams"));
+        assert!(!json.contains("powChallengeToken"));
+        assert!(!json.contains("powAction"));
+    }
+
+    #[test]
+    fn test_challenge_response_with_algorithm_params() {
+        let response = ChallengeResponse {
+            pow_challenge_id: uuid::Uuid::nil(),
+            pow_challenge_b64: "YWJjZA==".to_string(),
+            pow_protocol_version: 1,
+            pow_algorithm: "argon2id".to_string(),
+            pow_algorithm_params: Some(PowAlgorithmParams {
+                memory_kib: 4096,
+                iterations: 1,
+                parallelism: 1,
+            }),
+            pow_puzzle_count: 1,
+            pow_difficulty_bits: 8,
+            pow_expires_at_ms: 1234567890000,
+            pow_action: None,
+            pow_challenge_token: None,
+        };
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains(r#""powAlgorithm":"argon2id""#));
+        assert!(
+            json.contains(
+                r#""powAlgorithmParams":{"memoryKib":4096,"iterations":1,"parallelism":1}"#
+            )
+        );
     }
 
     #[test]
@@ -220,7 +320,8 @@ mod models_tests {
         let request: SubmitRequest = serde_json::from_str(json).unwrap();
 
         assert_eq!(request.challenge_id, uuid::Uuid::nil());
-        assert_eq!(request.nonce_u32, 12345);
+        assert_eq!(request.nonce_u32, Some(12345));
+        assert_eq!(request.nonces(), Some(vec![12345]));
         assert!(request.elapsed_ms.is_none());
         assert!(request.total_hashes.is_none());
     }
@@ -231,31 +332,190 @@ mod models_tests {
         let request: SubmitRequest = serde_json::from_str(json).unwrap();
 
         assert_eq!(request.challenge_id, uuid::Uuid::nil());
-        assert_eq!(request.nonce_u32, 12345);
+        assert_eq!(request.nonce_u32, Some(12345));
         assert_eq!(request.elapsed_ms, Some(5000));
         assert_eq!(request.total_hashes, Some(1000000));
     }
 
+    #[test]
+    fn test_submit_request_multi_puzzle() {
+        let json = r#"{"challengeId":"00000000-0000-0000-0000-000000000000","noncesU32":[1,2,3]}"#;
+        let request: SubmitRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.nonces(), Some(vec![1, 2, 3]));
+
+        let json = r#"{"challengeId":"00000000-0000-0000-0000-000000000000"}"#;
+        let request: SubmitRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.nonces(), None);
+    }
+
+    #[test]
+    fn test_submit_request_serialization_skips_absent_fields() {
+        let request = SubmitRequest {
+            challenge_id: uuid::Uuid::nil(),
+            challenge_token: None,
+            protocol_version: None,
+            nonce_u32: None,
+            nonces_u32: Some(vec![1, 2]),
+            nonces_u64: None,
+            elapsed_ms: Some(10),
+            total_hashes: None,
+        };
+
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(
+            json,
+            r#"{"challengeId":"00000000-0000-0000-0000-000000000000","noncesU32":[1,2],"elapsedMs":10}"#
+        );
+        let parsed: SubmitRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.nonces(), Some(vec![1, 2]));
+    }
+
+    #[test]
+    fn test_submit_request_v2_nonces_are_strings() {
+        let request = SubmitRequest {
+            challenge_id: uuid::Uuid::nil(),
+            challenge_token: None,
+            protocol_version: Some(2),
+            nonce_u32: None,
+            nonces_u32: None,
+            nonces_u64: Some(vec![u64::MAX, 7]),
+            elapsed_ms: None,
+            total_hashes: None,
+        };
+
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(
+            json,
+            r#"{"challengeId":"00000000-0000-0000-0000-000000000000","protocolVersion":2,"noncesU64":["18446744073709551615","7"]}"#
+        );
+        let parsed: SubmitRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.protocol_version(), Some(ProtocolVersion::V2));
+        assert_eq!(parsed.nonces(), Some(vec![u64::MAX, 7]));
+
+        // v2 ignores the v1 fields, and v1 ignores the v2 field
+        let json = r#"{"challengeId":"00000000-0000-0000-0000-000000000000","protocolVersion":2,"noncesU32":[1]}"#;
+        let request: SubmitRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.nonces(), None);
+        let json = r#"{"challengeId":"00000000-0000-0000-0000-000000000000","noncesU64":["1"]}"#;
+        let request: SubmitRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.protocol_version(), Some(ProtocolVersion::V1));
+        assert_eq!(request.nonces(), None);
+
+        let json = r#"{"challengeId":"00000000-0000-0000-0000-000000000000","protocolVersion":9}"#;
+        let request: SubmitRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.protocol_version(), None);
+
+        let json = r#"{"challengeId":"00000000-0000-0000-0000-000000000000","protocolVersion":2,"noncesU64":["-1"]}"#;
+        assert!(serde_json::from_str::<SubmitRequest>(json).is_err());
+    }
+
+    #[test]
+    fn test_challenge_response_deserialization() {
+        let json = r#"{"powChallengeId":"00000000-0000-0000-0000-000000000000","powChallengeB64":"YWJjZA==","powAlgorithm":"sha256","powPuzzleCount":2,"powDifficultyBits":17,"powExpiresAtMs":1}"#;
+        let response: ChallengeResponse = serde_json::from_str(json).unwrap();
+
+        assert_eq!(response.pow_puzzle_count, 2);
+        assert_eq!(response.pow_difficulty_bits, 17);
+        // Servers predating the field speak v1
+        assert_eq!(response.pow_protocol_version, 1);
+        assert!(response.pow_algorithm_params.is_none());
+        assert!(response.pow_challenge_token.is_none());
+    }
+
+    #[test]
+    fn test_challenge_query_deserialization() {
+        let query: ChallengeQuery =
+            serde_json::from_str(r#"{"action":"comment:create","payloadHash":"abc"}"#).unwrap();
+        assert_eq!(query.action.as_deref(), Some("comment:create"));
+        assert_eq!(query.payload_hash.as_deref(), Some("abc"));
+
+        let query: ChallengeQuery = serde_json::from_str("{}").unwrap();
+        assert!(query.action.is_none());
+    }
+
+    #[test]
+    fn test_proof_response_serialization() {
+        let response = ProofResponse {
+            pow_proof: "token".to_string(),
+            pow_action: "signup".to_string(),
+            pow_expires_at_ms: 1234567890000,
+        };
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains(r#""powProof":"token""#));
+        assert!(json.contains(r#""powAction":"signup""#));
+    }
+
     #[test]
     fn test_status_response_serialization() {
-        let response = StatusResponse { passed: true };
+        let json = serde_json::to_string(&StatusResponse::default()).unwrap();
+        assert_eq!(json, r#"{"passed":false}"#);
+
+        let response = StatusResponse {
+            passed: true,
+            remaining_requests: None,
+            expires_at_ms: Some(1_000),
+            remaining_ttl_ms: Some(500),
+            difficulty_bits: Some(20),
+            puzzle_count: Some(1),
+        };
         let json = serde_json::to_string(&response).unwrap();
         assert!(json.contains(r#""passed":true"#));
+        assert!(!json.contains("remainingRequests"));
+        assert!(json.contains(r#""expiresAtMs":1000"#));
+        assert!(json.contains(r#""remainingTtlMs":500"#));
+        assert!(json.contains(r#""difficultyBits":20"#));
+        assert!(json.contains(r#""puzzleCount":1"#));
 
-        let response = StatusResponse { passed: false };
+        let response = StatusResponse {
+            passed: false,
+            remaining_requests: Some(0),
+            ..Default::default()
+        };
         let json = serde_json::to_string(&response).unwrap();
         assert!(json.contains(r#""passed":false"#));
+        assert!(json.contains(r#""remainingRequests":0"#));
+    }
+
+    #[test]
+    fn test_ban_response_serialization() {
+        use crate::domain::ban::{Ban, BanSubject, OffenseKind};
+        use std::time::Duration;
+
+        let ban = Ban::new(
+            BanSubject::Fingerprint(vec![0xab; 32]),
+            OffenseKind::InvalidNonce,
+            20,
+            2,
+            Duration::from_secs(600),
+            1_000,
+        );
+        let json = serde_json::to_string(&BanResponse::from(ban)).unwrap();
+        assert!(json.contains(r#""subjectKind":"fingerprint""#));
+        assert!(json.contains(&format!(r#""subject":"{}""#, "ab".repeat(32))));
+        assert!(json.contains(r#""reason":"invalid_nonce""#));
+        assert!(json.contains(r#""strike":2"#));
+        assert!(json.contains(r#""expiresAtMs":601000"#));
     }
 }
 
 #[cfg(test)]
 mod domain_tests {
+    use crate::domain::algorithm::PowAlgorithm;
     use crate::domain::entities::*;
+    use crate::domain::services::{Nonce, ProtocolVersion, verify_pow};
     use crate::domain::value_objects::*;
 
     #[test]
     fn test_challenge_creation() {
-        let challenge = Challenge::new(vec![0u8; 32], 18, 120_000, vec![0u8; 32], None);
+        let challenge = Challenge::new(
+            vec![0u8; 32],
+            PowAlgorithm::Sha256,
+            18,
+            120_000,
+            vec![0u8; 32],
+            None,
+        );
 
         assert_eq!(challenge.challenge_bytes.len(), 32);
         assert_eq!(challenge.difficulty_bits, 18);
@@ -264,12 +524,158 @@ mod domain_tests {
 
     #[test]
     fn test_session_creation() {
-        let challenge = Challenge::new(vec![0u8; 32], 18, 120_000, vec![0u8; 32], None);
+        let challenge = Challenge::new(
+            vec![0u8; 32],
+            PowAlgorithm::Sha256,
+            18,
+            120_000,
+            vec![0u8; 32],
+            None,
+        );
 
-        let session = PowSession::new(&challenge, 3600_000);
+        let session = PowSession::new(&challenge, 3_600_000);
 
         assert_eq!(session.challenge_id, challenge.id);
         assert!(!session.is_expired());
+        assert!(session.is_general());
+        assert_eq!(session.remaining_requests, None);
+        assert!(!session.is_exhausted());
+    }
+
+    #[test]
+    fn test_session_records_difficulty() {
+        let challenge = Challenge::new(
+            vec![0u8; 32],
+            PowAlgorithm::Sha256,
+            18,
+            120_000,
+            vec![0u8; 32],
+            None,
+        )
+        .with_puzzle_count(4);
+
+        let session = PowSession::new(&challenge, 3_600_000);
+        assert_eq!(session.difficulty_bits, 18);
+        assert_eq!(session.puzzle_count, 4);
+
+        let now_ms = session.expires_at_ms - 1_000;
+        assert_eq!(session.remaining_ttl_ms(now_ms), 1_000);
+        assert_eq!(session.remaining_ttl_ms(session.expires_at_ms + 1), 0);
+    }
+
+    #[test]
+    fn test_session_request_budget() {
+        let challenge = Challenge::new(
+            vec![0u8; 32],
+            PowAlgorithm::Sha256,
+            18,
+            120_000,
+            vec![0u8; 32],
+            None,
+        );
+
+        let session = PowSession::new(&challenge, 3_600_000).with_request_budget(Some(100));
+        assert_eq!(session.remaining_requests, Some(100));
+        assert!(!session.is_exhausted());
+
+        let session = session.with_request_budget(Some(0));
+        assert!(session.is_exhausted());
+    }
+
+    #[test]
+    fn test_action_scoped_session() {
+        let action = PowAction::new("signup").unwrap();
+        let challenge = Challenge::new(
+            vec![0u8; 32],
+            PowAlgorithm::Sha256,
+            18,
+            120_000,
+            vec![0u8; 32],
+            None,
+        )
+        .with_action(action.clone(), Some(vec![1u8; 32]));
+
+        let session = PowSession::new(&challenge, 300_000);
+
+        assert_eq!(session.action, Some(action));
+        assert_eq!(session.payload_hash, Some(vec![1u8; 32]));
+        assert!(!session.is_general());
+    }
+
+    #[test]
+    fn test_pow_action_validation() {
+        assert_eq!(PowAction::new("signup").unwrap().as_str(), "signup");
+        assert!(PowAction::new("comment:create").is_some());
+        assert!(PowAction::new("").is_none());
+        assert!(PowAction::new("SignUp").is_none());
+        assert!(PowAction::new("sign up").is_none());
+        assert!(PowAction::new(&"a".repeat(65)).is_none());
+    }
+
+    #[test]
+    fn test_multi_puzzle_challenge_verify() {
+        let challenge = Challenge::new(
+            vec![5u8; 32],
+            PowAlgorithm::Sha256,
+            6,
+            120_000,
+            vec![0u8; 32],
+            None,
+        )
+        .with_puzzle_count(3);
+
+        let nonces: Vec<u64> = (0..3)
+            .map(|index| {
+                let bytes = crate::domain::services::sub_challenge(
+                    &challenge.challenge_bytes,
+                    index,
+                    challenge.puzzle_count,
+                );
+                (0..1_000_000u32)
+                    .find(|&n| crate::domain::services::verify_pow(&bytes, Nonce::V1(n), 6))
+                    .unwrap() as u64
+            })
+            .collect();
+
+        assert!(challenge.verify(&nonces));
+        assert!(!challenge.verify(&nonces[..2]));
+    }
+
+    #[test]
+    fn test_v2_challenge_verify() {
+        let challenge = Challenge::new(
+            vec![6u8; 32],
+            PowAlgorithm::Sha256,
+            6,
+            120_000,
+            vec![0u8; 32],
+            None,
+        )
+        .with_protocol_version(ProtocolVersion::V2);
+
+        // Search above the 32-bit range
+        let nonce = (u32::MAX as u64 + 1..)
+            .find(|&n| verify_pow(&challenge.challenge_bytes, Nonce::V2(n), 6))
+            .unwrap();
+        assert!(challenge.verify(&[nonce]));
+
+        // A v1 challenge rejects nonces it cannot encode
+        let v1 = Challenge {
+            protocol_version: ProtocolVersion::V1,
+            ..challenge
+        };
+        assert!(!v1.verify(&[nonce]));
+    }
+
+    #[test]
+    fn test_single_puzzle_uses_challenge_bytes() {
+        let bytes = vec![9u8; 32];
+        assert_eq!(crate::domain::services::sub_challenge(&bytes, 0, 1), bytes);
+        assert_ne!(crate::domain::services::sub_challenge(&bytes, 0, 2), bytes);
+        assert_eq!(crate::domain::services::split_difficulty(23, 1), 23);
+        assert_eq!(crate::domain::services::split_difficulty(23, 4), 21);
+        assert_eq!(crate::domain::services::split_difficulty(23, 6), 21);
+        assert_eq!(crate::domain::services::split_difficulty(1, 8), 1);
     }
 
     #[test]
@@ -286,6 +692,312 @@ mod domain_tests {
         let fp = ClientFingerprint::new([0u8; 32], None, None);
         assert_eq!(fp.hash_vec().len(), 32);
     }
+
+    #[test]
+    fn test_activity_kind_as_str() {
+        assert_eq!(ActivityKind::InvalidNonce.as_str(), "invalid_nonce");
+        assert_eq!(ActivityKind::SessionCreated.as_str(), "session_created");
+    }
+}
+
+#[cfg(test)]
+mod ban_guard_tests {
+    use crate::application::ban_guard::BanGuard;
+    use crate::application::config::PowConfig;
+    use crate::application::submit_solution::{SubmitSolutionInput, SubmitSolutionUseCase};
+    use crate::domain::algorithm::PowAlgorithm;
+    use crate::domain::ban::{Ban, BanKeys, BanPolicy, BanSubject, OffenseCounts, OffenseKind};
+    use crate::domain::difficulty::DifficultyInputs;
+    use crate::domain::entities::{ActivityKind, Challenge, PowSession};
+    use crate::domain::repository::{
+        ActivityRepository, BanRepository, ChallengeRepository, PowSessionRepository,
+        TelemetryRepository,
+    };
+    use crate::domain::services::ProtocolVersion;
+    use crate::domain::telemetry::{SolveRollup, SolveSample};
+    use crate::domain::value_objects::{ClientFingerprint, PowAction};
+    use crate::error::{PowError, PowResult};
+    use platform::crypto::sha256;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
+
+    /// In-memory offense log and ban list
+    #[derive(Clone, Default)]
+    struct MemoryBans(Arc<Mutex<(Vec<BanKeys>, Vec<Ban>)>>);
+
+    fn matches(subject: &BanSubject, keys: &BanKeys) -> bool {
+        match subject {
+            BanSubject::Fingerprint(hash) => *hash == keys.client_hash,
+            BanSubject::Network(network) => keys.network.as_ref() == Some(network),
+        }
+    }
+
+    impl BanRepository for MemoryBans {
+        async fn active_ban(&self, keys: &BanKeys, now_ms: i64) -> PowResult<Option<Ban>> {
+            let state = self.0.lock().unwrap();
+            Ok(state
+                .1
+                .iter()
+                .find(|b| b.expires_at_ms > now_ms && matches(&b.subject, keys))
+                .cloned())
+        }
+
+        async fn record_offense(
+            &self,
+            _kind: OffenseKind,
+            keys: &BanKeys,
+            _now_ms: i64,
+            _since_ms: i64,
+        ) -> PowResult<OffenseCounts> {
+            let mut state = self.0.lock().unwrap();
+            state.0.push(keys.clone());
+            let count =
+                |f: &dyn Fn(&BanKeys) -> bool| state.0.iter().filter(|k| f(k)).count() as u32;
+            Ok(OffenseCounts {
+                fingerprint: count(&|k| k.client_hash == keys.client_hash),
+                network: count(&|k| keys.network.is_some() && k.network == keys.network),
+            })
+        }
+
+        async fn count_bans(&self, subject: &BanSubject, _since_ms: i64) -> PowResult<u32> {
+            let state = self.0.lock().unwrap();
+            Ok(state.1.iter().filter(|b| b.subject == *subject).count() as u32)
+        }
+
+        async fn create_ban(&self, ban: &Ban) -> PowResult<()> {
+            let mut state = self.0.lock().unwrap();
+            state.0.retain(|k| !matches(&ban.subject, k));
+            state.1.push(ban.clone());
+            Ok(())
+        }
+
+        async fn list_active_bans(&self, now_ms: i64) -> PowResult<Vec<Ban>> {
+            let state = self.0.lock().unwrap();
+            Ok(state
+                .1
+                .iter()
+                .filter(|b| b.expires_at_ms > now_ms)
+                .cloned()
+                .collect())
+        }
+
+        async fn lift_ban(&self, _ban_id: Uuid, _now_ms: i64) -> PowResult<bool> {
+            Ok(false)
+        }
+    }
+
+    /// In-memory challenge store, bound to fingerprints like the Postgres one
+    #[derive(Clone, Default)]
+    struct MemoryChallenges(Arc<Mutex<HashMap<Uuid, Challenge>>>);
+
+    impl ChallengeRepository for MemoryChallenges {
+        async fn create(&self, challenge: &Challenge) -> PowResult<()> {
+            let mut challenges = self.0.lock().unwrap();
+            challenges.insert(challenge.id, challenge.clone());
+            Ok(())
+        }
+
+        async fn consume(
+            &self,
+            challenge_id: Uuid,
+            fingerprint: &ClientFingerprint,
+        ) -> PowResult<Option<Challenge>> {
+            let mut challenges = self.0.lock().unwrap();
+            match challenges.get(&challenge_id) {
+                Some(c) if c.client_fingerprint_hash != fingerprint.hash_vec() => {
+                    Err(PowError::ChallengeFingerprintMismatch)
+                }
+                _ => Ok(challenges.remove(&challenge_id)),
+            }
+        }
+
+        async fn mark_consumed(&self, _challenge_id: Uuid, _expires_at_ms: i64) -> PowResult<bool> {
+            unreachable!("stored mode")
+        }
+    }
+
+    /// Repositories a rejected submission never reaches
+    struct Unreached;
+
+    impl PowSessionRepository for Unreached {
+        async fn create(&self, _pow_session: &PowSession) -> PowResult<()> {
+            unreachable!()
+        }
+
+        async fn get(
+            &self,
+            _pow_session_id: Uuid,
+            _fingerprint: &ClientFingerprint,
+        ) -> PowResult<Option<PowSession>> {
+            unreachable!()
+        }
+
+        async fn spend_request(
+            &self,
+            _pow_session_id: Uuid,
+            _fingerprint: &ClientFingerprint,
+        ) -> PowResult<Option<PowSession>> {
+            unreachable!()
+        }
+
+        async fn renew(
+            &self,
+            _pow_session_id: Uuid,
+            _fingerprint: &ClientFingerprint,
+            _renewed: &PowSession,
+        ) -> PowResult<Option<PowSession>> {
+            unreachable!()
+        }
+
+        async fn consume(
+            &self,
+            _pow_session_id: Uuid,
+            _fingerprint: &ClientFingerprint,
+            _action: &PowAction,
+        ) -> PowResult<Option<PowSession>> {
+            unreachable!()
+        }
+
+        async fn delete(&self, _pow_session_id: Uuid) -> PowResult<()> {
+            unreachable!()
+        }
+    }
+
+    impl ActivityRepository for Unreached {
+        async fn record(
+            &self,
+            _kind: ActivityKind,
+            _fingerprint: &ClientFingerprint,
+        ) -> PowResult<()> {
+            unreachable!()
+        }
+
+        async fn summarize(
+            &self,
+            _fingerprint: &ClientFingerprint,
+            _window_ms: i64,
+        ) -> PowResult<DifficultyInputs> {
+            unreachable!()
+        }
+    }
+
+    impl TelemetryRepository for Unreached {
+        async fn record_solve(&self, _sample: &SolveSample, _solved_at_ms: i64) -> PowResult<()> {
+            unreachable!()
+        }
+
+        async fn solve_rollups(&self, _since_ms: i64) -> PowResult<Vec<SolveRollup>> {
+            unreachable!()
+        }
+    }
+
+    fn client(ip: &str) -> ClientFingerprint {
+        let user_agent = "Mozilla/5.0 (shared)";
+        ClientFingerprint::new(
+            sha256(user_agent.as_bytes()),
+            Some(ip.parse().unwrap()),
+            Some(user_agent.to_string()),
+        )
+    }
+
+    fn config() -> Arc<PowConfig> {
+        Arc::new(PowConfig {
+            ban_policy: Some(BanPolicy {
+                fingerprint_threshold: 3,
+                network_threshold: 0,
+                ..BanPolicy::default()
+            }),
+            ..PowConfig::development()
+        })
+    }
+
+    fn guard(repo: &MemoryBans) -> BanGuard<MemoryBans> {
+        BanGuard::new(Arc::new(repo.clone()), config())
+    }
+
+    #[tokio::test]
+    async fn test_ban_does_not_hit_other_clients_with_same_user_agent() {
+        let repo = MemoryBans::default();
+        let guard = guard(&repo);
+        let offender = client("203.0.113.7");
+        let bystander = client("198.51.100.7");
+
+        for _ in 0..3 {
+            guard.check(&offender).await.unwrap();
+            guard
+                .record_offense(OffenseKind::InvalidNonce, &offender)
+                .await;
+        }
+
+        assert!(matches!(
+            guard.check(&offender).await,
+            Err(PowError::Banned {
+                subject_kind: "fingerprint",
+                ..
+            })
+        ));
+        assert!(guard.check(&bystander).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_stored_challenge_fingerprint_mismatch_is_an_offense() {
+        let bans = MemoryBans::default();
+        let challenges = MemoryChallenges::default();
+        let use_case = SubmitSolutionUseCase::new(
+            Arc::new(challenges.clone()),
+            Arc::new(Unreached),
+            Arc::new(Unreached),
+            Arc::new(Unreached),
+            Arc::new(bans.clone()),
+            config(),
+        );
+
+        let owner = client("203.0.113.7");
+        let challenge = Challenge::new(
+            vec![0u8; 32],
+            PowAlgorithm::Sha256,
+            1,
+            60_000,
+            owner.hash_vec(),
+            owner.ip,
+        );
+        challenges.create(&challenge).await.unwrap();
+
+        let hijacker = ClientFingerprint::new(
+            sha256(b"curl/8.0"),
+            Some("198.51.100.7".parse().unwrap()),
+            Some("curl/8.0".to_string()),
+        );
+        for _ in 0..3 {
+            let result = use_case
+                .execute(
+                    SubmitSolutionInput {
+                        challenge_id: challenge.id,
+                        challenge_token: None,
+                        protocol_version: ProtocolVersion::V2,
+                        nonces: vec![0],
+                        elapsed_ms: None,
+                        total_hashes: None,
+                        current_session_token: None,
+                    },
+                    hijacker.clone(),
+                )
+                .await;
+            assert!(matches!(result, Err(PowError::ChallengeNotFound)));
+        }
+
+        // Each attempt counted, and the challenge is left to its owner
+        assert!(matches!(
+            guard(&bans).check(&hijacker).await,
+            Err(PowError::Banned {
+                subject_kind: "fingerprint",
+                ..
+            })
+        ));
+        assert!(guard(&bans).check(&owner).await.is_ok());
+        assert!(challenges.0.lock().unwrap().contains_key(&challenge.id));
+    }
 }
 
 #[cfg(test)]
@@ -300,6 +1012,7 @@ mod error_tests {
             (PowError::InvalidNonce, StatusCode::CONFLICT),
             (PowError::ChallengeExpired, StatusCode::GONE),
             (PowError::ChallengeNotFound, StatusCode::GONE),
+            (PowError::ChallengeFingerprintMismatch, StatusCode::GONE),
             (PowError::RateLimitExceeded, StatusCode::TOO_MANY_REQUESTS),
             (PowError::SessionInvalid, StatusCode::UNAUTHORIZED),
             (
@@ -310,6 +1023,25 @@ mod error_tests {
                 PowError::MissingHeader("User-Agent".into()),
                 StatusCode::BAD_REQUEST,
             ),
+            (
+                PowError::InvalidRequest("nonce".into()),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                PowError::Banned {
+                    subject_kind: "fingerprint",
+                    retry_after_secs: 60,
+                },
+                StatusCode::FORBIDDEN,
+            ),
+            (
+                PowError::Banned {
+                    subject_kind: "network",
+                    retry_after_secs: 60,
+                },
+                StatusCode::TOO_MANY_REQUESTS,
+            ),
+            (PowError::BanNotFound, StatusCode::NOT_FOUND),
             (
                 PowError::Internal("test".into()),
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -326,6 +1058,30 @@ mod error_tests {
         }
     }
 
+    #[test]
+    fn test_banned_sets_retry_after() {
+        let response = PowError::Banned {
+            subject_kind: "fingerprint",
+            retry_after_secs: 300,
+        }
+        .into_response();
+        assert_eq!(
+            response
+                .headers()
+                .get(axum::http::header::RETRY_AFTER)
+                .unwrap(),
+            "300"
+        );
+
+        let response = PowError::RateLimitExceeded.into_response();
+        assert!(
+            response
+                .headers()
+                .get(axum::http::header::RETRY_AFTER)
+                .is_none()
+        );
+    }
+
     #[test]
     fn test_error_display() {
         assert!(PowError::InvalidNonce.to_string().contains("nonce"));
@@ -337,3 +1093,384 @@ mod error_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod guard_layer_tests {
+    use crate::application::config::{PowConfig, SessionKey, SessionKeyRing};
+    use crate::domain::algorithm::PowAlgorithm;
+    use crate::domain::entities::{Challenge, PowSession};
+    use crate::domain::repository::PowSessionRepository;
+    use crate::domain::value_objects::{ClientFingerprint, PowAction};
+    use crate::error::PowResult;
+    use crate::presentation::guard_layer::PowGuardLayer;
+    use axum::Router;
+    use axum::body::Body;
+    use axum::http::{Method, Request, StatusCode, header};
+    use axum::routing::get;
+    use platform::client::ClientIp;
+    use platform::crypto::sha256;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    const USER_AGENT: &str = "guard-test";
+
+    /// In-memory session store
+    #[derive(Clone, Default)]
+    struct MemorySessions(Arc<Mutex<HashMap<Uuid, PowSession>>>);
+
+    impl PowSessionRepository for MemorySessions {
+        async fn create(&self, pow_session: &PowSession) -> PowResult<()> {
+            let mut sessions = self.0.lock().unwrap();
+            sessions.insert(pow_session.id, pow_session.clone());
+            Ok(())
+        }
+
+        async fn get(
+            &self,
+            pow_session_id: Uuid,
+            fingerprint: &ClientFingerprint,
+        ) -> PowResult<Option<PowSession>> {
+            let sessions = self.0.lock().unwrap();
+            Ok(sessions
+                .get(&pow_session_id)
+                .filter(|s| s.client_fingerprint_hash == fingerprint.hash_vec())
+                .cloned())
+        }
+
+        async fn spend_request(
+            &self,
+            pow_session_id: Uuid,
+            fingerprint: &ClientFingerprint,
+        ) -> PowResult<Option<PowSession>> {
+            let mut sessions = self.0.lock().unwrap();
+            let Some(session) = sessions.get_mut(&pow_session_id).filter(|s| {
+                s.is_general()
+                    && !s.is_exhausted()
+                    && s.client_fingerprint_hash == fingerprint.hash_vec()
+            }) else {
+                return Ok(None);
+            };
+            if let Some(remaining) = session.remaining_requests.as_mut() {
+                *remaining -= 1;
+            }
+            Ok(Some(session.clone()))
+        }
+
+        async fn renew(
+            &self,
+            pow_session_id: Uuid,
+            fingerprint: &ClientFingerprint,
+            renewed: &PowSession,
+        ) -> PowResult<Option<PowSession>> {
+            let mut sessions = self.0.lock().unwrap();
+            let Some(session) = sessions.get_mut(&pow_session_id).filter(|s| {
+                s.is_general()
+                    && !s.is_expired()
+                    && s.client_fingerprint_hash == fingerprint.hash_vec()
+            }) else {
+                return Ok(None);
+            };
+            session.expires_at_ms = renewed.expires_at_ms;
+            session.challenge_id = renewed.challenge_id;
+            session.remaining_requests = renewed.remaining_requests;
+            session.difficulty_bits = renewed.difficulty_bits;
+            session.puzzle_count = renewed.puzzle_count;
+            Ok(Some(session.clone()))
+        }
+
+        async fn consume(
+            &self,
+            pow_session_id: Uuid,
+            fingerprint: &ClientFingerprint,
+            action: &PowAction,
+        ) -> PowResult<Option<PowSession>> {
+            let mut sessions = self.0.lock().unwrap();
+            let valid = sessions.get(&pow_session_id).is_some_and(|s| {
+                s.action.as_ref() == Some(action)
+                    && s.client_fingerprint_hash == fingerprint.hash_vec()
+            });
+            Ok(valid.then(|| sessions.remove(&pow_session_id)).flatten())
+        }
+
+        async fn delete(&self, pow_session_id: Uuid) -> PowResult<()> {
+            self.0.lock().unwrap().remove(&pow_session_id);
+            Ok(())
+        }
+    }
+
+    fn config() -> Arc<PowConfig> {
+        Arc::new(PowConfig::development())
+    }
+
+    /// Store a session solved by the test client and return its signed token
+    fn session_token(
+        repo: &MemorySessions,
+        config: &PowConfig,
+        action: Option<&str>,
+        budget: Option<u32>,
+    ) -> String {
+        let mut challenge = Challenge::new(
+            vec![0u8; 32],
+            PowAlgorithm::Sha256,
+            1,
+            60_000,
+            sha256(USER_AGENT.as_bytes()).to_vec(),
+            None,
+        );
+        if let Some(action) = action {
+            challenge = challenge.with_action(PowAction::new(action).unwrap(), None);
+        }
+        let session = PowSession::new(&challenge, 60_000).with_request_budget(budget);
+        repo.0.lock().unwrap().insert(session.id, session.clone());
+        config.session_keys.sign(
+            session.id,
+            session.created_at.timestamp_millis(),
+            session.expires_at_ms,
+        )
+    }
+
+    fn app(layer: PowGuardLayer<MemorySessions>) -> Router {
+        Router::new()
+            .route(
+                "/protected",
+                get(|| async { "ok" }).options(|| async { "preflight" }),
+            )
+            .route_layer(layer)
+            .route("/public", get(|| async { "ok" }))
+    }
+
+    fn request(method: Method, uri: &str) -> axum::http::request::Builder {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::USER_AGENT, USER_AGENT)
+    }
+
+    async fn send(app: &Router, req: Request<Body>) -> axum::response::Response {
+        app.clone().oneshot(req).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_unprotected_route_passes() {
+        let app = app(PowGuardLayer::new(
+            Arc::new(MemorySessions::default()),
+            config(),
+        ));
+
+        let response = send(
+            &app,
+            request(Method::GET, "/public").body(Body::empty()).unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_protected_route_requires_session() {
+        let config = config();
+        let repo = MemorySessions::default();
+        let app = app(PowGuardLayer::new(Arc::new(repo.clone()), config.clone()));
+
+        let response = send(
+            &app,
+            request(Method::GET, "/protected")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()["x-pow-required"], "true");
+        assert_eq!(
+            response.headers()["x-pow-challenge"],
+            config.challenge_url.as_str()
+        );
+        assert!(response.headers().get(header::CONTENT_TYPE).is_none());
+
+        let token = session_token(&repo, &config, None, Some(2));
+        let cookie = format!("{}={token}", config.session_cookie_name);
+        let response = send(
+            &app,
+            request(Method::GET, "/protected")
+                .header(header::COOKIE, &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-pow-remaining"], "1");
+    }
+
+    #[tokio::test]
+    async fn test_exempt_methods() {
+        let repo = Arc::new(MemorySessions::default());
+
+        // OPTIONS is exempt by default
+        let default = app(PowGuardLayer::new(repo.clone(), config()));
+        let response = send(
+            &default,
+            request(Method::OPTIONS, "/protected")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let strict = app(PowGuardLayer::new(repo, config()).exempt_methods([]));
+        let response = send(
+            &strict,
+            request(Method::OPTIONS, "/protected")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_json_errors() {
+        let app =
+            app(PowGuardLayer::new(Arc::new(MemorySessions::default()), config()).json_errors());
+
+        let response = send(
+            &app,
+            request(Method::GET, "/protected")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()["x-pow-required"], "true");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], 401);
+        assert_eq!(json["detail"], "Proof of work required");
+    }
+
+    #[tokio::test]
+    async fn test_action_scope() {
+        let config = config();
+        let repo = MemorySessions::default();
+        let action = PowAction::new("export").unwrap();
+        let app = app(PowGuardLayer::new(Arc::new(repo.clone()), config.clone()).action(action));
+
+        let response = send(
+            &app,
+            request(Method::GET, "/protected")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()["x-pow-action"], "export");
+
+        // A general session is not enough without or_session()
+        let token = session_token(&repo, &config, None, None);
+        let cookie = format!("{}={token}", config.session_cookie_name);
+        let response = send(
+            &app,
+            request(Method::GET, "/protected")
+                .header(header::COOKIE, &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // A proof for the action is accepted exactly once
+        let proof = session_token(&repo, &config, Some("export"), None);
+        let with_proof = || {
+            request(Method::GET, "/protected")
+                .header("x-pow-proof", &proof)
+                .body(Body::empty())
+                .unwrap()
+        };
+        assert_eq!(send(&app, with_proof()).await.status(), StatusCode::OK);
+        assert_eq!(
+            send(&app, with_proof()).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    /// Log lines written while the returned guard is alive
+    fn capture_logs() -> (Arc<Mutex<Vec<u8>>>, tracing::subscriber::DefaultGuard) {
+        let logs = Arc::new(Mutex::new(Vec::new()));
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(move || LogWriter(writer.clone()))
+            .finish();
+        (logs, tracing::subscriber::set_default(subscriber))
+    }
+
+    struct LogWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bypass_passes_and_is_logged() {
+        let mut config = PowConfig::development();
+        config.bypass.networks = vec!["10.0.0.0/8".parse().unwrap()];
+        config.bypass.token_keys = Some(SessionKeyRing::new(SessionKey::new(1, [9u8; 32])));
+        let credential_id = Uuid::new_v4();
+        let token = config
+            .bypass
+            .issue_token(credential_id, Duration::from_secs(60))
+            .unwrap();
+        let app = app(PowGuardLayer::new(
+            Arc::new(MemorySessions::default()),
+            Arc::new(config),
+        ));
+        let (logs, _guard) = capture_logs();
+
+        let mut req = request(Method::GET, "/protected")
+            .body(Body::empty())
+            .unwrap();
+        req.extensions_mut()
+            .insert(ClientIp(Some("10.1.2.3".parse().unwrap())));
+        assert_eq!(send(&app, req).await.status(), StatusCode::OK);
+
+        let req = request(Method::GET, "/protected")
+            .header("x-pow-bypass", &token)
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(send(&app, req).await.status(), StatusCode::OK);
+
+        // Other clients still need a session
+        let mut req = request(Method::GET, "/protected")
+            .body(Body::empty())
+            .unwrap();
+        req.extensions_mut()
+            .insert(ClientIp(Some("192.0.2.1".parse().unwrap())));
+        assert_eq!(send(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+        let logs = String::from_utf8(logs.lock().unwrap().clone()).unwrap();
+        let bypassed: Vec<&str> = logs
+            .lines()
+            .filter(|l| l.contains("PoW session requirement bypassed"))
+            .collect();
+        assert_eq!(bypassed.len(), 2, "{logs}");
+        assert!(bypassed[0].contains("reason=\"network\""), "{logs}");
+        assert!(bypassed[0].contains("detail=network 10.0.0.0/8"), "{logs}");
+        assert!(bypassed[1].contains("reason=\"token\""), "{logs}");
+        assert!(
+            bypassed[1].contains(&format!("detail=token {credential_id}")),
+            "{logs}"
+        );
+    }
+}
@@ -11,7 +11,7 @@ use uuid::Uuid;
 /// Usage:
 /// ```
 /// use kernel::id::{Id, markers};
-/// type UserId = Id<markers::User>;
+/// type ChallengeId = Id<markers::Challenge>;
 /// ```
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Id<T> {
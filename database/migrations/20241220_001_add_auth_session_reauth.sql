@@ -0,0 +1,17 @@
+-- Step-up Re-authentication Migration
+-- Track when a session last proved possession of a credential ("sudo mode")
+-- ============================================================================
+-- Auth Sessions: last_reauthenticated_at
+-- ============================================================================
+ALTER TABLE auth_sessions
+    ADD COLUMN IF NOT EXISTS last_reauthenticated_at TIMESTAMPTZ;
+
+-- Existing sessions were created by a full sign-in
+UPDATE
+    auth_sessions
+SET
+    last_reauthenticated_at = created_at
+WHERE
+    last_reauthenticated_at IS NULL;
+
+COMMENT ON COLUMN auth_sessions.last_reauthenticated_at IS 'Last password/TOTP verification for step-up checks on sensitive operations';
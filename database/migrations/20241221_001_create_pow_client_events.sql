@@ -0,0 +1,32 @@
+-- PoW Client Activity Log
+-- 難易度ポリシーの入力（不正 nonce・セッション乱発）を記録する
+-- 発行数はレート制限のカウンタ（rate_limit_buckets）から得るため、challenge 発行ごとの行は記録しない
+
+CREATE TABLE IF NOT EXISTS pow_client_events (
+    -- 連番
+    pow_client_event_id BIGSERIAL PRIMARY KEY,
+
+    -- イベント種別
+    event_kind TEXT NOT NULL CHECK (
+        event_kind IN ('invalid_nonce', 'session_created')
+    ),
+
+    -- クライアント識別 hash（User-Agent hash とネットワークプレフィックスから算出、ClientNetworkKey::client_hash）
+    client_fingerprint_hash BYTEA NOT NULL CHECK (octet_length(client_fingerprint_hash) = 32),
+
+    -- クライアント IP のネットワークプレフィックス（IPv4 /24, IPv6 /48）
+    client_network CIDR,
+
+    -- 発生日時（UNIX timestamp ms）
+    created_at_ms BIGINT NOT NULL
+);
+
+-- 古いイベント削除用インデックス
+CREATE INDEX IF NOT EXISTS idx_pow_client_events_created_at
+    ON pow_client_events (created_at_ms);
+
+-- クライアント単位の集計用インデックス
+CREATE INDEX IF NOT EXISTS idx_pow_client_events_fingerprint
+    ON pow_client_events (client_fingerprint_hash, created_at_ms);
+
+COMMENT ON TABLE pow_client_events IS 'Recent PoW client activity used as input for the adaptive difficulty policy.';
@@ -0,0 +1,23 @@
+-- PoW アルゴリズムの選択
+-- challenge ごとにアルゴリズム ID とパラメータを保存する（既定は sha256）
+
+ALTER TABLE pow_challenges
+    -- アルゴリズム ID
+    ADD COLUMN IF NOT EXISTS pow_algorithm TEXT NOT NULL DEFAULT 'sha256'
+        CHECK (pow_algorithm IN ('sha256', 'argon2id')),
+
+    -- Argon2id パラメータ（argon2id の場合のみ）
+    ADD COLUMN IF NOT EXISTS argon2_memory_kib INTEGER CHECK (argon2_memory_kib > 0),
+    ADD COLUMN IF NOT EXISTS argon2_iterations INTEGER CHECK (argon2_iterations > 0),
+    ADD COLUMN IF NOT EXISTS argon2_parallelism INTEGER CHECK (argon2_parallelism > 0);
+
+-- argon2id は全パラメータ必須
+ALTER TABLE pow_challenges
+    ADD CONSTRAINT pow_challenges_argon2_params_check CHECK (
+        pow_algorithm <> 'argon2id'
+        OR (
+            argon2_memory_kib IS NOT NULL
+            AND argon2_iterations IS NOT NULL
+            AND argon2_parallelism IS NOT NULL
+        )
+    );
@@ -0,0 +1,8 @@
+-- マルチパズル challenge
+-- 1 つの challenge を k 個の独立したサブパズルに分割し、解答時間のばらつきを抑える
+-- pow_difficulty_bits はサブパズル 1 つあたりの難易度
+
+ALTER TABLE pow_challenges
+    -- サブパズル数（1 = 従来の単一 nonce）
+    ADD COLUMN IF NOT EXISTS pow_puzzle_count SMALLINT NOT NULL DEFAULT 1
+        CHECK (pow_puzzle_count >= 1 AND pow_puzzle_count <= 64);
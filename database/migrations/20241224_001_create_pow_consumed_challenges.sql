@@ -0,0 +1,17 @@
+-- PoW Consumed Challenges
+-- ステートレスモードで解答済みの challenge ID を記録し、二重使用を防ぐ
+-- challenge 本体は署名付きトークンとしてクライアントが保持する
+
+CREATE TABLE IF NOT EXISTS pow_consumed_challenges (
+    -- 解答済み challenge ID（トークン内の ID）
+    pow_challenge_id UUID PRIMARY KEY,
+
+    -- challenge の有効期限（UNIX timestamp ms）。これ以降は行を削除してよい
+    expires_at_ms BIGINT NOT NULL
+);
+
+-- 期限切れ行の削除用インデックス
+CREATE INDEX IF NOT EXISTS idx_pow_consumed_challenges_expires_at
+    ON pow_consumed_challenges (expires_at_ms);
+
+COMMENT ON TABLE pow_consumed_challenges IS 'Short-lived set of solved stateless PoW challenges, kept until expiry to prevent double-spending.';
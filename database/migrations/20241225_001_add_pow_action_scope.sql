@@ -0,0 +1,20 @@
+-- アクション単位の PoW
+-- challenge を特定アクション（signup, signin, comment:create など）に限定し、
+-- 任意でリクエスト本文の SHA-256 に紐付ける
+-- 解答後はアクション用の使い捨て証明（pow_sessions の 1 行）になる
+
+ALTER TABLE pow_challenges
+    -- 対象アクション（NULL = 通常のセッション）
+    ADD COLUMN IF NOT EXISTS pow_action TEXT
+        CHECK (pow_action IS NULL OR octet_length(pow_action) BETWEEN 1 AND 64),
+    -- リクエスト本文の SHA-256（NULL = 本文に紐付けない）
+    ADD COLUMN IF NOT EXISTS pow_payload_hash BYTEA
+        CHECK (pow_payload_hash IS NULL OR octet_length(pow_payload_hash) = 32);
+
+ALTER TABLE pow_sessions
+    -- 証明の対象アクション（NULL = 通常のセッション）
+    ADD COLUMN IF NOT EXISTS pow_action TEXT
+        CHECK (pow_action IS NULL OR octet_length(pow_action) BETWEEN 1 AND 64),
+    -- 証明が紐付くリクエスト本文の SHA-256
+    ADD COLUMN IF NOT EXISTS pow_payload_hash BYTEA
+        CHECK (pow_payload_hash IS NULL OR octet_length(pow_payload_hash) = 32);
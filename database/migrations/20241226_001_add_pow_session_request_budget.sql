@@ -0,0 +1,8 @@
+-- PoW セッションのリクエスト予算
+-- require_pow_session を通過するたびに 1 減らし、0 になったら再度 PoW を要求する
+-- 1 回解いた challenge で大量アクセス（スクレイピング）されるのを防ぐ
+
+ALTER TABLE pow_sessions
+    -- 残りリクエスト数（NULL = TTL 内は無制限）
+    ADD COLUMN IF NOT EXISTS remaining_requests INTEGER
+        CHECK (remaining_requests IS NULL OR remaining_requests >= 0);
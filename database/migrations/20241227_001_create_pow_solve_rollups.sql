@@ -0,0 +1,50 @@
+-- PoW Solve Telemetry Rollup
+-- 難易度調整用に、解答時間を 1 時間単位・対数バケットで集計する
+
+CREATE TABLE IF NOT EXISTS pow_solve_rollups (
+    -- 集計期間の開始（UNIX timestamp ms、1 時間単位）
+    period_start_ms BIGINT NOT NULL,
+
+    -- アルゴリズム識別子（sha256 / argon2id）
+    pow_algorithm TEXT NOT NULL,
+
+    -- 実効難易度（難易度 + log2(パズル数)）
+    pow_work_bits SMALLINT NOT NULL CHECK (pow_work_bits BETWEEN 1 AND 64),
+
+    -- デバイス種別（User-Agent から推定）
+    device_class TEXT NOT NULL CHECK (
+        device_class IN ('desktop', 'mobile', 'bot', 'unknown')
+    ),
+
+    -- ブラウザ系統（User-Agent から推定）
+    ua_family TEXT NOT NULL,
+
+    -- 解答時間（発行〜提出、サーバー計測）の対数バケット（2 倍ごとに 4 バケット）
+    elapsed_bucket SMALLINT NOT NULL,
+
+    -- 件数
+    samples BIGINT NOT NULL DEFAULT 0,
+
+    -- サーバー計測の解答時間合計（ms）
+    server_elapsed_ms_sum BIGINT NOT NULL DEFAULT 0,
+
+    -- クライアント申告値が揃っていた件数（検証されない参考値）
+    client_samples BIGINT NOT NULL DEFAULT 0,
+
+    -- クライアント申告の解答時間合計（ms）
+    client_elapsed_ms_sum BIGINT NOT NULL DEFAULT 0,
+
+    -- クライアント申告のハッシュ回数合計
+    client_hashes_sum BIGINT NOT NULL DEFAULT 0,
+
+    PRIMARY KEY (
+        period_start_ms,
+        pow_algorithm,
+        pow_work_bits,
+        device_class,
+        ua_family,
+        elapsed_bucket
+    )
+);
+
+COMMENT ON TABLE pow_solve_rollups IS 'Hourly histogram of PoW solve times per work, device class and UA family, used to tune difficulty.';
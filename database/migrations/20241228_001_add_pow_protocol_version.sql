@@ -0,0 +1,8 @@
+-- PoW ワイヤプロトコルのバージョン
+-- nonce のエンコーディングを決める（1 = 32-bit nonce, 2 = 64-bit nonce）
+-- 移行期間中は v1 クライアントも受け付けるため、challenge ごとに保持する
+
+ALTER TABLE pow_challenges
+    -- プロトコルバージョン（1 = 従来の 4 バイト nonce）
+    ADD COLUMN IF NOT EXISTS pow_protocol_version SMALLINT NOT NULL DEFAULT 1
+        CHECK (pow_protocol_version IN (1, 2));
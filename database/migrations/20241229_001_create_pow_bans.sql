@@ -0,0 +1,84 @@
+-- PoW Ban List
+-- 不正 nonce・challenge 乱発・fingerprint 不一致を違反として記録し、
+-- しきい値を超えた fingerprint / ネットワークプレフィックスを一時的に ban する
+
+CREATE TABLE IF NOT EXISTS pow_offenses (
+    -- 連番
+    pow_offense_id BIGSERIAL PRIMARY KEY,
+
+    -- 違反種別
+    offense_kind TEXT NOT NULL CHECK (
+        offense_kind IN ('invalid_nonce', 'challenge_flood', 'fingerprint_mismatch')
+    ),
+
+    -- クライアント識別 hash（User-Agent hash とネットワークプレフィックスから算出、ClientNetworkKey::client_hash）
+    client_fingerprint_hash BYTEA NOT NULL CHECK (octet_length(client_fingerprint_hash) = 32),
+
+    -- クライアント IP のネットワークプレフィックス（IPv4 /24, IPv6 /48）
+    client_network CIDR,
+
+    -- 発生日時（UNIX timestamp ms）
+    created_at_ms BIGINT NOT NULL
+);
+
+-- fingerprint 単位の集計用インデックス
+CREATE INDEX IF NOT EXISTS idx_pow_offenses_fingerprint
+    ON pow_offenses (client_fingerprint_hash, created_at_ms);
+
+-- ネットワークプレフィックス単位の集計用インデックス
+CREATE INDEX IF NOT EXISTS idx_pow_offenses_network
+    ON pow_offenses (client_network, created_at_ms);
+
+-- 古い違反の削除用インデックス
+CREATE INDEX IF NOT EXISTS idx_pow_offenses_created_at
+    ON pow_offenses (created_at_ms);
+
+COMMENT ON TABLE pow_offenses IS 'Recent PoW offenses counted towards temporary bans.';
+
+CREATE TABLE IF NOT EXISTS pow_bans (
+    -- ban ID
+    pow_ban_id UUID PRIMARY KEY,
+
+    -- 対象の種別（fingerprint / network）
+    subject_kind TEXT NOT NULL CHECK (subject_kind IN ('fingerprint', 'network')),
+
+    -- 対象クライアントの識別 hash（subject_kind = 'fingerprint'、ClientNetworkKey::client_hash）
+    client_fingerprint_hash BYTEA CHECK (octet_length(client_fingerprint_hash) = 32),
+
+    -- 対象ネットワークプレフィックス（subject_kind = 'network'）
+    client_network CIDR,
+
+    -- しきい値を超えた違反種別
+    reason TEXT NOT NULL,
+
+    -- ban 時点のウィンドウ内違反数
+    offense_count INTEGER NOT NULL,
+
+    -- 同じ対象に対する何回目の ban か（期間の段階的な延長に使う）
+    strike INTEGER NOT NULL CHECK (strike >= 1),
+
+    -- 作成日時・期限（UNIX timestamp ms）
+    created_at_ms BIGINT NOT NULL,
+    expires_at_ms BIGINT NOT NULL,
+
+    -- 管理者による解除日時（UNIX timestamp ms）
+    lifted_at_ms BIGINT,
+
+    CHECK (
+        (subject_kind = 'fingerprint' AND client_fingerprint_hash IS NOT NULL)
+        OR (subject_kind = 'network' AND client_network IS NOT NULL)
+    )
+);
+
+-- 有効な ban の検索用インデックス
+CREATE INDEX IF NOT EXISTS idx_pow_bans_fingerprint
+    ON pow_bans (client_fingerprint_hash, expires_at_ms);
+
+CREATE INDEX IF NOT EXISTS idx_pow_bans_network
+    ON pow_bans (client_network, expires_at_ms);
+
+-- 古い ban の削除・一覧用インデックス
+CREATE INDEX IF NOT EXISTS idx_pow_bans_created_at
+    ON pow_bans (created_at_ms);
+
+COMMENT ON TABLE pow_bans IS 'Temporary bans of abusive PoW clients; kept past expiry to escalate repeat bans.';
@@ -0,0 +1,11 @@
+-- PoW セッションの獲得難易度
+-- /api/pow/status で返すため、最後に解いた challenge の難易度をセッションに保持する
+-- 有効期限内に再度 challenge を解くと、新しい行を作らず既存セッションを延長（更新）する
+
+ALTER TABLE pow_sessions
+    -- サブパズル 1 つあたりの難易度（0 = 不明、このマイグレーション以前のセッション）
+    ADD COLUMN IF NOT EXISTS pow_difficulty_bits SMALLINT NOT NULL DEFAULT 0
+        CHECK (pow_difficulty_bits BETWEEN 0 AND 255),
+    -- サブパズル数
+    ADD COLUMN IF NOT EXISTS pow_puzzle_count SMALLINT NOT NULL DEFAULT 1
+        CHECK (pow_puzzle_count BETWEEN 1 AND 255);
@@ -0,0 +1,35 @@
+-- Rate Limit Buckets
+-- GCRA（generic cell rate algorithm）によるレート制限の状態
+-- キーごとに「理論到着時刻」（TAT）だけを保持する
+-- 固定ウィンドウの pow_rate_limits（境界で 2 倍のバーストを許していた）を置き換える
+
+CREATE TABLE IF NOT EXISTS rate_limit_buckets (
+    -- 制限対象のキー（"pow:challenge:<fingerprint>" など、制限ごとに名前空間を分ける）
+    bucket_key TEXT PRIMARY KEY,
+
+    -- 理論到着時刻（UNIX timestamp ms）
+    -- これが現在時刻より過去なら、バーストは全て回復している
+    tat_ms BIGINT NOT NULL
+);
+
+-- 回復済みバケットの削除用インデックス
+CREATE INDEX IF NOT EXISTS idx_rate_limit_buckets_tat
+    ON rate_limit_buckets (tat_ms);
+
+COMMENT ON TABLE rate_limit_buckets IS 'GCRA rate limit state (theoretical arrival time per key).';
+
+-- 固定ウィンドウのレート制限は使われなくなった
+CREATE OR REPLACE FUNCTION cleanup_expired_pow_data() RETURNS void AS $$
+BEGIN
+    -- 期限切れ challenge を削除
+    DELETE FROM pow_challenges WHERE expires_at_ms < (EXTRACT(EPOCH FROM now()) * 1000)::BIGINT;
+
+    -- 期限切れ session を削除
+    DELETE FROM pow_sessions WHERE expires_at_ms < (EXTRACT(EPOCH FROM now()) * 1000)::BIGINT;
+
+    -- 回復済みのレート制限バケットを削除
+    DELETE FROM rate_limit_buckets WHERE tat_ms < (EXTRACT(EPOCH FROM now()) * 1000)::BIGINT;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TABLE IF EXISTS pow_rate_limits;
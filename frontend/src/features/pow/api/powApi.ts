@@ -1,9 +1,41 @@
 // frontend/src/features/pow/api/powApi.ts
 
-import type { PowChallenge, PowSubmit } from "./types";
+import type {
+  PowAlgorithm,
+  PowChallenge,
+  PowProof,
+  PowScope,
+  PowStatus,
+  PowSubmit,
+} from "./types";
 
 const API_BASE = import.meta.env.VITE_API_BASE_URL ?? "";
 
+/**
+ * CSRF token (double-submit)
+ * Backend が全レスポンスの X-CSRF-Token ヘッダで返す値を保持し、
+ * POST 時に同じヘッダで送り返す
+ */
+let csrfToken: string | null = null;
+
+function rememberCsrfToken(res: Response): void {
+  const token = res.headers.get("X-CSRF-Token");
+  if (token) {
+    csrfToken = token;
+  }
+}
+
+async function ensureCsrfToken(): Promise<string> {
+  if (!csrfToken) {
+    const res = await fetch(`${API_BASE}/api/pow/status`, {
+      method: "GET",
+      credentials: "include",
+    });
+    rememberCsrfToken(res);
+  }
+  return csrfToken ?? "";
+}
+
 /**
  * PoW API - Backend 呼び出し
  *
@@ -16,15 +48,25 @@ export const powApi = {
   /**
    * GET /api/pow/challenge
    * サーバから新しい challenge を取得
+   * scope を指定するとアクション用の使い捨て証明になる
    */
-  async issue(): Promise<PowChallenge> {
-    const res = await fetch(`${API_BASE}/api/pow/challenge`, {
+  async issue(scope?: PowScope): Promise<PowChallenge> {
+    const params = new URLSearchParams();
+    if (scope) {
+      params.set("action", scope.action);
+      if (scope.payloadHash) {
+        params.set("payloadHash", scope.payloadHash);
+      }
+    }
+    const query = params.size > 0 ? `?${params}` : "";
+    const res = await fetch(`${API_BASE}/api/pow/challenge${query}`, {
       method: "GET",
       credentials: "include", // cookie を送受信
       headers: {
         "Content-Type": "application/json",
       },
     });
+    rememberCsrfToken(res);
 
     if (res.status === 429) {
       throw new PowApiError("rate_limit", "Rate limit exceeded");
@@ -36,31 +78,53 @@ export const powApi = {
 
     const data = await res.json();
 
+    // Worker が計算できるのは sha256 と argon2id（パラメータ必須）
+    const algorithm: PowAlgorithm = data.powAlgorithm ?? "sha256";
+    if (algorithm !== "sha256" && algorithm !== "argon2id") {
+      throw new PowApiError("unsupported", `Unsupported PoW algorithm: ${algorithm}`);
+    }
+    if (algorithm === "argon2id" && !data.powAlgorithmParams) {
+      throw new PowApiError("unsupported", "Missing Argon2id parameters");
+    }
+
     return {
       id: data.powChallengeId,
       challengeB64: data.powChallengeB64,
+      algorithm,
+      algorithmParams: data.powAlgorithmParams,
       difficultyBits: data.powDifficultyBits,
+      puzzleCount: data.powPuzzleCount ?? 1,
       expiresAtMs: data.powExpiresAtMs,
+      action: data.powAction,
+      challengeToken: data.powChallengeToken,
     };
   },
 
   /**
    * POST /api/pow/submit
    * nonce を送信して検証
+   * 有効な session cookie があれば新しい session ではなく既存の session が延長される
    *
-   * @returns void on success (204)
+   * @returns null on success (204), or the proof for action-scoped challenges (200)
    * @throws PowApiError with code "invalid_nonce" (409), "expired" (410), "rate_limit" (429)
    */
-  async submit(payload: PowSubmit): Promise<void> {
+  async submit(payload: PowSubmit): Promise<PowProof | null> {
+    const token = await ensureCsrfToken();
     const res = await fetch(`${API_BASE}/api/pow/submit`, {
       method: "POST",
       credentials: "include",
       headers: {
         "Content-Type": "application/json",
+        "X-CSRF-Token": token,
       },
       body: JSON.stringify({
         challengeId: payload.challengeId,
-        nonceU32: payload.nonceU32,
+        // ステートレスモードでは署名付き challenge が必須
+        ...(payload.challengeToken ? { challengeToken: payload.challengeToken } : {}),
+        // 単一パズルは従来の nonceU32、複数は noncesU32
+        ...(payload.nonces.length === 1
+          ? { nonceU32: payload.nonces[0] }
+          : { noncesU32: payload.nonces }),
         // Telemetry only (server does not use for verification)
         elapsedMs: payload.elapsedMs,
         totalHashes: payload.totalHashes,
@@ -69,7 +133,17 @@ export const powApi = {
 
     if (res.status === 204) {
       // Success - session cookie has been set
-      return;
+      return null;
+    }
+
+    if (res.status === 200) {
+      // アクション用の証明（cookie は変更されない）
+      const data = await res.json();
+      return {
+        proof: data.powProof,
+        action: data.powAction,
+        expiresAtMs: data.powExpiresAtMs,
+      };
     }
 
     if (res.status === 409) {
@@ -89,9 +163,9 @@ export const powApi = {
 
   /**
    * GET /api/pow/status
-   * Session の有効性を確認
+   * Session の状態（期限・残り予算・難易度）を取得
    */
-  async checkStatus(): Promise<boolean> {
+  async status(): Promise<PowStatus> {
     const res = await fetch(`${API_BASE}/api/pow/status`, {
       method: "GET",
       credentials: "include",
@@ -99,13 +173,30 @@ export const powApi = {
         "Content-Type": "application/json",
       },
     });
+    rememberCsrfToken(res);
 
     if (!res.ok) {
-      return false;
+      return { passed: false };
     }
 
     const data = await res.json();
-    return data.passed === true;
+    return {
+      passed: data.passed === true,
+      remainingRequests: data.remainingRequests,
+      expiresAtMs: data.expiresAtMs,
+      remainingTtlMs: data.remainingTtlMs,
+      difficultyBits: data.difficultyBits,
+      puzzleCount: data.puzzleCount,
+    };
+  },
+
+  /**
+   * GET /api/pow/status
+   * Session の有効性を確認
+   */
+  async checkStatus(): Promise<boolean> {
+    const status = await powApi.status();
+    return status.passed;
   },
 
   /**
@@ -113,14 +204,23 @@ export const powApi = {
    * Session を破棄（?pow=reset 用）
    */
   async logout(): Promise<void> {
+    const token = await ensureCsrfToken();
     await fetch(`${API_BASE}/api/pow/logout`, {
       method: "POST",
       credentials: "include",
+      headers: {
+        "X-CSRF-Token": token,
+      },
     });
   },
 };
 
-export type PowApiErrorCode = "invalid_nonce" | "expired" | "rate_limit" | "network";
+export type PowApiErrorCode =
+  | "invalid_nonce"
+  | "expired"
+  | "rate_limit"
+  | "unsupported"
+  | "network";
 
 export class PowApiError extends Error {
   public code: PowApiErrorCode;
@@ -131,3 +231,45 @@ export class PowApiError extends Error {
     this.code = code;
   }
 }
+
+/**
+ * Backend の "PoW required" 応答を通知するイベント名
+ * PowGate が購読し、PoW をやり直す
+ */
+export const POW_REQUIRED_EVENT = "pow:required";
+
+export type PowRequiredDetail = {
+  action: string | null; // アクション単位のガードのみ
+  challengeUrl: string | null;
+};
+
+/**
+ * "PoW required" 応答（401 + X-PoW-Required: true）を検出して PowGate に通知
+ * 保護された API（/api/auth/signup, /api/auth/signin など）の呼び出し側で使う
+ *
+ * @returns PoW が必要な応答だった場合 true
+ */
+export function handlePowRequired(res: Response): boolean {
+  if (res.status !== 401 || res.headers.get("X-PoW-Required") !== "true") {
+    return false;
+  }
+  const detail: PowRequiredDetail = {
+    action: res.headers.get("X-PoW-Action"),
+    challengeUrl: res.headers.get("X-PoW-Challenge"),
+  };
+  window.dispatchEvent(new CustomEvent(POW_REQUIRED_EVENT, { detail }));
+  return true;
+}
+
+/**
+ * リクエスト本文の SHA-256（base64url, padding なし）
+ * payload に紐付いた challenge を発行するときに使う
+ */
+export async function hashPayload(body: string): Promise<string> {
+  const digest = await crypto.subtle.digest("SHA-256", new TextEncoder().encode(body));
+  let binary = "";
+  for (const b of new Uint8Array(digest)) {
+    binary += String.fromCharCode(b);
+  }
+  return btoa(binary).replace(/\+/g, "-").replace(/\//g, "_").replace(/=+$/, "");
+}
@@ -1,15 +1,52 @@
 // frontend/src/features/pow/api/types.ts
 
+export type PowAlgorithm = "sha256" | "argon2id";
+
+export type PowAlgorithmParams = {
+  memoryKib: number;
+  iterations: number;
+  parallelism: number;
+};
+
 export type PowChallenge = {
   id: string;
   challengeB64: string; // bytes を base64 で
-  difficultyBits: number; // 先頭ゼロbit数（UI表示の「難易度N」に対応）
+  algorithm: PowAlgorithm; // 計算するハッシュ関数
+  algorithmParams?: PowAlgorithmParams; // argon2id のみ
+  difficultyBits: number; // サブパズル 1 つあたりの先頭ゼロbit数
+  puzzleCount: number; // サブパズル数（1 = 単一 nonce）
+  expiresAtMs: number;
+  action?: string; // アクション単位の challenge のみ
+  challengeToken?: string; // 署名付き challenge（ステートレスモードのみ）
+};
+
+// アクション単位の challenge（signup / signin / comment:create など）
+export type PowScope = {
+  action: string;
+  payloadHash?: string; // リクエスト本文の SHA-256（base64url, padding なし）
+};
+
+// アクション用の使い捨て証明（X-PoW-Proof ヘッダで送る）
+export type PowProof = {
+  proof: string;
+  action: string;
   expiresAtMs: number;
 };
 
 export type PowSubmit = {
   challengeId: string;
-  nonceU32: number;
+  challengeToken?: string; // challenge 応答の値をそのまま返す
+  nonces: number[]; // サブパズル順
   totalHashes: number;
   elapsedMs: number;
 };
+
+// GET /api/pow/status の応答（session が無い場合は passed のみ）
+export type PowStatus = {
+  passed: boolean;
+  remainingRequests?: number; // 予算付き session のみ
+  expiresAtMs?: number;
+  remainingTtlMs?: number; // 期限までの残り時間
+  difficultyBits?: number; // session を得たときのサブパズル難易度
+  puzzleCount?: number;
+};
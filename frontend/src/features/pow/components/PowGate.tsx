@@ -1,8 +1,9 @@
 // frontend/src/features/pow/components/PowGate.tsx
 
-import React, { useEffect, useMemo, useState } from "react";
-import { powApi } from "../api/powApi";
+import React, { useCallback, useEffect, useMemo, useState } from "react";
+import { POW_REQUIRED_EVENT, powApi } from "../api/powApi";
 import { usePow } from "../hooks/usePow";
+import { usePowSessionRefresh } from "../hooks/usePowSessionRefresh";
 import { PowOverlay, type PowOverlayVm } from "./PowOverlay";
 
 type Props = {
@@ -51,6 +52,16 @@ export function PowGate({ children }: Props) {
     })();
   }, [debug.force, debug.reset]);
 
+  // Backend rejected a request with "PoW required" (session expired / budget exhausted)
+  useEffect(() => {
+    const onRequired = () => setSessionValid(false);
+    window.addEventListener(POW_REQUIRED_EVENT, onRequired);
+    return () => window.removeEventListener(POW_REQUIRED_EVENT, onRequired);
+  }, []);
+
+  const onSuccess = useCallback(() => setSessionValid(true), []);
+  const onExpired = useCallback(() => setSessionValid(false), []);
+
   // Determine whether PoW is required (avoid issuing challenge before session check completes)
   const needsPow = sessionChecked ? !sessionValid || debug.force : false;
 
@@ -58,6 +69,13 @@ export function PowGate({ children }: Props) {
   const pow = usePow({
     enabled: needsPow,
     mode: debug.mode,
+    onSuccess,
+  });
+
+  // Renew the session in the background before it expires
+  usePowSessionRefresh({
+    enabled: sessionChecked && sessionValid && !debug.force && debug.mode === "normal",
+    onExpired,
   });
 
   // Show nothing until session check completes
@@ -74,8 +74,8 @@ export function usePow(options: UsePowOptions = {}) {
   const expectedHashes = useMemo(() => {
     if (vm.difficulty == null) return null;
     // UI上の「期待値」(実際のPoWは確率的。ここは表示用途)
-    return Math.pow(2, vm.difficulty);
-  }, [vm.difficulty]);
+    return Math.pow(2, vm.difficulty) * (challenge?.puzzleCount ?? 1);
+  }, [vm.difficulty, challenge]);
 
   // If disabled, ensure worker is stopped and state is not "running"
   useEffect(() => {
@@ -150,7 +150,10 @@ export function usePow(options: UsePowOptions = {}) {
     const stop = startPowWorker(
       {
         challengeB64: challenge.challengeB64,
+        algorithm: challenge.algorithm,
+        algorithmParams: challenge.algorithmParams,
         difficultyBits: challenge.difficultyBits,
+        puzzleCount: challenge.puzzleCount,
         mode: resolvedMode,
         simHashRate,
       },
@@ -182,12 +185,18 @@ export function usePow(options: UsePowOptions = {}) {
           return;
         }
 
+        if (m.kind === "error") {
+          setVm((p) => ({ ...p, phase: "error", statusText: m.message, errorCode: m.code }));
+          return;
+        }
+
         // found（mode=sim では来ない）
         setVm((p) => ({ ...p, phase: "submitting", statusText: "verifying..." }));
         try {
           await powApi.submit({
             challengeId: challenge.id,
-            nonceU32: m.nonceU32,
+            challengeToken: challenge.challengeToken,
+            nonces: m.nonces,
             totalHashes: m.totalHashes,
             elapsedMs: m.elapsedMs,
           });
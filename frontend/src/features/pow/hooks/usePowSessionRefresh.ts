@@ -0,0 +1,104 @@
+// frontend/src/features/pow/hooks/usePowSessionRefresh.ts
+
+import { useEffect } from "react";
+import { powApi } from "../api/powApi";
+import type { PowChallenge } from "../api/types";
+import { startPowWorker } from "../worker/workerClient";
+
+/** 期限のこれだけ前に更新を始める（上限） */
+const MAX_LEAD_MS = 60_000;
+
+/** 更新に失敗したときの再試行間隔 */
+const RETRY_MS = 30_000;
+
+export type UsePowSessionRefreshOptions = {
+  /** session が有効な間だけ true にする */
+  enabled: boolean;
+  /** session を更新できなかった（期限切れ・予算切れ）ときに呼ばれる */
+  onExpired?: () => void;
+};
+
+/** challenge をバックグラウンドで解く（overlay は出さない） */
+function solve(challenge: PowChallenge) {
+  type Found = { nonces: number[]; totalHashes: number; elapsedMs: number };
+  return new Promise<Found>((resolve, reject) => {
+    const stop = startPowWorker(
+      {
+        challengeB64: challenge.challengeB64,
+        algorithm: challenge.algorithm,
+        algorithmParams: challenge.algorithmParams,
+        difficultyBits: challenge.difficultyBits,
+        puzzleCount: challenge.puzzleCount,
+        mode: "normal",
+      },
+      (m) => {
+        if (m.kind === "progress") return;
+        stop();
+        if (m.kind === "error") {
+          reject(new Error(m.message));
+        } else {
+          resolve(m);
+        }
+      }
+    );
+  });
+}
+
+/**
+ * PoW session の先回り更新
+ *
+ * GET /api/pow/status の残り時間を見て、期限の少し前（残り時間の 1/5、最大 60 秒）に
+ * 新しい challenge を解いて submit する。cookie が送られるので Backend は既存の
+ * session を延長する（並行する session は作られない）。
+ */
+export function usePowSessionRefresh({ enabled, onExpired }: UsePowSessionRefreshOptions) {
+  useEffect(() => {
+    if (!enabled) return;
+
+    let cancelled = false;
+    let timer: ReturnType<typeof setTimeout> | undefined;
+
+    const schedule = (delayMs: number) => {
+      if (cancelled) return;
+      timer = setTimeout(() => void refresh(), Math.max(0, delayMs));
+    };
+
+    const scheduleFromStatus = async () => {
+      const status = await powApi.status();
+      if (cancelled) return;
+      if (!status.passed || status.remainingTtlMs == null) {
+        onExpired?.();
+        return;
+      }
+      const lead = Math.min(MAX_LEAD_MS, status.remainingTtlMs / 5);
+      schedule(status.remainingTtlMs - lead);
+    };
+
+    const refresh = async () => {
+      try {
+        const challenge = await powApi.issue();
+        if (cancelled) return;
+        const found = await solve(challenge);
+        if (cancelled) return;
+        await powApi.submit({
+          challengeId: challenge.id,
+          challengeToken: challenge.challengeToken,
+          nonces: found.nonces,
+          totalHashes: found.totalHashes,
+          elapsedMs: found.elapsedMs,
+        });
+        await scheduleFromStatus();
+      } catch {
+        // 一時的な失敗（rate limit など）は少し待って再試行
+        schedule(RETRY_MS);
+      }
+    };
+
+    scheduleFromStatus().catch(() => schedule(RETRY_MS));
+
+    return () => {
+      cancelled = true;
+      if (timer) clearTimeout(timer);
+    };
+  }, [enabled, onExpired]);
+}
@@ -2,11 +2,14 @@
 
 /// <reference lib="webworker" />
 
-export {}; // TSに「モジュール」として認識させる（型衝突回避）
+import type { PowAlgorithm, PowAlgorithmParams } from "../api/types";
 
 type StartMsg = {
   challengeB64: string;
-  difficultyBits: number;
+  algorithm?: PowAlgorithm; // 既定 sha256
+  algorithmParams?: PowAlgorithmParams; // argon2id のみ
+  difficultyBits: number; // サブパズル 1 つあたり
+  puzzleCount?: number; // 既定 1（単一 nonce）
   mode: "normal" | "sim";
   simHashRate?: number;
 };
@@ -20,12 +23,18 @@ type WorkerProgress = {
 
 type WorkerFound = {
   kind: "found";
-  nonceU32: number;
+  nonces: number[]; // サブパズル順
   totalHashes: number;
   elapsedMs: number;
 };
 
-type WorkerMsg = WorkerProgress | WorkerFound;
+type WorkerError = {
+  kind: "error";
+  code: "unsupported";
+  message: string;
+};
+
+type WorkerMsg = WorkerProgress | WorkerFound | WorkerError;
 
 const ctx: DedicatedWorkerGlobalScope = self as unknown as DedicatedWorkerGlobalScope;
 
@@ -184,8 +193,18 @@ function hasLeadingZeroBits(hash: Uint8Array, bits: number): boolean {
   return (hash[full] & mask) === 0;
 }
 
+/**
+ * サブパズル i の challenge bytes
+ * puzzleCount = 1 のときは challenge そのもの、それ以外は SHA-256(challenge || i_be)
+ */
+function subChallenge(challenge: Uint8Array, index: number, puzzleCount: number): Uint8Array {
+  if (puzzleCount <= 1) return challenge;
+  return sha256(concat(challenge, u32be(index)));
+}
+
 ctx.addEventListener("message", (e: MessageEvent<StartMsg>) => {
   const { challengeB64, difficultyBits, mode, simHashRate } = e.data;
+  const puzzleCount = Math.max(1, e.data.puzzleCount ?? 1);
 
   // --- sim: 低負荷で“終わらない”進捗だけ生成（画面確認用） ---
   if (mode === "sim") {
@@ -209,42 +228,166 @@ ctx.addEventListener("message", (e: MessageEvent<StartMsg>) => {
     return; // found を送らない => 永続表示
   }
 
-  // --- normal: 本物のPoW（見つかったら found を返す） ---
-  const challenge = fromB64(challengeB64);
+  // --- normal: 本物のPoW（全サブパズルが解けたら found を返す） ---
+  const base = fromB64(challengeB64);
+  const algorithm = e.data.algorithm ?? "sha256";
+  void loadWasm().then((wasm) => {
+    if (algorithm === "argon2id") {
+      // argon2id は wasm でのみ計算できる（TS 実装なし）
+      if (!wasm || !e.data.algorithmParams) {
+        post({
+          kind: "error",
+          code: "unsupported",
+          message: "Argon2id requires the WebAssembly solver",
+        });
+        return;
+      }
+      const params = e.data.algorithmParams;
+      let solver: WasmPowWorker;
+      try {
+        solver = wasm.PowWorker.argon2id(
+          base,
+          difficultyBits,
+          puzzleCount,
+          params.memoryKib,
+          params.iterations,
+          params.parallelism
+        );
+      } catch (err) {
+        post({ kind: "error", code: "unsupported", message: String(err) });
+        return;
+      }
+      solveWithWasm(solver, ARGON2ID_BATCH);
+      return;
+    }
 
-  const started = performance.now();
-  let total = 0;
-  let nonce = (Math.random() * 0xffffffff) >>> 0;
+    if (wasm) {
+      solveWithWasm(new wasm.PowWorker(base, difficultyBits, puzzleCount), BATCH);
+    } else {
+      solveWithTs(base, difficultyBits, puzzleCount);
+    }
+  });
+});
+
+// ---- WebAssembly ソルバー（backend/crates/pow-solver, `npm run build:wasm`） ----
+
+type WasmPowWorker = {
+  search(start: number, count: number): number | undefined;
+  readonly solved: boolean;
+  readonly nonces: Uint32Array;
+  readonly totalHashes: number;
+  free(): void;
+};
+
+type WasmModule = {
+  default: () => Promise<unknown>;
+  PowWorker: {
+    new (challenge: Uint8Array, difficultyBits: number, puzzleCount: number): WasmPowWorker;
+    argon2id(
+      challenge: Uint8Array,
+      difficultyBits: number,
+      puzzleCount: number,
+      memoryKib: number,
+      iterations: number,
+      parallelism: number
+    ): WasmPowWorker;
+  };
+};
+
+const BATCH = 50_000;
 
+// argon2id は 1 ハッシュが重いので、進捗を返せるよう小さなバッチで回す
+const ARGON2ID_BATCH = 8;
+
+/** wasm を読み込む。未ビルド・非対応環境では null（TS 実装にフォールバック） */
+async function loadWasm(): Promise<WasmModule | null> {
+  try {
+    const url = `${import.meta.env.BASE_URL}pow-wasm/pow_solver.js`;
+    const wasm = (await import(/* @vite-ignore */ url)) as WasmModule;
+    await wasm.default();
+    return wasm;
+  } catch {
+    return null;
+  }
+}
+
+/** 進捗の間引き（250ms ごと） */
+function progressReporter(started: number): (total: number) => void {
   let lastReportAt = started;
   let lastTotal = 0;
 
+  return (total) => {
+    const now = performance.now();
+    if (now - lastReportAt < 250) return;
+
+    const elapsedMs = Math.max(1, Math.round(now - started));
+    const deltaSec = (now - lastReportAt) / 1000;
+    const hashRate = deltaSec > 0 ? Math.round((total - lastTotal) / deltaSec) : 0;
+
+    post({ kind: "progress", totalHashes: total, elapsedMs, hashRate });
+
+    lastReportAt = now;
+    lastTotal = total;
+  };
+}
+
+/** サーバーと同じ Rust コードで探索（バッチ単位で進捗を返す） */
+function solveWithWasm(solver: WasmPowWorker, batch: number): void {
+  const started = performance.now();
+  const report = progressReporter(started);
+  let nonce = (Math.random() * 0xffffffff) >>> 0;
+
+  while (!solver.solved) {
+    if (solver.search(nonce, batch) === undefined) {
+      nonce = (nonce + batch) >>> 0;
+    } else {
+      nonce = (Math.random() * 0xffffffff) >>> 0;
+    }
+    report(solver.totalHashes);
+  }
+
+  const elapsedMs = Math.max(0, Math.round(performance.now() - started));
+  post({
+    kind: "found",
+    nonces: Array.from(solver.nonces),
+    totalHashes: solver.totalHashes,
+    elapsedMs,
+  });
+  solver.free();
+}
+
+/** TS 実装（wasm が使えない環境向け） */
+function solveWithTs(base: Uint8Array, difficultyBits: number, puzzleCount: number): void {
+  const started = performance.now();
+  const report = progressReporter(started);
+  let total = 0;
+  const nonces: number[] = [];
+  let challenge = subChallenge(base, 0, puzzleCount);
+  let nonce = (Math.random() * 0xffffffff) >>> 0;
+
   while (true) {
-    for (let i = 0; i < 50_000; i++) {
+    for (let i = 0; i < BATCH; i++) {
       const input = concat(challenge, u32be(nonce));
       const h = sha256(input);
       total++;
 
       if (hasLeadingZeroBits(h, difficultyBits)) {
-        const elapsedMs = Math.max(0, Math.round(performance.now() - started));
-        post({ kind: "found", nonceU32: nonce >>> 0, totalHashes: total, elapsedMs });
-        return;
+        nonces.push(nonce >>> 0);
+
+        if (nonces.length === puzzleCount) {
+          const elapsedMs = Math.max(0, Math.round(performance.now() - started));
+          post({ kind: "found", nonces, totalHashes: total, elapsedMs });
+          return;
+        }
+
+        challenge = subChallenge(base, nonces.length, puzzleCount);
+        nonce = (Math.random() * 0xffffffff) >>> 0;
+        continue;
       }
 
       nonce = (nonce + 1) >>> 0;
     }
 
-    const now = performance.now();
-    if (now - lastReportAt >= 250) {
-      const elapsedMs = Math.max(1, Math.round(now - started));
-      const deltaHashes = total - lastTotal;
-      const deltaSec = (now - lastReportAt) / 1000;
-      const hashRate = deltaSec > 0 ? Math.round(deltaHashes / deltaSec) : 0;
-
-      post({ kind: "progress", totalHashes: total, elapsedMs, hashRate });
-
-      lastReportAt = now;
-      lastTotal = total;
-    }
+    report(total);
   }
-});
+}
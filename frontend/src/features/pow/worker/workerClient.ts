@@ -1,5 +1,7 @@
 // frontend/src/features/pow/worker/workerClient.ts
 
+import type { PowAlgorithm, PowAlgorithmParams } from "../api/types";
+
 export type PowMode = "normal" | "sim";
 
 export type WorkerProgress = {
@@ -11,16 +13,26 @@ export type WorkerProgress = {
 
 export type WorkerFound = {
   kind: "found";
-  nonceU32: number;
+  nonces: number[]; // サブパズル順
   totalHashes: number;
   elapsedMs: number;
 };
 
-export type WorkerMsg = WorkerProgress | WorkerFound;
+// 計算できない challenge（argon2id で wasm が読み込めない場合など）
+export type WorkerError = {
+  kind: "error";
+  code: "unsupported";
+  message: string;
+};
+
+export type WorkerMsg = WorkerProgress | WorkerFound | WorkerError;
 
 export type WorkerStart = {
   challengeB64: string;
-  difficultyBits: number;
+  algorithm: PowAlgorithm;
+  algorithmParams?: PowAlgorithmParams; // argon2id のみ
+  difficultyBits: number; // サブパズル 1 つあたり
+  puzzleCount: number;
   mode: PowMode;
   simHashRate?: number; // mode=sim のときだけ使用（任意）
 };